@@ -0,0 +1,113 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use crate::hittable::{hit_solid, HitRecord, Hittable, HittableList};
+use crate::types::aabb::Aabb;
+use crate::types::interval::Interval;
+use crate::types::ray::Ray;
+
+/// Bounding volume hierarchy over a list of objects, so rays only test the
+/// objects whose boxes they pass through.
+pub struct BvhNode {
+    left: Arc<dyn Hittable + Sync + Send>,
+    right: Arc<dyn Hittable + Sync + Send>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> BvhNode {
+        let mut objects = list.objects;
+        BvhNode::from_objects(&mut objects)
+    }
+
+    fn from_objects(objects: &mut [Arc<dyn Hittable + Sync + Send>]) -> BvhNode {
+        let bbox = objects.iter().fold(Aabb::empty(), |bbox, object| {
+            Aabb::surrounding(&bbox, &object.bounding_box())
+        });
+
+        // Split along the longest axis of the node's box.
+        let axis = if bbox.x.size() > bbox.y.size() && bbox.x.size() > bbox.z.size() {
+            0
+        } else if bbox.y.size() > bbox.z.size() {
+            1
+        } else {
+            2
+        };
+
+        let (left, right): (
+            Arc<dyn Hittable + Sync + Send>,
+            Arc<dyn Hittable + Sync + Send>,
+        ) = match objects.len() {
+            0 => (Arc::new(HittableList::new()), Arc::new(HittableList::new())),
+            1 => (objects[0].clone(), objects[0].clone()),
+            2 => (objects[0].clone(), objects[1].clone()),
+            _ => {
+                objects.sort_by(|a, b| box_compare(a.as_ref(), b.as_ref(), axis));
+                let mid = objects.len() / 2;
+                let (lower, upper) = objects.split_at_mut(mid);
+                (
+                    Arc::new(BvhNode::from_objects(lower)),
+                    Arc::new(BvhNode::from_objects(upper)),
+                )
+            }
+        };
+
+        BvhNode { left, right, bbox }
+    }
+}
+
+fn box_compare(a: &dyn Hittable, b: &dyn Hittable, axis: usize) -> Ordering {
+    let a_min = a.bounding_box().axis(axis).min;
+    let b_min = b.bounding_box().axis(axis).min;
+    a_min.partial_cmp(&b_min).unwrap_or(Ordering::Equal)
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+
+        let hit_left = hit_solid(self.left.as_ref(), r, ray_t, rec);
+        let max = if hit_left { rec.t } else { ray_t.max };
+        let hit_right = hit_solid(self.right.as_ref(), r, &Interval::new(ray_t.min, max), rec);
+
+        hit_left || hit_right
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        if !self.bbox.hit(r, ray_t) {
+            return 1.0;
+        }
+        let left = self.left.transmittance(r, ray_t);
+        // A node holding a single object references it from both sides.
+        if left == 0.0 || Arc::ptr_eq(&self.left, &self.right) {
+            return left;
+        }
+        left * self.right.transmittance(r, ray_t)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn is_aggregate(&self) -> bool {
+        true
+    }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        // A node holding a single object references it from both sides.
+        let children = if Arc::ptr_eq(&self.left, &self.right) {
+            vec![&self.left]
+        } else {
+            vec![&self.left, &self.right]
+        };
+        for child in children {
+            if child.is_light() {
+                lights.add(child.clone());
+            } else {
+                child.collect_lights(lights);
+            }
+        }
+    }
+}
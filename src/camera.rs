@@ -1,29 +1,120 @@
-use crate::hittable::{self, Hittable, HittableList};
+use crate::fog::Fog;
+use crate::hittable::{self, Hittable, HittableList, RAY_EPSILON};
+use crate::light_sampler::{LightSampler, LightSelection};
+use crate::lights::light::Light;
+use crate::math::power_heuristic;
 use crate::random::{random_f64, random_f64_with_bounds};
+use crate::sky::Sky;
 use crate::types::interval;
 use crate::types::ray::Ray;
 use crate::types::render_task::RenderTask;
+use crate::types::spectrum::SampledWavelengths;
 use crate::types::vec3::Vec3;
 
 use crate::types::vec3::Vec3 as Point3;
 
 use crate::types::image::Image;
 
+/// How the shutter's transmission varies while it is open, which weights the
+/// ray times drawn within the shutter interval.
+#[derive(Clone, Copy)]
+pub enum ShutterCurve {
+    /// Fully open for the whole interval.
+    Box,
+    /// Opens linearly until the middle of the interval, then closes linearly.
+    Triangle,
+    /// Opens linearly over the first `open` fraction of the interval and
+    /// closes linearly over the last `close` fraction.
+    Trapezoid { open: f64, close: f64 },
+}
+
+impl ShutterCurve {
+    /// Maps a uniform `u` in [0,1) to a fraction of the shutter interval,
+    /// distributed like the curve.
+    pub fn sample(&self, u: f64) -> f64 {
+        let (a, b) = match *self {
+            ShutterCurve::Box => return u,
+            ShutterCurve::Triangle => (0.5, 0.5),
+            ShutterCurve::Trapezoid { open, close } => {
+                let open = open.clamp(0.0, 1.0);
+                (open, close.clamp(0.0, 1.0 - open))
+            }
+        };
+
+        // Invert the piecewise CDF of the ramp up, plateau and ramp down.
+        let area = 1.0 - (a + b) / 2.0;
+        let s = u * area;
+        if s < a / 2.0 {
+            (2.0 * a * s).sqrt()
+        } else if s < area - b / 2.0 {
+            a + (s - a / 2.0)
+        } else {
+            1.0 - (2.0 * b * (area - s)).sqrt()
+        }
+    }
+}
+
+/// How the scene is projected onto the image.
+#[derive(Clone, Copy)]
+pub enum Projection {
+    /// Rays spread out from the lens to cover `vfov`, so distant things
+    /// look smaller.
+    Perspective,
+    /// Rays run parallel along the view direction, keeping sizes and
+    /// parallel lines as they are, for technical and architectural views.
+    /// The view is `view_height` scene units tall and `vfov` is unused.
+    /// An aperture still blurs what is away from the focus distance.
+    Orthographic { view_height: f64 },
+}
+
+/// What rays see when they escape the scene.
+#[derive(Clone)]
+pub enum Background {
+    /// The white to light blue sky gradient of the book cover.
+    Gradient,
+    Solid(Vec3),
+    /// Daylight for outdoor scenes.
+    Sky(Box<Sky>),
+}
+
 pub struct Camera {
+    pub projection: Projection,
     pub vfov: f64,
     pub aspect_ratio: f64,
     pub image_width: i32,
     pub samples_per_pixel: i32,
     pub max_depth: i32,
+    /// Bounces a path always takes before Russian roulette may end it.
+    pub roulette_depth: i32,
     pub image_height: i32,
+    /// Ray times are drawn between these, weighted by `shutter_curve`. Both
+    /// lie in [0,1], the span over which objects move. A shutter closing
+    /// before it opens takes every ray at `shutter_open`.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    pub shutter_curve: ShutterCurve,
+    /// Time the sensor takes to read out from the top row to the bottom one;
+    /// each row's shutter opens that much later than the row above. Every
+    /// row is exposed for the same time within the shutter interval, so this
+    /// is capped at its length. Zero gives a global shutter.
+    pub rolling_shutter: f64,
+    /// Scene-wide medium every ray travels through, if any.
+    pub fog: Option<Fog>,
+    pub background: Background,
+    /// Lights that are not part of the world, like point lights and the sun.
+    pub punctual_lights: Vec<Box<dyn Light + Sync + Send>>,
+    /// How the emissive objects in the world are picked for direct lighting.
+    pub light_selection: LightSelection,
+    /// Traces each sample for a handful of wavelengths instead of RGB, so
+    /// dispersive glass splits light into its colors. Colors in the scene
+    /// are upsampled to spectra and the image holds CIE XYZ until output.
+    pub spectral: bool,
     center: Point3,
-    lower_left_corner: Point3,
-    horizontal: Vec3,
-    vertical: Vec3,
     u: Vec3,
     v: Vec3,
     w: Vec3,
     lens_radius: f64,
+    focus_dist: f64,
 }
 
 impl Camera {
@@ -40,26 +131,40 @@ impl Camera {
         focus_dist: f64,
     ) -> Camera {
         let mut camera = Camera {
+            projection: Projection::Perspective,
             vfov,
             aspect_ratio,
             image_width,
             samples_per_pixel,
             max_depth,
+            roulette_depth: 3,
             image_height: 0,
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            shutter_curve: ShutterCurve::Box,
+            rolling_shutter: 0.0,
+            fog: None,
+            background: Background::Gradient,
+            punctual_lights: Vec::new(),
+            light_selection: LightSelection::Bvh,
+            spectral: false,
             center: Point3(0.0, 0.0, 0.0),
-            lower_left_corner: Point3(0.0, 0.0, 0.0),
-            horizontal: Vec3(0.0, 0.0, 0.0),
-            vertical: Vec3(0.0, 0.0, 0.0),
             u: Vec3(0.0, 0.0, 0.0),
             v: Vec3(0.0, 0.0, 0.0),
             w: Vec3(0.0, 0.0, 0.0),
             lens_radius: 0.0,
+            focus_dist: 0.0,
         };
         camera.init(lookfrom, lookat, vup, aperture, focus_dist);
-        return camera;
+        camera
     }
 
-    pub fn render(&self, world: &HittableList, task: RenderTask) -> Box<Image> {
+    pub fn render(
+        &self,
+        world: &HittableList,
+        lights: &LightSampler,
+        task: RenderTask,
+    ) -> Box<Image> {
         let mut j = 0;
         let mut image = Image::new(self.image_width, self.image_height);
         let sample_num = task.samples_per_pixel;
@@ -77,7 +182,10 @@ impl Camera {
                             i as f64 / self.image_width as f64,
                             (self.image_height - j) as f64 / self.image_height as f64,
                         );
-                        pixel_color = pixel_color + ray_color(&ray, self.max_depth, &world);
+                        pixel_color += match self.spectral {
+                            true => self.spectral_color(&ray, world, lights),
+                            false => self.ray_color(&ray, world, lights, &mut None),
+                        };
                     }
                     image.set_pixel(i, j, pixel_color);
                     // write_color(pixel_color,self.samples_per_pixel);
@@ -102,80 +210,286 @@ impl Camera {
             self.image_height = 1;
         }
 
-        let theta = degrees_to_radians(self.vfov);
-        let h = (theta / 2.0).tan();
-
-        // Determine viewport dimensions.
-        let viewport_height = 2.0 * h;
-        let viewport_width = self.aspect_ratio * viewport_height;
-
         self.w = (*lookfrom - *lookat).unit_vector();
         self.u = vup.cross(&self.w).unit_vector();
         self.v = self.w.cross(&self.u);
 
         self.center = lookfrom.clone();
-        self.horizontal = focus_dist * viewport_width * self.u;
-        self.vertical = focus_dist * viewport_height * self.v;
-        self.lower_left_corner =
-            self.center - self.horizontal / 2.0 - self.vertical / 2.0 - focus_dist * self.w;
-
         self.lens_radius = aperture / 2.0;
-
-        // println!("lower left corner {:?}",self.lower_left_corner);
-        // println!("up right corner {:?}",self.lower_left_corner + self.horizontal + self.vertical);
+        self.focus_dist = focus_dist;
     }
 
     fn get_ray(&self, s: f64, t: f64) -> Ray {
         let rd = self.lens_radius * random_in_unit_disk();
         let offset = self.u * rd.x() + self.v * rd.y();
-        let ray_time = random_f64();
-        return Ray::new_with_tm(
-            self.center + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.center - offset,
-            ray_time,
-        );
+        // t runs from the bottom of the image up, scanlines from the top down.
+        let interval = (self.shutter_close - self.shutter_open).max(0.0);
+        let readout = self.rolling_shutter.clamp(0.0, interval);
+        let row_delay = readout * (1.0 - t);
+        let ray_time = self.shutter_open
+            + row_delay
+            + self.shutter_curve.sample(random_f64()) * (interval - readout);
+        // Where (s, t) falls on a view of the given height centered on the
+        // camera, in the plane facing it.
+        let across = |view_height: f64| {
+            (s - 0.5) * view_height * self.aspect_ratio * self.u + (t - 0.5) * view_height * self.v
+        };
+        match self.projection {
+            Projection::Perspective => {
+                // Rays leave the lens for the point on the focus plane, which
+                // spans `vfov` from the camera.
+                let view_height =
+                    2.0 * (degrees_to_radians(self.vfov) / 2.0).tan() * self.focus_dist;
+                let target = self.center - self.focus_dist * self.w + across(view_height);
+                Ray::new_with_tm(
+                    self.center + offset,
+                    target - self.center - offset,
+                    ray_time,
+                )
+            }
+            Projection::Orthographic { view_height } => {
+                // Each ray starts from its own point on a film as large as the
+                // view, and the lens bends it towards the focus plane behind.
+                let film = self.center + across(view_height);
+                Ray::new_with_tm(film + offset, -self.focus_dist * self.w - offset, ray_time)
+            }
+        }
     }
-}
 
-fn ray_color(ray: &Ray, depth: i32, world: &HittableList) -> Vec3 {
-    use Vec3 as Color;
+    /// Radiance arriving along `ray`, gathered by following the path it
+    /// starts one bounce at a time. After `roulette_depth` bounces, paths are
+    /// ended at random with a probability that grows as their throughput
+    /// drops, and the survivors are weighted up to keep the estimate unbiased.
+    ///
+    /// With `wavelengths`, the result and everything along the way are the
+    /// spectral values at those wavelengths rather than RGB.
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &HittableList,
+        lights: &LightSampler,
+        wavelengths: &mut Option<SampledWavelengths>,
+    ) -> Vec3 {
+        use Vec3 as Color;
+
+        let mut color = Color(0.0, 0.0, 0.0);
+        let mut throughput = Color(1.0, 1.0, 1.0);
+        let mut ray = *ray;
+        // Set when the vertex the ray left from also gathered light by sampling
+        // `lights`, to the density with which its material picked the ray.
+        // Emitters the ray finds are then weighted against the light sampling
+        // strategy with the power heuristic.
+        let mut bsdf_pdf: Option<f64> = None;
+        // Whether the ray left a vertex that did not sample the punctual
+        // lights, so the sun has to be found in the sky instead.
+        let mut specular_bounce = true;
+
+        // The bounce limit still caps the paths that survive the roulette.
+        for depth in 0..self.max_depth {
+            let mut rec = hittable::HitRecord::new();
+            let mut hit = world.hit(
+                &ray,
+                &interval::Interval {
+                    min: RAY_EPSILON,
+                    max: f64::INFINITY,
+                },
+                &mut rec,
+            );
 
-    // If we've exceeded the ray bounce limit, no more light is gathered.
-    if depth <= 0 {
-        return Color(0.0, 0.0, 0.0);
+            // The ray may scatter off the fog before reaching whatever it hit, or
+            // on its way out to the sky.
+            if let Some(fog) = &self.fog {
+                let t_max = if hit { rec.t } else { f64::INFINITY };
+                if let Some(t) = fog.sample_distance(&ray, t_max) {
+                    rec = fog.collision(&ray, t);
+                    hit = true;
+                }
+            }
+
+            if !hit {
+                color += throughput
+                    * path_values(self.background_color(&ray, specular_bounce), wavelengths);
+                break;
+            }
+
+            let mut emitted = rec.mat.emitted(&ray, &rec);
+            if let Some(bsdf_pdf) = bsdf_pdf {
+                let light_pdf = lights.pdf_value(&ray);
+                if light_pdf > 0.0 {
+                    emitted = emitted * power_heuristic(bsdf_pdf, light_pdf);
+                }
+            }
+
+            let sample_lights = !rec.mat.is_specular() && !lights.is_empty();
+            let mut direct = match sample_lights {
+                true => self.sample_light(&ray, &rec, world, lights),
+                false => Color(0.0, 0.0, 0.0),
+            };
+            if !rec.mat.is_specular() {
+                direct += self.sample_punctual_lights(&ray, &rec, world);
+            }
+            color += throughput * path_values(emitted + direct, wavelengths);
+
+            let mut scattered: Ray = Ray::new(Vec3::zero_vec3(), Vec3::zero_vec3());
+            let mut attenuation: Color = Color(0.0, 0.0, 0.0);
+            if !rec
+                .mat
+                .scatter(&ray, &rec, &mut attenuation, &mut scattered)
+                || rec.leaks(&scattered.dir())
+            {
+                break;
+            }
+            let specular_sample = rec.mat.is_specular_sample(&ray, &rec, &scattered);
+            bsdf_pdf = match sample_lights && !specular_sample {
+                true => Some(rec.mat.pdf(&ray, &rec, &scattered)),
+                false => None,
+            };
+            specular_bounce = specular_sample;
+            if rec.mat.is_dispersive() {
+                if let Some(wavelengths) = wavelengths {
+                    wavelengths.terminate_secondary();
+                }
+            }
+            throughput = throughput * path_values(attenuation, wavelengths);
+            ray = scattered.with_wavelength(wavelengths.as_ref().map(|w| w.hero()));
+
+            if depth + 1 >= self.roulette_depth {
+                let survival = throughput
+                    .x()
+                    .max(throughput.y())
+                    .max(throughput.z())
+                    .min(0.95);
+                if random_f64() >= survival {
+                    break;
+                }
+                throughput = throughput / survival;
+            }
+        }
+        color
     }
 
-    let mut rec = hittable::HitRecord::new();
-    if world.hit(
-        ray,
-        &interval::Interval {
-            min: 0.001,
-            max: f64::INFINITY,
-        },
-        &mut rec,
-    ) {
-        let mut scattered: Ray = Ray::new(Vec3::zero_vec3(), Vec3::zero_vec3());
-        let mut attenuation: Color = Color(0.0, 0.0, 0.0);
+    /// CIE XYZ of the light arriving along `ray`, traced for wavelengths
+    /// picked at random.
+    fn spectral_color(&self, ray: &Ray, world: &HittableList, lights: &LightSampler) -> Vec3 {
+        let hero = SampledWavelengths::sample_visible(random_f64());
+        let ray = ray.with_wavelength(Some(hero.hero()));
+        let mut wavelengths = Some(hero);
+        let values = self.ray_color(&ray, world, lights, &mut wavelengths);
+        wavelengths.unwrap().to_xyz(&values)
+    }
 
-        if rec.mat.scatter(ray, &rec, &mut attenuation, &mut scattered) {
-            return attenuation * ray_color(&scattered, depth - 1, world);
+    fn background_color(&self, ray: &Ray, show_sun: bool) -> Vec3 {
+        match &self.background {
+            Background::Solid(color) => *color,
+            Background::Sky(sky) if show_sun => sky.radiance_with_sun(&ray.dir()),
+            Background::Sky(sky) => sky.radiance(&ray.dir()),
+            Background::Gradient => {
+                let unit_direction = ray.dir().unit_vector();
+                let a = 0.5 * (unit_direction.y() + 1.0);
+                Vec3(1.0, 1.0, 1.0) * (1.0 - a) + Vec3(0.5, 0.7, 1.0) * a
+            }
         }
+    }
 
-        return Color(0.0, 0.0, 0.0);
+    /// Next-event estimation: light reaching `rec` directly from a point
+    /// picked on one of the `lights`, dimmed by whatever the shadow ray to it
+    /// passes through, weighted against the material sampling the same
+    /// direction.
+    fn sample_light(
+        &self,
+        ray: &Ray,
+        rec: &hittable::HitRecord,
+        world: &HittableList,
+        lights: &LightSampler,
+    ) -> Vec3 {
+        let black = Vec3::zero_vec3();
+        let dir = match lights.random(&rec.p, ray.time()) {
+            Some(dir) => dir,
+            None => return black,
+        };
+        if rec.leaks(&dir) {
+            return black;
+        }
+        let shadow_ray = Ray::new_with_tm(rec.p, dir, ray.time());
+        let light_pdf = lights.pdf_value(&shadow_ray);
+        if light_pdf <= 0.0 {
+            return black;
+        }
+        let f = rec.mat.eval(ray, rec, &shadow_ray);
+        if f.near_zero() {
+            return black;
+        }
 
-        // old
+        let mut light_rec = hittable::HitRecord::new();
+        if !lights.hit(
+            &shadow_ray,
+            &interval::Interval::new(RAY_EPSILON, f64::INFINITY),
+            &mut light_rec,
+        ) {
+            return black;
+        }
+        let transmittance = world.transmittance(
+            &shadow_ray,
+            &interval::Interval::new(RAY_EPSILON, light_rec.t * (1.0 - 1e-6)),
+        );
+        if transmittance == 0.0 {
+            return black;
+        }
+        let mut radiance = light_rec.mat.emitted(&shadow_ray, &light_rec) * transmittance;
+        if let Some(fog) = &self.fog {
+            radiance = radiance * fog.transmittance(&shadow_ray, light_rec.t);
+        }
+        let bsdf_pdf = rec.mat.pdf(ray, rec, &shadow_ray);
+        f * radiance * power_heuristic(light_pdf, bsdf_pdf) / light_pdf
+    }
 
-        // // let dir = Vec3::random_on_hemisphere(&rec.normal);
-        // let dir = Vec3::random_unit_vector() + rec.normal;
-        // return ray_color(&Ray { orig: rec.p, dir: dir },depth-1,world) * 0.5;
+    /// Light reaching `rec` from each of the `punctual_lights`, dimmed by
+    /// whatever lies in between.
+    /// Scattered rays can never find these lights, so no weighting is needed.
+    fn sample_punctual_lights(
+        &self,
+        ray: &Ray,
+        rec: &hittable::HitRecord,
+        world: &HittableList,
+    ) -> Vec3 {
+        let mut total = Vec3::zero_vec3();
+        for light in self.punctual_lights.iter() {
+            let Some(sample) = light.sample(&rec.p) else {
+                continue;
+            };
+            if rec.leaks(&sample.dir) {
+                continue;
+            }
+            let shadow_ray = Ray::new_with_tm(rec.p, sample.dir, ray.time());
+            let f = rec.mat.eval(ray, rec, &shadow_ray);
+            if f.near_zero() {
+                continue;
+            }
 
-        // // return (rec.normal + color(1.0,1.0,1.0)) * 0.5;
-        // old end
+            let transmittance = world.transmittance(
+                &shadow_ray,
+                &interval::Interval::new(RAY_EPSILON, sample.distance * (1.0 - 1e-6)),
+            );
+            if transmittance == 0.0 {
+                continue;
+            }
+            let mut radiance = sample.radiance * transmittance;
+            if let Some(fog) = &self.fog {
+                radiance = radiance * fog.transmittance(&shadow_ray, sample.distance);
+            }
+            total += f * radiance;
+        }
+        total
     }
+}
 
-    let unit_direction = ray.dir().unit_vector();
-    let a = 0.5 * (unit_direction.y() + 1.0);
-    Color(1.0, 1.0, 1.0) * (1.0 - a) + Color(0.5, 0.7, 1.0) * a
+/// `rgb` in the terms a path is traced in: as it is for RGB rendering, or
+/// upsampled to the path's wavelengths for spectral rendering.
+fn path_values(rgb: Vec3, wavelengths: &Option<SampledWavelengths>) -> Vec3 {
+    match wavelengths {
+        Some(wavelengths) => wavelengths.upsample(&rgb),
+        None => rgb,
+    }
 }
 
 fn degrees_to_radians(degrees: f64) -> f64 {
@@ -195,3 +509,193 @@ fn random_in_unit_disk() -> Vec3 {
         return p;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> Camera {
+        Camera::new(
+            20.0,
+            16.0 / 9.0,
+            40,
+            1,
+            1,
+            &Vec3(13.0, 2.0, 3.0),
+            &Vec3(0.0, 0.0, 0.0),
+            &Vec3(0.0, 1.0, 0.0),
+            0.1,
+            10.0,
+        )
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel_and_fill_the_view() {
+        let mut camera = camera();
+        camera.lens_radius = 0.0;
+        camera.projection = Projection::Orthographic { view_height: 4.0 };
+        let view_width = 4.0 * 16.0 / 9.0;
+        for (s, t) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0), (0.5, 0.5)] {
+            let ray = camera.get_ray(s, t);
+            assert!((ray.dir().unit_vector() + camera.w).length() < 1e-12);
+            let across = ray.origin() - camera.center;
+            assert!(across.dot(&camera.w).abs() < 1e-12);
+            assert!((across.dot(&camera.u) - (s - 0.5) * view_width).abs() < 1e-12);
+            assert!((across.dot(&camera.v) - (t - 0.5) * 4.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn perspective_rays_span_the_field_of_view() {
+        let mut camera = camera();
+        camera.lens_radius = 0.0;
+        let center = camera.get_ray(0.5, 0.5);
+        assert!((center.origin() - camera.center).length() < 1e-12);
+        assert!((center.dir().unit_vector() + camera.w).length() < 1e-12);
+        for t in [0.0, 1.0] {
+            let edge = camera.get_ray(0.5, t).dir().unit_vector();
+            let angle = edge.dot(&-camera.w).acos().to_degrees();
+            assert!((angle - 10.0).abs() < 1e-9, "{angle}");
+        }
+    }
+
+    #[test]
+    fn rolling_shutter_stays_within_the_interval() {
+        let mut camera = camera();
+        camera.shutter_open = 0.2;
+        camera.shutter_close = 0.9;
+        camera.rolling_shutter = 0.5;
+        for t in [0.0, 0.3, 1.0] {
+            for _ in 0..1000 {
+                let time = camera.get_ray(0.5, t).time();
+                assert!((0.2..=0.9).contains(&time), "{time}");
+            }
+        }
+
+        // Longer readouts than the interval are capped at it.
+        camera.rolling_shutter = 5.0;
+        assert!((camera.get_ray(0.5, 0.0).time() - 0.9).abs() < 1e-12);
+        assert!((camera.get_ray(0.5, 1.0).time() - 0.2).abs() < 1e-12);
+
+        // A shutter closing before it opens freezes time instead of panicking.
+        camera.shutter_close = 0.1;
+        assert_eq!(camera.get_ray(0.5, 0.3).time(), 0.2);
+    }
+
+    #[test]
+    fn shutter_curves_follow_their_shape() {
+        // Fraction of samples below 0.25, against the area under each curve.
+        let below_quarter = |curve: ShutterCurve| {
+            let n = 10000;
+            let count = (0..n)
+                .filter(|i| curve.sample((*i as f64 + 0.5) / n as f64) < 0.25)
+                .count();
+            count as f64 / n as f64
+        };
+        assert!((below_quarter(ShutterCurve::Box) - 0.25).abs() < 1e-3);
+        assert!((below_quarter(ShutterCurve::Triangle) - 0.125).abs() < 1e-3);
+        // Ramps up over the first half, so 0.25^2 / 0.5 of the area of 0.75.
+        let trapezoid = ShutterCurve::Trapezoid {
+            open: 0.5,
+            close: 0.0,
+        };
+        assert!((below_quarter(trapezoid) - 0.0625 / 0.75).abs() < 1e-3);
+    }
+
+    // A ball of scattering fog against a uniform white sky, looked at from
+    // outside: light bounces around inside for a while before it gets out.
+    fn furnace(albedo: f64) -> HittableList {
+        use crate::constant_medium::ConstantMedium;
+        use crate::materials::lambertian::Lambertian;
+        use crate::sphere::Sphere;
+        use std::sync::Arc;
+
+        let boundary = Sphere::new(
+            Point3(0.0, 0.0, 0.0),
+            1.0,
+            Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+        );
+        let mut world = HittableList::new();
+        world.add(Arc::new(ConstantMedium::new(
+            Arc::new(boundary),
+            3.0,
+            Vec3(albedo, albedo, albedo),
+        )));
+        world
+    }
+
+    fn mean_radiance(camera: &Camera, world: &HittableList, n: usize) -> f64 {
+        let lights = LightSampler::new(HittableList::new(), LightSelection::Bvh);
+        let ray = Ray::new(Point3(0.0, 0.0, 5.0), Vec3(0.0, 0.0, -1.0));
+        let total: f64 = (0..n)
+            .map(|_| camera.ray_color(&ray, world, &lights, &mut None).x())
+            .sum();
+        total / n as f64
+    }
+
+    #[test]
+    fn russian_roulette_keeps_the_furnace_white() {
+        // Nothing absorbs, so every path that gets out brings back exactly
+        // the sky, however often the roulette ends some of them early.
+        let mut camera = camera();
+        camera.background = Background::Solid(Vec3(1.0, 1.0, 1.0));
+        camera.max_depth = 1000;
+        camera.roulette_depth = 0;
+        let mean = mean_radiance(&camera, &furnace(1.0), 20000);
+        assert!((mean - 1.0).abs() < 0.02, "{mean}");
+    }
+
+    #[test]
+    fn russian_roulette_does_not_change_the_mean() {
+        let mut camera = camera();
+        camera.background = Background::Solid(Vec3(1.0, 1.0, 1.0));
+        camera.max_depth = 1000;
+        let world = furnace(0.8);
+        camera.roulette_depth = camera.max_depth;
+        let without = mean_radiance(&camera, &world, 20000);
+        camera.roulette_depth = 0;
+        let with = mean_radiance(&camera, &world, 20000);
+        assert!((with - without).abs() < 0.02, "{with} {without}");
+    }
+
+    #[test]
+    fn light_sampling_agrees_with_following_the_bsdf() {
+        use crate::materials::{diffuse_light::DiffuseLight, lambertian::Lambertian};
+        use crate::quad::Quad;
+        use std::sync::Arc;
+
+        // A grey floor under a square lamp facing down, in the dark.
+        let mut world = HittableList::new();
+        world.add(Arc::new(Quad::new(
+            Point3(-5.0, 0.0, -5.0),
+            Vec3(0.0, 0.0, 10.0),
+            Vec3(10.0, 0.0, 0.0),
+            Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+        )));
+        world.add(Arc::new(Quad::new(
+            Point3(-1.0, 2.0, -1.0),
+            Vec3(2.0, 0.0, 0.0),
+            Vec3(0.0, 0.0, 2.0),
+            Box::new(DiffuseLight::new(Vec3(4.0, 4.0, 4.0))),
+        )));
+        let mut camera = camera();
+        camera.background = Background::Solid(Vec3(0.0, 0.0, 0.0));
+        camera.max_depth = 20;
+
+        let ray = Ray::new(Point3(0.0, 1.0, 3.0), Vec3(0.0, -1.0, -3.0));
+        let mean = |lights: &LightSampler| {
+            let n = 40000;
+            let total: f64 = (0..n)
+                .map(|_| camera.ray_color(&ray, &world, lights, &mut None).x())
+                .sum();
+            total / n as f64
+        };
+        // Without lights to sample, the lamp is only found by scattering.
+        let bsdf_only = mean(&LightSampler::new(HittableList::new(), LightSelection::Bvh));
+        let mis = mean(&LightSampler::new(world.lights(), LightSelection::Bvh));
+        assert!(
+            (mis - bsdf_only).abs() < 0.03 * bsdf_only,
+            "{mis} {bsdf_only}"
+        );
+    }
+}
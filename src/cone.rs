@@ -0,0 +1,112 @@
+use crate::hittable::{HitRecord, Hittable};
+use crate::materials::material::Material;
+use crate::math::{azimuth, disk_dpdv, hit_disk, solve_quadratic};
+use crate::types::aabb::Aabb;
+use crate::types::interval::Interval;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// A cone whose base of `radius` sits at `center` and whose apex lies `height`
+/// above it, swept `phi_max` radians around the vertical axis.
+pub struct Cone {
+    pub radius: f64,
+    pub height: f64,
+    pub phi_max: f64,
+    pub capped: bool,
+    pub mat: Box<dyn Material + Sync + Send>,
+    center: Point3,
+}
+
+impl Cone {
+    /// `phi_max` is given in degrees, like the camera's field of view.
+    pub fn new(
+        center: Point3,
+        radius: f64,
+        height: f64,
+        phi_max: f64,
+        capped: bool,
+        mat: Box<dyn Material + Sync + Send>,
+    ) -> Cone {
+        Cone {
+            radius,
+            height,
+            phi_max: phi_max.clamp(0.0, 360.0).to_radians(),
+            capped,
+            mat,
+            center,
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let origin = r.origin() - self.center;
+        let dir = r.dir();
+        let mut closest = Interval::new(ray_t.min, ray_t.max);
+        let mut hit_anything = false;
+
+        // x^2 + z^2 = (k (h - y))^2 with k = r / h
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let oy = origin.y() - self.height;
+        let a = dir.x() * dir.x() + dir.z() * dir.z() - k2 * dir.y() * dir.y();
+        let b = 2.0 * (origin.x() * dir.x() + origin.z() * dir.z() - k2 * oy * dir.y());
+        let c = origin.x() * origin.x() + origin.z() * origin.z() - k2 * oy * oy;
+        if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+            for t in [t0, t1] {
+                if !closest.surrounds(t) {
+                    continue;
+                }
+                let p = origin + t * dir;
+                let phi = azimuth(p.x(), p.z());
+                if p.y() < 0.0 || p.y() > self.height || phi > self.phi_max {
+                    continue;
+                }
+                closest.max = t;
+                hit_anything = true;
+                rec.t = t;
+                rec.p = r.at(t);
+                let outward_normal = Vec3(p.x(), k2 * (self.height - p.y()), p.z()).unit_vector();
+                rec.set_face_normal(r, &outward_normal);
+                rec.u = phi / self.phi_max;
+                rec.v = p.y() / self.height;
+                let rho = (p.x() * p.x() + p.z() * p.z()).sqrt().max(1e-12);
+                rec.set_tangents(
+                    self.phi_max * Vec3(-p.z(), 0.0, p.x()),
+                    self.height * Vec3(-k * p.x() / rho, 1.0, -k * p.z() / rho),
+                );
+                break;
+            }
+        }
+
+        if self.capped {
+            if let Some((t, p)) = hit_disk(&origin, &dir, 0.0, self.radius, self.phi_max, &closest)
+            {
+                hit_anything = true;
+                rec.t = t;
+                rec.p = r.at(t);
+                rec.set_face_normal(r, &Vec3(0.0, -1.0, 0.0));
+                rec.u = azimuth(p.x(), p.z()) / self.phi_max;
+                rec.v = (p.x() * p.x() + p.z() * p.z()).sqrt() / self.radius;
+                rec.set_tangents(
+                    self.phi_max * Vec3(-p.z(), 0.0, p.x()),
+                    disk_dpdv(&p, self.radius),
+                );
+            }
+        }
+
+        if hit_anything {
+            rec.mat = self.mat.my_copy();
+        }
+        hit_anything
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(
+            self.center + Vec3(-self.radius, 0.0, -self.radius),
+            self.center + Vec3(self.radius, self.height, self.radius),
+        )
+    }
+}
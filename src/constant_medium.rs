@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use crate::hittable::{HitRecord, Hittable};
+use crate::materials::isotropic::Isotropic;
+use crate::materials::material::Material;
+use crate::random::random_f64;
+use crate::types::aabb::Aabb;
+use crate::types::interval::Interval;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+
+/// A volume of uniform density filling a closed boundary, like smoke or fog.
+/// Rays passing through may scatter at a random depth, with the probability
+/// growing with `density` and the distance travelled inside.
+pub struct ConstantMedium {
+    boundary: Arc<dyn Hittable + Sync + Send>,
+    neg_inv_density: f64,
+    phase_function: Box<dyn Material + Sync + Send>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Arc<dyn Hittable + Sync + Send>,
+        density: f64,
+        albedo: Color,
+    ) -> ConstantMedium {
+        ConstantMedium::new_with_phase(boundary, density, Box::new(Isotropic::new(albedo)))
+    }
+
+    pub fn new_with_phase(
+        boundary: Arc<dyn Hittable + Sync + Send>,
+        density: f64,
+        phase_function: Box<dyn Material + Sync + Send>,
+    ) -> ConstantMedium {
+        ConstantMedium {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        // Distance into the medium at which the ray scatters; walk the parts of
+        // the ray inside the boundary until it has been used up. Using spans
+        // keeps non-convex boundaries correct.
+        let ray_length = r.dir().length();
+        let mut remaining = self.neg_inv_density * random_f64().ln();
+
+        for span in self.boundary.hit_spans(r) {
+            let t0 = span.enter.t.max(ray_t.min);
+            let t1 = span.exit.t.min(ray_t.max);
+            if t0 >= t1 {
+                continue;
+            }
+            let distance_inside = (t1 - t0) * ray_length;
+            if remaining > distance_inside {
+                remaining -= distance_inside;
+                continue;
+            }
+
+            rec.t = t0 + remaining / ray_length;
+            rec.p = r.at(rec.t);
+            rec.set_face_normal(r, &-r.dir()); // arbitrary, but facing the ray
+            rec.mat = self.phase_function.my_copy();
+            return true;
+        }
+        false
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        let ray_length = r.dir().length();
+        let distance_inside: f64 = self
+            .boundary
+            .hit_spans(r)
+            .iter()
+            .map(|span| {
+                let t0 = span.enter.t.max(ray_t.min);
+                let t1 = span.exit.t.min(ray_t.max);
+                (t1 - t0).max(0.0) * ray_length
+            })
+            .sum();
+        (distance_inside / self.neg_inv_density).exp()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::HittableList;
+    use crate::materials::lambertian::Lambertian;
+    use crate::sphere::Sphere;
+    use crate::types::vec3::Vec3 as Point3;
+
+    fn ball(center: Point3) -> Arc<Sphere> {
+        Arc::new(Sphere::new(
+            center,
+            1.0,
+            Box::new(Lambertian::new(Color(0.5, 0.5, 0.5))),
+        ))
+    }
+
+    #[test]
+    fn shadow_rays_fade_through_media_and_stop_at_surfaces() {
+        let mut world = HittableList::new();
+        world.add(Arc::new(ConstantMedium::new(
+            ball(Point3(0.0, 0.0, 0.0)),
+            0.5,
+            Color(1.0, 1.0, 1.0),
+        )));
+        let r = Ray::new(Point3(-5.0, 0.0, 0.0), Vec3(2.0, 0.0, 0.0));
+
+        // Two units inside the sphere, or half of that when the ray stops at
+        // its center.
+        let through = world.transmittance(&r, &Interval::new(0.0, 5.0));
+        assert!((through - (-1.0f64).exp()).abs() < 1e-9);
+        let halfway = world.transmittance(&r, &Interval::new(0.0, 2.5));
+        assert!((halfway - (-0.5f64).exp()).abs() < 1e-9);
+
+        world.add(ball(Point3(3.0, 0.0, 0.0)));
+        assert_eq!(world.transmittance(&r, &Interval::new(0.0, 5.0)), 0.0);
+        assert!(world.transmittance(&r, &Interval::new(0.0, 3.0)) > 0.0);
+    }
+}
@@ -0,0 +1,252 @@
+use std::sync::Arc;
+
+use crate::hittable::{HitRecord, Hittable, Span};
+use crate::types::aabb::Aabb;
+use crate::types::interval::Interval;
+use crate::types::ray::Ray;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    /// Everything inside the left object that is not inside the right one.
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(&self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => in_left || in_right,
+            CsgOp::Intersection => in_left && in_right,
+            CsgOp::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Constructive solid geometry node combining two closed objects. Children may
+/// themselves be `Csg` nodes, instances, or any closed `Hittable`.
+pub struct Csg {
+    pub op: CsgOp,
+    left: Arc<dyn Hittable + Sync + Send>,
+    right: Arc<dyn Hittable + Sync + Send>,
+    bbox: Aabb,
+}
+
+impl Csg {
+    pub fn new(
+        op: CsgOp,
+        left: Arc<dyn Hittable + Sync + Send>,
+        right: Arc<dyn Hittable + Sync + Send>,
+    ) -> Csg {
+        let (a, b) = (left.bounding_box(), right.bounding_box());
+        let overlap =
+            |i: &Interval, j: &Interval| Interval::new(i.min.max(j.min), i.max.min(j.max));
+        let bbox = match op {
+            CsgOp::Union => Aabb::surrounding(&a, &b),
+            CsgOp::Intersection => Aabb::new(
+                overlap(&a.x, &b.x),
+                overlap(&a.y, &b.y),
+                overlap(&a.z, &b.z),
+            ),
+            CsgOp::Difference => a,
+        };
+        Csg {
+            op,
+            left,
+            right,
+            bbox,
+        }
+    }
+
+    pub fn union(
+        left: Arc<dyn Hittable + Sync + Send>,
+        right: Arc<dyn Hittable + Sync + Send>,
+    ) -> Csg {
+        Csg::new(CsgOp::Union, left, right)
+    }
+
+    pub fn intersection(
+        left: Arc<dyn Hittable + Sync + Send>,
+        right: Arc<dyn Hittable + Sync + Send>,
+    ) -> Csg {
+        Csg::new(CsgOp::Intersection, left, right)
+    }
+
+    pub fn difference(
+        left: Arc<dyn Hittable + Sync + Send>,
+        right: Arc<dyn Hittable + Sync + Send>,
+    ) -> Csg {
+        Csg::new(CsgOp::Difference, left, right)
+    }
+}
+
+impl Csg {
+    /// Sweeps the boundaries of both children along the whole line through
+    /// `r` in order, tracking whether the line is inside each, and calls
+    /// `visit` with each boundary where the operation's outcome changes and
+    /// whether it enters the result. Stops early when `visit` returns false.
+    fn sweep(&self, r: &Ray, mut visit: impl FnMut(HitRecord, bool) -> bool) {
+        if !self.bbox.hit(r, &Interval::universe()) {
+            return;
+        }
+        let left = self.left.hit_spans(r);
+        if left.is_empty() && self.op != CsgOp::Union {
+            return;
+        }
+
+        let mut events = Vec::new();
+        for (spans, from_right) in [(left, false), (self.right.hit_spans(r), true)] {
+            for span in spans {
+                events.push((span.enter, from_right, true));
+                events.push((span.exit, from_right, false));
+            }
+        }
+        events.sort_by(|a, b| a.0.t.total_cmp(&b.0.t));
+
+        let (mut in_left, mut in_right, mut inside) = (false, false, false);
+        for (mut rec, from_right, entering) in events {
+            if from_right {
+                in_right = entering;
+            } else {
+                in_left = entering;
+            }
+            let now_inside = self.op.inside(in_left, in_right);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+
+            // A carved-out surface faces the other way: its outward normal
+            // points into the subtracted object. The stored normal already
+            // opposes the ray, so only the side flips.
+            if from_right && self.op == CsgOp::Difference {
+                rec.front_face = !rec.front_face;
+            }
+            if !visit(rec, inside) {
+                return;
+            }
+        }
+    }
+}
+
+impl Hittable for Csg {
+    // The children's spans cover the whole line, since the sweep has to know
+    // which side of each boundary it starts on, but it stops at the first
+    // boundary inside `ray_t`.
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false;
+        }
+        let mut found = false;
+        self.sweep(r, |boundary, _| {
+            if boundary.t >= ray_t.max {
+                return false;
+            }
+            if ray_t.surrounds(boundary.t) {
+                *rec = boundary;
+                found = true;
+                return false;
+            }
+            true
+        });
+        found
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn hit_spans(&self, r: &Ray) -> Vec<Span> {
+        let mut spans = Vec::new();
+        let mut enter: Option<HitRecord> = None;
+        self.sweep(r, |rec, entering| {
+            if entering {
+                enter = Some(rec);
+            } else if let Some(enter) = enter.take() {
+                spans.push(Span { enter, exit: rec });
+            }
+            true
+        });
+        spans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+    use crate::sphere::Sphere;
+    use crate::types::vec3::Vec3;
+
+    // Two unit spheres overlapping between x = -0.5 and x = 0.5, and a ray
+    // along x through both that reaches the first at t = 3.5.
+    fn spheres() -> (Arc<Sphere>, Arc<Sphere>, Ray) {
+        let sphere = |x| {
+            Arc::new(Sphere::new(
+                Vec3(x, 0.0, 0.0),
+                1.0,
+                Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+            ))
+        };
+        let r = Ray::new(Vec3(-5.0, 0.0, 0.0), Vec3(1.0, 0.0, 0.0));
+        (sphere(-0.5), sphere(0.5), r)
+    }
+
+    fn span_ts(csg: &Csg, r: &Ray) -> Vec<(f64, f64)> {
+        csg.hit_spans(r)
+            .iter()
+            .map(|span| (span.enter.t, span.exit.t))
+            .collect()
+    }
+
+    fn assert_spans(actual: Vec<(f64, f64)>, expected: &[(f64, f64)]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for ((enter, exit), (e_enter, e_exit)) in actual.iter().zip(expected) {
+            assert!((enter - e_enter).abs() < 1e-9, "{actual:?}");
+            assert!((exit - e_exit).abs() < 1e-9, "{actual:?}");
+        }
+    }
+
+    #[test]
+    fn union_spans_both_spheres() {
+        let (a, b, r) = spheres();
+        assert_spans(span_ts(&Csg::union(a, b), &r), &[(3.5, 6.5)]);
+    }
+
+    #[test]
+    fn intersection_spans_the_overlap() {
+        let (a, b, r) = spheres();
+        assert_spans(span_ts(&Csg::intersection(a, b), &r), &[(4.5, 5.5)]);
+    }
+
+    #[test]
+    fn difference_flips_the_carved_surface() {
+        let (a, b, r) = spheres();
+        let csg = Csg::difference(a.clone(), b.clone());
+        assert_spans(span_ts(&csg, &r), &[(3.5, 4.5)]);
+
+        // The ray leaves through the right sphere's surface, which faces
+        // into it, so the exit is a back face with the normal against the ray.
+        let exit = &csg.hit_spans(&r)[0].exit;
+        assert!(!exit.front_face);
+        assert!((exit.normal - Vec3(-1.0, 0.0, 0.0)).length() < 1e-9);
+
+        // Seen from the other side, the left sphere is all that is left.
+        let back = Ray::new(Vec3(5.0, 0.0, 0.0), Vec3(-1.0, 0.0, 0.0));
+        assert_spans(span_ts(&csg, &back), &[(5.5, 6.5)]);
+        assert_spans(span_ts(&Csg::difference(b, a), &back), &[(3.5, 4.5)]);
+    }
+
+    #[test]
+    fn hit_finds_the_first_boundary_in_the_interval() {
+        let (a, b, r) = spheres();
+        let csg = Csg::difference(a, b);
+        let mut rec = HitRecord::new();
+        assert!(csg.hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 3.5).abs() < 1e-9 && rec.front_face);
+        assert!(csg.hit(&r, &Interval::new(4.0, f64::INFINITY), &mut rec));
+        assert!((rec.t - 4.5).abs() < 1e-9 && !rec.front_face);
+        assert!(!csg.hit(&r, &Interval::new(4.6, f64::INFINITY), &mut rec));
+        assert!(!csg.hit(&r, &Interval::new(0.001, 3.0), &mut rec));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::hittable::{HitRecord, Hittable};
+use crate::materials::material::Material;
+use crate::math::{azimuth, disk_dpdv, hit_disk, solve_quadratic};
+use crate::types::aabb::Aabb;
+use crate::types::interval::Interval;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// A cylinder around the vertical axis through `center`, spanning `y_min..y_max`
+/// relative to the center and swept `phi_max` radians around the axis.
+pub struct Cylinder {
+    pub radius: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub phi_max: f64,
+    pub capped: bool,
+    pub mat: Box<dyn Material + Sync + Send>,
+    center: Point3,
+}
+
+impl Cylinder {
+    /// `phi_max` is given in degrees, like the camera's field of view.
+    pub fn new(
+        center: Point3,
+        radius: f64,
+        y_min: f64,
+        y_max: f64,
+        phi_max: f64,
+        capped: bool,
+        mat: Box<dyn Material + Sync + Send>,
+    ) -> Cylinder {
+        Cylinder {
+            radius,
+            y_min: y_min.min(y_max),
+            y_max: y_min.max(y_max),
+            phi_max: phi_max.clamp(0.0, 360.0).to_radians(),
+            capped,
+            mat,
+            center,
+        }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        // Work in the cylinder's local frame, with the axis through the origin.
+        let origin = r.origin() - self.center;
+        let dir = r.dir();
+        let mut closest = Interval::new(ray_t.min, ray_t.max);
+        let mut hit_anything = false;
+
+        let a = dir.x() * dir.x() + dir.z() * dir.z();
+        let b = 2.0 * (origin.x() * dir.x() + origin.z() * dir.z());
+        let c = origin.x() * origin.x() + origin.z() * origin.z() - self.radius * self.radius;
+        if let Some((t0, t1)) = solve_quadratic(a, b, c) {
+            for t in [t0, t1] {
+                if !closest.surrounds(t) {
+                    continue;
+                }
+                let p = origin + t * dir;
+                let phi = azimuth(p.x(), p.z());
+                if p.y() < self.y_min || p.y() > self.y_max || phi > self.phi_max {
+                    continue;
+                }
+                closest.max = t;
+                hit_anything = true;
+                rec.t = t;
+                rec.p = r.at(t);
+                let outward_normal = Vec3(p.x(), 0.0, p.z()) / self.radius;
+                rec.set_face_normal(r, &outward_normal);
+                rec.u = phi / self.phi_max;
+                rec.v = (p.y() - self.y_min) / (self.y_max - self.y_min);
+                rec.set_tangents(
+                    self.phi_max * Vec3(-p.z(), 0.0, p.x()),
+                    Vec3(0.0, self.y_max - self.y_min, 0.0),
+                );
+                break;
+            }
+        }
+
+        if self.capped {
+            for (y, side) in [(self.y_min, -1.0), (self.y_max, 1.0)] {
+                if let Some((t, p)) =
+                    hit_disk(&origin, &dir, y, self.radius, self.phi_max, &closest)
+                {
+                    closest.max = t;
+                    hit_anything = true;
+                    rec.t = t;
+                    rec.p = r.at(t);
+                    rec.set_face_normal(r, &Vec3(0.0, side, 0.0));
+                    rec.u = azimuth(p.x(), p.z()) / self.phi_max;
+                    rec.v = (p.x() * p.x() + p.z() * p.z()).sqrt() / self.radius;
+                    rec.set_tangents(
+                        self.phi_max * Vec3(-p.z(), 0.0, p.x()),
+                        disk_dpdv(&p, self.radius),
+                    );
+                }
+            }
+        }
+
+        if hit_anything {
+            rec.mat = self.mat.my_copy();
+        }
+        hit_anything
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(
+            self.center + Vec3(-self.radius, self.y_min, -self.radius),
+            self.center + Vec3(self.radius, self.y_max, self.radius),
+        )
+    }
+}
@@ -0,0 +1,164 @@
+use crate::hittable::HitRecord;
+use crate::materials::henyey_greenstein::HenyeyGreenstein;
+use crate::random::random_f64;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+
+/// A medium filling the whole scene, thinning out exponentially with height:
+/// `density * exp(-falloff * (y - base_height))`. With no falloff it is a
+/// uniform haze everywhere.
+pub struct Fog {
+    pub density: f64,
+    pub falloff: f64,
+    pub base_height: f64,
+    pub albedo: Color,
+    /// Henyey-Greenstein asymmetry; haze scatters mostly forwards.
+    pub g: f64,
+}
+
+impl Fog {
+    pub fn uniform(density: f64, albedo: Color) -> Fog {
+        Fog::height(density, 0.0, 0.0, albedo)
+    }
+
+    pub fn height(density: f64, falloff: f64, base_height: f64, albedo: Color) -> Fog {
+        Fog {
+            density,
+            falloff,
+            base_height,
+            albedo,
+            g: 0.3,
+        }
+    }
+
+    /// Distance along `r` at which it scatters off the fog before `t_max`, if it does.
+    pub fn sample_distance(&self, r: &Ray, t_max: f64) -> Option<f64> {
+        if self.density <= 0.0 {
+            return None;
+        }
+
+        // Work in units of distance, then convert back to the ray's parameter.
+        let len = r.dir().length();
+        let dir_y = r.dir().y() / len;
+        let tau = -(1.0 - random_f64()).ln();
+
+        // Optical depth from the origin to distance s is
+        // a * (1 - exp(-b s)) / b, which we invert for the sampled depth.
+        let a = self.density * (-self.falloff * (r.origin().y() - self.base_height)).exp();
+        let b = self.falloff * dir_y;
+        let distance = if b.abs() < 1e-9 {
+            tau / a
+        } else {
+            let x = 1.0 - tau * b / a;
+            if x <= 0.0 {
+                // Looking upwards through thinning fog that is never thick enough.
+                return None;
+            }
+            -x.ln() / b
+        };
+
+        let t = distance / len;
+        if t < t_max {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    /// Fraction of light that makes it along `r` from its origin to `t_max`.
+    pub fn transmittance(&self, r: &Ray, t_max: f64) -> f64 {
+        if self.density <= 0.0 {
+            return 1.0;
+        }
+        let len = r.dir().length();
+        let distance = t_max * len;
+        let a = self.density * (-self.falloff * (r.origin().y() - self.base_height)).exp();
+        let b = self.falloff * r.dir().y() / len;
+        let optical_depth = if b.abs() < 1e-9 {
+            a * distance
+        } else {
+            a * (1.0 - (-b * distance).exp()) / b
+        };
+        (-optical_depth).exp()
+    }
+
+    /// The hit record for a ray scattering off the fog at `t`.
+    pub fn collision(&self, r: &Ray, t: f64) -> HitRecord {
+        let mut rec = HitRecord::new();
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_face_normal(r, &-r.dir()); // arbitrary, but facing the ray
+        rec.mat = Box::new(HenyeyGreenstein::new(self.albedo, self.g));
+        rec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use Vec3 as Point3;
+
+    fn height_fog() -> Fog {
+        Fog::height(0.3, 0.5, 1.0, Color(0.9, 0.9, 0.9))
+    }
+
+    // exp(-∫σ) along `r` up to `t`, summed numerically from the density.
+    fn numeric_transmittance(fog: &Fog, r: &Ray, t: f64) -> f64 {
+        let steps = 10000;
+        let dt = t / steps as f64;
+        let optical_depth: f64 = (0..steps)
+            .map(|i| {
+                let y = r.at((i as f64 + 0.5) * dt).y();
+                fog.density * (-fog.falloff * (y - fog.base_height)).exp()
+            })
+            .sum::<f64>()
+            * dt
+            * r.dir().length();
+        (-optical_depth).exp()
+    }
+
+    #[test]
+    fn transmittance_matches_the_density_along_the_ray() {
+        let rays = [
+            Ray::new(Point3(0.0, 0.5, 0.0), Vec3(2.0, 1.0, 0.5)),
+            Ray::new(Point3(0.0, 4.0, 0.0), Vec3(0.3, -1.0, 0.0)),
+        ];
+        for fog in [height_fog(), Fog::uniform(0.3, Color(0.9, 0.9, 0.9))] {
+            for r in rays.iter() {
+                let expected = numeric_transmittance(&fog, r, 3.0);
+                assert!((fog.transmittance(r, 3.0) - expected).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn horizontal_rays_see_constant_height_fog() {
+        // At y = 3 the fog is 0.3 * exp(-0.5 * 2) thick all along the ray.
+        let fog = height_fog();
+        let r = Ray::new(Point3(0.0, 3.0, 0.0), Vec3(0.0, 0.0, 2.0));
+        let expected = (-0.3 * (-1.0_f64).exp() * 2.0 * 5.0).exp();
+        assert!((fog.transmittance(&r, 5.0) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sampled_distances_follow_the_transmittance() {
+        // Upwards through thinning fog, some rays are never scattered at all.
+        let r = Ray::new(Point3(0.0, 0.5, 0.0), Vec3(2.0, 1.0, 0.5));
+        let n = 100000;
+        for fog in [height_fog(), Fog::uniform(0.3, Color(0.9, 0.9, 0.9))] {
+            let samples: Vec<Option<f64>> = (0..n).map(|_| fog.sample_distance(&r, 4.0)).collect();
+            for t in [0.5, 1.0, 2.0, 4.0] {
+                let beyond = samples.iter().filter(|s| s.is_none_or(|s| s >= t)).count();
+                let fraction = beyond as f64 / n as f64;
+                let expected = numeric_transmittance(&fog, &r, t);
+                assert!(
+                    (fraction - expected).abs() < 0.01,
+                    "{t}: {fraction} {expected}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::hittable::{HitRecord, Hittable};
+use crate::materials::henyey_greenstein::HenyeyGreenstein;
+use crate::random::random_f64;
+use crate::types::aabb::Aabb;
+use crate::types::interval::Interval;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+use crate::types::voxel_grid::VoxelGrid;
+
+use Vec3 as Point3;
+use Vec3 as Color;
+
+/// A participating medium whose density varies through a voxel grid stretched
+/// over an axis-aligned box. Collisions are found with delta tracking against
+/// the grid's maximum density, so no step size needs tuning.
+pub struct HeterogeneousMedium {
+    bbox: Aabb,
+    density: VoxelGrid,
+    density_scale: f64,
+    albedo: Color,
+    g: f64,
+    emission: Option<(VoxelGrid, Color)>,
+}
+
+impl HeterogeneousMedium {
+    /// The grid is scaled by `density_scale` and spans the box between the
+    /// corners `a` and `b`. `albedo` is the single-scattering albedo and `g`
+    /// the Henyey-Greenstein asymmetry.
+    pub fn new(
+        a: Point3,
+        b: Point3,
+        density: VoxelGrid,
+        density_scale: f64,
+        albedo: Color,
+        g: f64,
+    ) -> HeterogeneousMedium {
+        HeterogeneousMedium {
+            bbox: Aabb::from_points(a, b),
+            density,
+            density_scale,
+            albedo,
+            g,
+            emission: None,
+        }
+    }
+
+    /// Makes the medium glow with `color` scaled by the values in `grid`,
+    /// like the temperature field of a flame.
+    pub fn with_emission(mut self, grid: VoxelGrid, color: Color) -> HeterogeneousMedium {
+        self.emission = Some((grid, color));
+        self
+    }
+
+    fn majorant(&self) -> f64 {
+        self.density_scale * self.density.max_value()
+    }
+
+    fn grid_coords(&self, p: &Point3) -> Point3 {
+        Point3(
+            (p.x() - self.bbox.x.min) / self.bbox.x.size(),
+            (p.y() - self.bbox.y.min) / self.bbox.y.size(),
+            (p.z() - self.bbox.z.min) / self.bbox.z.size(),
+        )
+    }
+
+    fn sigma_t(&self, p: &Point3) -> f64 {
+        self.density_scale * self.density.lookup(&self.grid_coords(p))
+    }
+}
+
+impl Hittable for HeterogeneousMedium {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let majorant = self.majorant();
+        let range = match self.bbox.intersect(r, ray_t) {
+            Some(range) if majorant > 0.0 => range,
+            _ => return false,
+        };
+
+        // Delta tracking: take exponential steps as if the whole box had the
+        // maximum density, and accept each tentative collision with the ratio
+        // of the local density to that maximum.
+        let step = 1.0 / (majorant * r.dir().length());
+        let mut t = range.min;
+        loop {
+            t -= (1.0 - random_f64()).ln() * step;
+            if t >= range.max {
+                return false;
+            }
+            let p = r.at(t);
+            if random_f64() * majorant >= self.sigma_t(&p) {
+                continue;
+            }
+
+            // At a real collision the absorbed fraction of the light is
+            // replaced by whatever the medium emits there.
+            let emission = match &self.emission {
+                Some((grid, color)) => {
+                    (Color(1.0, 1.0, 1.0) - self.albedo)
+                        * *color
+                        * grid.lookup(&self.grid_coords(&p))
+                }
+                None => Color::zero_vec3(),
+            };
+
+            rec.t = t;
+            rec.p = p;
+            rec.set_face_normal(r, &-r.dir()); // arbitrary, but facing the ray
+            rec.mat = Box::new(HenyeyGreenstein::new_emissive(
+                self.albedo,
+                self.g,
+                emission,
+            ));
+            return true;
+        }
+    }
+
+    /// Estimated with ratio tracking. Unlike a delta tracked collision this is
+    /// never all-or-nothing, which keeps shadows through the medium smooth.
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        let majorant = self.majorant();
+        let range = match self.bbox.intersect(r, ray_t) {
+            Some(range) if majorant > 0.0 => range,
+            _ => return 1.0,
+        };
+
+        let step = 1.0 / (majorant * r.dir().length());
+        let mut transmittance = 1.0;
+        let mut t = range.min;
+        loop {
+            t -= (1.0 - random_f64()).ln() * step;
+            if t >= range.max {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.sigma_t(&r.at(t)) / majorant;
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
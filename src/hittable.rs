@@ -2,21 +2,45 @@ use std::sync::Arc;
 
 use crate::materials::material;
 use crate::materials::material::Material;
+use crate::random::random_f64;
+use crate::types::aabb::Aabb;
 use crate::types::interval::Interval;
+use crate::types::light_bounds::LightBounds;
+use crate::types::onb::Onb;
 use crate::types::ray::Ray;
 use crate::types::vec3::Vec3;
 use Vec3 as Point3;
 
+/// Shading normals are kept at least this far above the horizon seen from
+/// the viewer.
+const MIN_SHADING_COSINE: f64 = 0.01;
+
+/// How far along a ray leaving a surface hits start to count, so that it
+/// does not hit the surface it leaves again through rounding error.
+pub const RAY_EPSILON: f64 = 0.001;
+
 // #[derive(Copy,Clone)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
     pub mat: Box<dyn Material + Sync + Send>,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
+    /// How the surface moves with `u` and `v`, zero where the shape has no
+    /// parameterization.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// The frame materials shade in: the tangent `u` follows `dpdu` and the
+    /// shading normal `w` is on the same side as `normal`. It only differs
+    /// from the geometric normal for interpolated or mapped normals.
+    pub shading: Onb,
 }
 
 impl HitRecord {
+    /// Also resets the tangents and the shading frame, so shapes call this
+    /// first and then `set_tangents` and `set_shading_normal` as they can.
     pub fn set_face_normal(&mut self, r: &Ray, outward_normal: &Vec3) {
         self.front_face = r.dir().dot(outward_normal) < 0.0;
         // println!("dot product {}",r.dir().dot(outward_normal));
@@ -24,21 +48,177 @@ impl HitRecord {
         self.normal = match self.front_face {
             true => *outward_normal,
             false => -(*outward_normal),
+        };
+        self.dpdu = Vec3(0.0, 0.0, 0.0);
+        self.dpdv = Vec3(0.0, 0.0, 0.0);
+        self.shading = Onb::new(&self.normal);
+    }
+
+    /// Sets the surface derivatives and turns the shading frame to follow
+    /// `dpdu`.
+    pub fn set_tangents(&mut self, dpdu: Vec3, dpdv: Vec3) {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+        self.shading = Onb::from_tangent(&self.shading.w, &dpdu);
+    }
+
+    /// Shades with `outward_normal` instead of the geometric normal. Where
+    /// it would face away from the viewer it is bent back towards the
+    /// geometric normal until it just faces them, since a material cannot
+    /// shade a surface seen from behind.
+    pub fn set_shading_normal(&mut self, r: &Ray, outward_normal: &Vec3) {
+        let mut n = match self.front_face {
+            true => outward_normal.unit_vector(),
+            false => -outward_normal.unit_vector(),
+        };
+        let wo = -r.dir().unit_vector();
+        let cos_o = wo.dot(&n);
+        if cos_o < MIN_SHADING_COSINE {
+            let cos_g = wo.dot(&self.normal).max(1e-6);
+            n = (n + self.normal * ((MIN_SHADING_COSINE - cos_o) / cos_g)).unit_vector();
         }
+        self.shading = Onb::from_tangent(&n, &self.dpdu);
     }
+
+    /// Whether `dir` is on opposite sides of the geometric and the shading
+    /// surface. Light must not pass between such directions and the hit,
+    /// or it leaks through the surface or is lit from behind.
+    pub fn leaks(&self, dir: &Vec3) -> bool {
+        dir.dot(&self.normal) * dir.dot(&self.shading.w) < 0.0
+    }
+
     pub fn new() -> HitRecord {
         HitRecord {
             p: Point3(0.0, 0.0, 0.0),
             normal: Vec3(0.0, 0.0, 0.0),
             mat: Box::new(material::Nothing {}),
             t: 0.0,
+            u: 0.0,
+            v: 0.0,
             front_face: true,
+            dpdu: Vec3(0.0, 0.0, 0.0),
+            dpdv: Vec3(0.0, 0.0, 0.0),
+            shading: Onb::new(&Vec3(0.0, 0.0, 1.0)),
+        }
+    }
+}
+
+impl Clone for HitRecord {
+    fn clone(&self) -> HitRecord {
+        HitRecord {
+            p: self.p,
+            normal: self.normal,
+            mat: self.mat.my_copy(),
+            t: self.t,
+            u: self.u,
+            v: self.v,
+            front_face: self.front_face,
+            dpdu: self.dpdu,
+            dpdv: self.dpdv,
+            shading: self.shading,
         }
     }
 }
 
+/// A stretch of a ray inside a solid, between the hits where it enters and
+/// where it leaves.
+pub struct Span {
+    pub enter: HitRecord,
+    pub exit: HitRecord,
+}
+
 pub trait Hittable {
     fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool;
+    fn bounding_box(&self) -> Aabb;
+
+    /// Every span of the whole line through `r` that lies inside the object,
+    /// in increasing `t`. Only meaningful for closed objects.
+    ///
+    /// The default walks the line with repeated `hit` calls, pairing each
+    /// front-face hit with the back-face hit that follows it.
+    fn hit_spans(&self, r: &Ray) -> Vec<Span> {
+        let mut spans = Vec::new();
+        let mut enter: Option<HitRecord> = None;
+        let mut t_min = -f64::INFINITY;
+        loop {
+            let mut rec = HitRecord::new();
+            if !self.hit(r, &Interval::new(t_min, f64::INFINITY), &mut rec) {
+                break;
+            }
+            // Step past the hit so a root found twice is not reported again.
+            t_min = rec.t + 1e-9 * rec.t.abs().max(1.0);
+            if rec.front_face {
+                enter = Some(rec);
+            } else if let Some(enter) = enter.take() {
+                spans.push(Span { enter, exit: rec });
+            }
+        }
+        spans
+    }
+
+    /// Fraction of light getting through this object along `r` over `ray_t`,
+    /// for shadow rays. Surfaces block it all, media only some of it.
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        match hit_solid(self, r, ray_t, &mut HitRecord::new()) {
+            true => 0.0,
+            false => 1.0,
+        }
+    }
+
+    /// Whether this object emits light and supports `pdf_value` and `random`.
+    fn is_light(&self) -> bool {
+        false
+    }
+
+    /// Whether this object holds others and passes through the holes cut
+    /// out of their materials itself, with `hit_solid`.
+    fn is_aggregate(&self) -> bool {
+        false
+    }
+
+    /// Adds the lights among this object's children, for aggregates.
+    fn collect_lights(&self, _lights: &mut HittableList) {}
+
+    /// Solid angle density with which `random` picks `r`'s direction from
+    /// `r`'s origin.
+    fn pdf_value(&self, _r: &Ray) -> f64 {
+        0.0
+    }
+
+    /// A direction from `origin` towards a random point on the object.
+    fn random(&self, _origin: &Point3, _time: f64) -> Vec3 {
+        Vec3(1.0, 0.0, 0.0)
+    }
+
+    /// Where the light is, how much power it has and which way it shines,
+    /// for choosing between lights.
+    fn light_bounds(&self) -> LightBounds {
+        LightBounds::omni(self.bounding_box(), 1.0)
+    }
+}
+
+/// `object.hit`, passing through hits on holes cut out of the material, as
+/// aggregates do for what they hold. Each hit is checked only by the
+/// aggregate holding the object directly, in that aggregate's space.
+pub fn hit_solid(
+    object: &(impl Hittable + ?Sized),
+    r: &Ray,
+    ray_t: &Interval,
+    rec: &mut HitRecord,
+) -> bool {
+    if object.is_aggregate() {
+        return object.hit(r, ray_t, rec);
+    }
+    let mut t_min = ray_t.min;
+    let mut temp_rec = HitRecord::new();
+    while object.hit(r, &Interval::new(t_min, ray_t.max), &mut temp_rec) {
+        if !temp_rec.mat.is_cut_out(&temp_rec) {
+            *rec = temp_rec;
+            return true;
+        }
+        t_min = temp_rec.t;
+    }
+    false
 }
 
 pub struct HittableList {
@@ -46,69 +226,22 @@ pub struct HittableList {
 }
 
 impl HittableList {
+    pub fn new() -> HittableList {
+        HittableList {
+            objects: Vec::new(),
+        }
+    }
     pub fn clear(&mut self) {
         self.objects.clear();
     }
     pub fn add(&mut self, object: Arc<dyn Hittable + Sync + Send>) {
         self.objects.push(object);
     }
-    pub fn random_scene(&mut self) {
-        use crate::materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
-        use crate::random::{random_f64, random_f64_with_bounds};
-        use crate::sphere::Sphere;
-        use Vec3 as Color;
-
-        let material_ground = Lambertian {
-            albedo: Vec3(0.5, 0.5, 0.5),
-        };
-        self.add(Arc::new(Sphere::new(
-            Vec3(0.0, -1000.0, -1.0),
-            1000.0,
-            Box::new(material_ground),
-        )));
-
-        let mut a = -11;
-        while a < 11 {
-            let mut b = -11;
-            while b < 11 {
-                let choose_mat = random_f64();
-                let center = Point3::new(
-                    a as f64 + 0.9 * random_f64(),
-                    0.2,
-                    b as f64 + 0.9 * random_f64(),
-                );
-
-                if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                    let sphere_material: Box<dyn Material + Sync + Send>;
-
-                    if choose_mat < 0.8 {
-                        // diffuse
-                        let aldebo = Color::random() * Color::random();
-                        sphere_material = Box::new(Lambertian::new(aldebo));
-                        let center2 = center + Vec3::new(0., random_f64_with_bounds(0., 0.5), 0.);
-
-                        self.add(Arc::new(Sphere::new_moving(
-                            center,
-                            center2,
-                            0.2,
-                            sphere_material,
-                        )));
-                    } else if choose_mat < 0.95 {
-                        // metal
-                        let aldebo = Color::random_with_bounds(0.5, 1.0);
-                        let fuzz = random_f64_with_bounds(0.0, 0.5);
-                        sphere_material = Box::new(Metal::new(aldebo, fuzz));
-                        self.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
-                    } else {
-                        // glass
-                        sphere_material = Box::new(Dielectric::new(1.5));
-                        self.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
-                    }
-                }
-                b += 1;
-            }
-            a += 1;
-        }
+    /// Every light in the list, including those inside nested aggregates.
+    pub fn lights(&self) -> HittableList {
+        let mut lights = HittableList::new();
+        self.collect_lights(&mut lights);
+        lights
     }
 }
 
@@ -118,7 +251,8 @@ impl Hittable for HittableList {
         let mut closest_so_far = ray_t.max;
         for object in self.objects.iter() {
             let mut temp_rec = HitRecord::new();
-            if object.hit(
+            if hit_solid(
+                object.as_ref(),
                 r,
                 &Interval {
                     min: ray_t.min,
@@ -133,4 +267,49 @@ impl Hittable for HittableList {
         }
         return hit_anything;
     }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        let mut transmittance = 1.0;
+        for object in self.objects.iter() {
+            transmittance *= object.transmittance(r, ray_t);
+            if transmittance == 0.0 {
+                break;
+            }
+        }
+        transmittance
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.objects.iter().fold(Aabb::empty(), |bbox, object| {
+            Aabb::surrounding(&bbox, &object.bounding_box())
+        })
+    }
+
+    fn is_aggregate(&self) -> bool {
+        true
+    }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        for object in self.objects.iter() {
+            if object.is_light() {
+                lights.add(object.clone());
+            } else {
+                object.collect_lights(lights);
+            }
+        }
+    }
+
+    // As a light list, pick one of the objects uniformly.
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self.objects.iter().map(|object| object.pdf_value(r)).sum();
+        sum / self.objects.len() as f64
+    }
+
+    fn random(&self, origin: &Point3, time: f64) -> Vec3 {
+        let index = (random_f64() * self.objects.len() as f64) as usize;
+        self.objects[index.min(self.objects.len() - 1)].random(origin, time)
+    }
 }
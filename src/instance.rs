@@ -0,0 +1,240 @@
+use std::sync::Arc;
+
+use crate::hittable::{hit_solid, HitRecord, Hittable, HittableList, Span};
+use crate::types::aabb::Aabb;
+use crate::types::animated_transform::AnimatedTransform;
+use crate::types::interval::Interval;
+use crate::types::light_bounds::LightBounds;
+use crate::types::onb::Onb;
+use crate::types::ray::Ray;
+use crate::types::transform::Transform;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// Places a shared object in the world through an affine transform. The
+/// wrapped object is only referenced, so the same geometry can be instanced
+/// many times for the cost of one transform each.
+///
+/// Instances made with `new_animated` pick their transform from the ray's
+/// time, which blurs any motion the keyframes describe.
+///
+/// Lights can be instanced too: each light inside the object is sampled
+/// through the instance's transform.
+pub struct Instance {
+    object: Arc<dyn Hittable + Sync + Send>,
+    transform: Transform,
+    motion: Option<AnimatedTransform>,
+    bbox: Aabb,
+}
+
+impl Instance {
+    pub fn new(object: Arc<dyn Hittable + Sync + Send>, transform: Transform) -> Instance {
+        let bbox = transform.apply_aabb(&object.bounding_box());
+        Instance {
+            object,
+            transform,
+            motion: None,
+            bbox,
+        }
+    }
+
+    pub fn new_animated(
+        object: Arc<dyn Hittable + Sync + Send>,
+        motion: AnimatedTransform,
+    ) -> Instance {
+        let bbox = motion.motion_bounds(&object.bounding_box());
+        Instance {
+            object,
+            transform: Transform::identity(),
+            motion: Some(motion),
+            bbox,
+        }
+    }
+
+    fn transform_at(&self, time: f64) -> Transform {
+        match &self.motion {
+            Some(motion) => motion.interpolate(time),
+            None => self.transform,
+        }
+    }
+
+    /// The same placement for another object, for instancing the lights
+    /// held inside this one.
+    fn with_object(&self, object: Arc<dyn Hittable + Sync + Send>) -> Instance {
+        let bbox = match &self.motion {
+            Some(motion) => motion.motion_bounds(&object.bounding_box()),
+            None => self.transform.apply_aabb(&object.bounding_box()),
+        };
+        Instance {
+            object,
+            transform: self.transform,
+            motion: self.motion.clone(),
+            bbox,
+        }
+    }
+}
+
+// How much `transform` stretches solid angle around the unit direction `dir`:
+// a linear map M takes a small cone around `dir` to one |det M| / |M dir|^3
+// times its size.
+fn solid_angle_scale(transform: &Transform, dir: &Vec3) -> f64 {
+    let x = transform.apply_vector(&Vec3(1.0, 0.0, 0.0));
+    let y = transform.apply_vector(&Vec3(0.0, 1.0, 0.0));
+    let z = transform.apply_vector(&Vec3(0.0, 0.0, 1.0));
+    let det = x.dot(&y.cross(&z)).abs();
+    det / transform.apply_vector(dir).length().powi(3)
+}
+
+// Moves a hit found in object space back into world space. The normal's side
+// relative to the ray is preserved by the transform, so front_face stays valid.
+fn to_world(transform: &Transform, rec: &mut HitRecord) {
+    rec.p = transform.apply_point(&rec.p);
+    rec.normal = transform.apply_normal(&rec.normal).unit_vector();
+    rec.dpdu = transform.apply_vector(&rec.dpdu);
+    rec.dpdv = transform.apply_vector(&rec.dpdv);
+    let shading_normal = transform.apply_normal(&rec.shading.w);
+    rec.shading = Onb::from_tangent(&shading_normal, &rec.dpdu);
+}
+
+impl Hittable for Instance {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        // Intersect in object space, then move the hit back into world space.
+        let transform = self.transform_at(r.time());
+        let object_ray = transform.inverse().apply_ray(r);
+        if !hit_solid(self.object.as_ref(), &object_ray, ray_t, rec) {
+            return false;
+        }
+        to_world(&transform, rec);
+        true
+    }
+
+    fn transmittance(&self, r: &Ray, ray_t: &Interval) -> f64 {
+        let object_ray = self.transform_at(r.time()).inverse().apply_ray(r);
+        self.object.transmittance(&object_ray, ray_t)
+    }
+
+    fn is_aggregate(&self) -> bool {
+        true
+    }
+
+    fn is_light(&self) -> bool {
+        self.object.is_light()
+    }
+
+    fn collect_lights(&self, lights: &mut HittableList) {
+        let mut inner = HittableList::new();
+        self.object.collect_lights(&mut inner);
+        for light in inner.objects {
+            lights.add(Arc::new(self.with_object(light)));
+        }
+    }
+
+    // The object's density over directions in its own space, carried over to
+    // the world's directions.
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        let to_object = self.transform_at(r.time()).inverse();
+        let object_ray = to_object.apply_ray(r);
+        self.object.pdf_value(&object_ray) * solid_angle_scale(&to_object, &r.dir().unit_vector())
+    }
+
+    fn random(&self, origin: &Point3, time: f64) -> Vec3 {
+        let transform = self.transform_at(time);
+        let object_origin = transform.inverse().apply_point(origin);
+        transform.apply_vector(&self.object.random(&object_origin, time))
+    }
+
+    // The emission cone does not survive shears and uneven scales, so only
+    // the box and the power are kept, which is exact for rigid motions.
+    fn light_bounds(&self) -> LightBounds {
+        LightBounds::omni(self.bbox, self.object.light_bounds().phi)
+    }
+
+    fn hit_spans(&self, r: &Ray) -> Vec<Span> {
+        let transform = self.transform_at(r.time());
+        let object_ray = transform.inverse().apply_ray(r);
+        let mut spans = self.object.hit_spans(&object_ray);
+        for span in spans.iter_mut() {
+            to_world(&transform, &mut span.enter);
+            to_world(&transform, &mut span.exit);
+        }
+        spans
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::sphere::Sphere;
+
+    // A sphere light stretched into an ellipsoid and turned.
+    fn stretched_lamp() -> Instance {
+        let lamp = Sphere::new(
+            Point3(0.0, 0.0, 0.0),
+            1.0,
+            Box::new(DiffuseLight::new(Vec3(4.0, 4.0, 4.0))),
+        );
+        let transform = Transform::identity()
+            .scale(Vec3(2.0, 0.5, 1.0))
+            .rotate(30.0, Vec3(1.0, 1.0, 0.0))
+            .translate(Vec3(0.0, 1.0, -4.0));
+        Instance::new(Arc::new(lamp), transform)
+    }
+
+    #[test]
+    fn lights_inside_instances_are_collected() {
+        let mut group = HittableList::new();
+        group.add(Arc::new(stretched_lamp()));
+        let mut world = HittableList::new();
+        world.add(Arc::new(Instance::new(
+            Arc::new(group),
+            Transform::from_translation(Vec3(1.0, 0.0, 0.0)),
+        )));
+        let lights = world.lights();
+        assert_eq!(lights.objects.len(), 1);
+        assert!(lights.objects[0].is_light());
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_over_the_sphere() {
+        let lamp = stretched_lamp();
+        let origin = Point3(0.3, -0.2, 0.5);
+
+        // Directions on rings of equal area, all weighing the same.
+        let (rings, steps) = (1000, 1000);
+        let mut total = 0.0;
+        for i in 0..rings {
+            let z = 1.0 - 2.0 * (i as f64 + 0.5) / rings as f64;
+            let radius = (1.0 - z * z).sqrt();
+            for j in 0..steps {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / steps as f64;
+                let dir = Vec3(radius * phi.cos(), radius * phi.sin(), z);
+                total += lamp.pdf_value(&Ray::new(origin, dir));
+            }
+        }
+        let integral = total * 4.0 * PI / (rings * steps) as f64;
+        assert!((integral - 1.0).abs() < 1e-2, "{integral}");
+    }
+
+    #[test]
+    fn samples_point_at_the_instance() {
+        let lamp = stretched_lamp();
+        let origin = Point3(0.3, -0.2, 0.5);
+        for _ in 0..1000 {
+            let r = Ray::new(origin, lamp.random(&origin, 0.0));
+            assert!(lamp.hit(
+                &r,
+                &Interval::new(0.001, f64::INFINITY),
+                &mut HitRecord::new()
+            ));
+            assert!(lamp.pdf_value(&r) > 0.0);
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use crate::hittable::{hit_solid, HitRecord, Hittable, HittableList, RAY_EPSILON};
+use crate::random::random_f64;
+use crate::types::interval::Interval;
+use crate::types::light_bounds::LightBounds;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// How next-event estimation picks which light to sample.
+#[derive(Clone, Copy)]
+pub enum LightSelection {
+    /// Every light is equally likely.
+    Uniform,
+    /// Lights are picked in proportion to the power they give off.
+    Power,
+    /// Walks a BVH over the lights, at each node favoring the child likely
+    /// to contribute more to the shading point, judging by its distance,
+    /// power and the directions it shines in.
+    Bvh,
+}
+
+/// A node of the light BVH. The first child of an interior node comes right
+/// after it in the node array.
+struct LightNode {
+    bounds: LightBounds,
+    /// The light's index for leaves, the second child's for interior nodes.
+    index: usize,
+    leaf: bool,
+}
+
+/// Picks lights for next-event estimation, treating all of them together as
+/// one light whose directions are a mixture of theirs.
+pub struct LightSampler {
+    pub selection: LightSelection,
+    lights: Vec<Arc<dyn Hittable + Sync + Send>>,
+    power_pmf: Vec<f64>,
+    power_cdf: Vec<f64>,
+    nodes: Vec<LightNode>,
+}
+
+impl LightSampler {
+    pub fn new(lights: HittableList, selection: LightSelection) -> LightSampler {
+        let lights = lights.objects;
+        let bounds: Vec<LightBounds> = lights.iter().map(|light| light.light_bounds()).collect();
+
+        let total_power: f64 = bounds.iter().map(|b| b.phi.max(0.0)).sum();
+        let power_pmf: Vec<f64> = if total_power > 0.0 {
+            bounds
+                .iter()
+                .map(|b| b.phi.max(0.0) / total_power)
+                .collect()
+        } else {
+            vec![1.0 / lights.len() as f64; lights.len()]
+        };
+        let power_cdf = power_pmf
+            .iter()
+            .scan(0.0, |sum, p| {
+                *sum += p;
+                Some(*sum)
+            })
+            .collect();
+
+        let mut nodes = Vec::with_capacity(2 * lights.len());
+        let mut indices: Vec<usize> = (0..lights.len()).collect();
+        if !indices.is_empty() {
+            build_nodes(&mut nodes, &bounds, &mut indices);
+        }
+
+        LightSampler {
+            selection,
+            lights,
+            power_pmf,
+            power_cdf,
+            nodes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// The closest light along `r` over `ray_t`, ignoring everything else.
+    pub fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let mut hit_anything = false;
+        let mut closest_so_far = ray_t.max;
+        for light in self.lights.iter() {
+            if hit_solid(
+                light.as_ref(),
+                r,
+                &Interval::new(ray_t.min, closest_so_far),
+                rec,
+            ) {
+                hit_anything = true;
+                closest_so_far = rec.t;
+            }
+        }
+        hit_anything
+    }
+
+    /// A direction from `origin` towards a random point on one of the
+    /// lights, or `None` if none of them can light `origin`.
+    pub fn random(&self, origin: &Point3, time: f64) -> Option<Vec3> {
+        let light = self.pick(origin)?;
+        Some(self.lights[light].random(origin, time))
+    }
+
+    /// Index of a light picked at random to light `origin`.
+    fn pick(&self, origin: &Point3) -> Option<usize> {
+        let light = match self.selection {
+            LightSelection::Uniform => {
+                ((random_f64() * self.lights.len() as f64) as usize).min(self.lights.len() - 1)
+            }
+            LightSelection::Power => {
+                let u = random_f64();
+                self.power_cdf
+                    .partition_point(|&c| c <= u)
+                    .min(self.lights.len() - 1)
+            }
+            LightSelection::Bvh => {
+                let mut index = 0;
+                while !self.nodes[index].leaf {
+                    let (p_first, p_second) = self.child_probabilities(index, origin);
+                    if p_first + p_second == 0.0 {
+                        return None;
+                    }
+                    index = if random_f64() < p_first {
+                        index + 1
+                    } else {
+                        self.nodes[index].index
+                    };
+                }
+                self.nodes[index].index
+            }
+        };
+        Some(light)
+    }
+
+    /// Solid angle density with which `random` picks `r`'s direction from
+    /// `r`'s origin, summed over every light the ray could reach.
+    pub fn pdf_value(&self, r: &Ray) -> f64 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+        let origin = r.origin();
+        let ray_t = Interval::new(RAY_EPSILON, f64::INFINITY);
+
+        // Only lights whose boxes the ray passes through can have a density,
+        // so walk the tree the same way `random` does and skip the rest.
+        let mut pdf = 0.0;
+        let mut stack = vec![(0, 1.0)];
+        while let Some((index, probability)) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.bounds.hit(r, &ray_t) {
+                continue;
+            }
+            if node.leaf {
+                let pmf = match self.selection {
+                    LightSelection::Uniform => 1.0 / self.lights.len() as f64,
+                    LightSelection::Power => self.power_pmf[node.index],
+                    LightSelection::Bvh => probability,
+                };
+                pdf += pmf * self.lights[node.index].pdf_value(r);
+                continue;
+            }
+            let (p_first, p_second) = match self.selection {
+                LightSelection::Bvh => self.child_probabilities(index, &origin),
+                _ => (1.0, 1.0),
+            };
+            if p_first > 0.0 {
+                stack.push((index + 1, probability * p_first));
+            }
+            if p_second > 0.0 {
+                stack.push((node.index, probability * p_second));
+            }
+        }
+        pdf
+    }
+
+    fn child_probabilities(&self, index: usize, p: &Point3) -> (f64, f64) {
+        let first = self.nodes[index + 1].bounds.importance(p);
+        let second = self.nodes[self.nodes[index].index].bounds.importance(p);
+        let total = first + second;
+        if total <= 0.0 {
+            return (0.0, 0.0);
+        }
+        (first / total, second / total)
+    }
+}
+
+/// Appends the subtree over the lights at `indices`, splitting at the median
+/// centroid along the longest axis of their centroids' extent.
+fn build_nodes(nodes: &mut Vec<LightNode>, bounds: &[LightBounds], indices: &mut [usize]) {
+    let node_bounds = indices[1..].iter().fold(bounds[indices[0]], |b, &i| {
+        LightBounds::union(&b, &bounds[i])
+    });
+    let node = nodes.len();
+    if indices.len() == 1 {
+        nodes.push(LightNode {
+            bounds: node_bounds,
+            index: indices[0],
+            leaf: true,
+        });
+        return;
+    }
+    nodes.push(LightNode {
+        bounds: node_bounds,
+        index: 0,
+        leaf: false,
+    });
+
+    let centroid = |i: usize, axis: usize| {
+        let c = bounds[i].centroid();
+        match axis {
+            0 => c.x(),
+            1 => c.y(),
+            _ => c.z(),
+        }
+    };
+    let extent = |axis: usize| {
+        let (min, max) = indices
+            .iter()
+            .fold((f64::INFINITY, -f64::INFINITY), |(lo, hi), &i| {
+                let c = centroid(i, axis);
+                (lo.min(c), hi.max(c))
+            });
+        max - min
+    };
+    let axis = (0..3)
+        .max_by(|&a, &b| extent(a).partial_cmp(&extent(b)).unwrap_or(Ordering::Equal))
+        .unwrap();
+    indices.sort_by(|&a, &b| {
+        centroid(a, axis)
+            .partial_cmp(&centroid(b, axis))
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mid = indices.len() / 2;
+    let (lower, upper) = indices.split_at_mut(mid);
+    build_nodes(nodes, bounds, lower);
+    nodes[node].index = nodes.len();
+    build_nodes(nodes, bounds, upper);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::diffuse_light::DiffuseLight;
+    use crate::sphere::Sphere;
+    use crate::types::color::luminance;
+
+    /// Lamps of different power, each in its own direction from the origin.
+    fn lamps() -> Vec<Arc<Sphere>> {
+        [
+            (Point3(3.0, 0.0, 0.0), 0.2, 10.0),
+            (Point3(0.0, 4.0, 0.0), 0.5, 2.0),
+            (Point3(0.0, 0.0, -6.0), 0.3, 40.0),
+            (Point3(-2.0, -2.0, 2.0), 0.4, 1.0),
+        ]
+        .into_iter()
+        .map(|(center, radius, brightness)| {
+            Arc::new(Sphere::new(
+                center,
+                radius,
+                Box::new(DiffuseLight::new(Vec3(1.0, 1.0, 1.0) * brightness)),
+            ))
+        })
+        .collect()
+    }
+
+    #[test]
+    fn pdf_matches_how_often_each_light_is_picked() {
+        let origin = Point3(0.0, 0.0, 0.0);
+        let n = 100_000;
+        for selection in [
+            LightSelection::Uniform,
+            LightSelection::Power,
+            LightSelection::Bvh,
+        ] {
+            let lamps = lamps();
+            let mut list = HittableList::new();
+            for lamp in lamps.iter() {
+                list.add(lamp.clone());
+            }
+            let sampler = LightSampler::new(list, selection);
+
+            let mut picks = vec![0; lamps.len()];
+            for _ in 0..n {
+                picks[sampler.pick(&origin).unwrap()] += 1;
+            }
+            // Towards a lamp's center only that lamp has a density, so the
+            // sampler's is the chance of picking it times the lamp's own.
+            for (lamp, count) in lamps.iter().zip(picks) {
+                let r = Ray::new(origin, lamp.light_bounds().centroid() - origin);
+                let pmf = sampler.pdf_value(&r) / lamp.pdf_value(&r);
+                let frequency = count as f64 / n as f64;
+                assert!((frequency - pmf).abs() < 0.01, "{frequency} vs {pmf}");
+            }
+        }
+    }
+
+    /// Small lamps on a grid above the floor, a few much brighter than the rest.
+    fn lamp_grid() -> HittableList {
+        let mut lamps = HittableList::new();
+        for i in 0..20 {
+            for j in 0..20 {
+                let brightness = if (i * 20 + j) % 37 == 0 { 400.0 } else { 20.0 };
+                lamps.add(Arc::new(Sphere::new(
+                    Point3(i as f64 * 1.5, 1.0, j as f64 * 1.5),
+                    0.05,
+                    Box::new(DiffuseLight::new(Vec3(1.0, 1.0, 1.0) * brightness)),
+                )));
+            }
+        }
+        lamps
+    }
+
+    #[test]
+    fn light_bvh_has_less_variance_than_uniform_picks() {
+        let p = Point3(5.0, 0.0, 8.0);
+
+        // Mean and variance of the light reaching a floor point, weighted by
+        // the cosine, as next-event estimation would estimate it.
+        let estimate = |selection: LightSelection| {
+            let sampler = LightSampler::new(lamp_grid(), selection);
+            let n = 4_000;
+            let (mut sum, mut sum_squares) = (0.0, 0.0);
+            for _ in 0..n {
+                let Some(dir) = sampler.random(&p, 0.0) else {
+                    continue;
+                };
+                let r = Ray::new(p, dir);
+                let mut rec = HitRecord::new();
+                if !sampler.hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rec) {
+                    continue;
+                }
+                let cosine = dir.unit_vector().y().max(0.0);
+                let value = luminance(&rec.mat.emitted(&r, &rec)) * cosine / sampler.pdf_value(&r);
+                sum += value;
+                sum_squares += value * value;
+            }
+            let mean = sum / n as f64;
+            (mean, sum_squares / n as f64 - mean * mean)
+        };
+
+        let (_, uniform) = estimate(LightSelection::Uniform);
+        let (_, bvh) = estimate(LightSelection::Bvh);
+        assert!(bvh < uniform, "{bvh} vs {uniform}");
+    }
+}
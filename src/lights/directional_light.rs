@@ -0,0 +1,92 @@
+use std::f64::consts::PI;
+
+use super::light::{Light, LightSample};
+use crate::random::random_f64;
+use crate::types::onb::Onb;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+use Vec3 as Point3;
+
+/// Light from a source infinitely far away, such as the sun. With a zero
+/// angular diameter all of it arrives from one direction and casts hard
+/// shadows; otherwise it comes from a small disk in the sky and the shadows
+/// get soft edges.
+pub struct DirectionalLight {
+    /// Unit vector pointing from the scene towards the light.
+    pub direction: Vec3,
+    /// Irradiance on a surface facing the light.
+    pub irradiance: Color,
+    cos_half_angle: f64,
+}
+
+impl DirectionalLight {
+    /// `angular_diameter` is in degrees; the sun's is about 0.53.
+    pub fn new(direction: Vec3, irradiance: Color, angular_diameter: f64) -> DirectionalLight {
+        DirectionalLight {
+            direction: direction.unit_vector(),
+            irradiance,
+            cos_half_angle: (angular_diameter / 2.0).to_radians().cos(),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _p: &Point3) -> Option<LightSample> {
+        if self.cos_half_angle >= 1.0 {
+            return Some(LightSample {
+                dir: self.direction,
+                distance: f64::INFINITY,
+                radiance: self.irradiance,
+            });
+        }
+
+        // Uniform over the cone of directions the disk covers. The disk's
+        // radiance is the irradiance spread over its projected solid angle,
+        // and dividing by the uniform density multiplies by the solid angle.
+        let cos_theta = 1.0 - random_f64() * (1.0 - self.cos_half_angle);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = 2.0 * PI * random_f64();
+        let dir = Onb::new(&self.direction).local(&Vec3(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+        let solid_angle = 2.0 * PI * (1.0 - self.cos_half_angle);
+        let projected_solid_angle = PI * (1.0 - self.cos_half_angle * self.cos_half_angle);
+        Some(LightSample {
+            dir,
+            distance: f64::INFINITY,
+            radiance: self.irradiance * solid_angle / projected_solid_angle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hard_light_comes_from_one_direction() {
+        let light = DirectionalLight::new(Vec3(0.0, 2.0, 0.0), Color(3.0, 3.0, 3.0), 0.0);
+        let sample = light.sample(&Point3(1.0, 2.0, 3.0)).unwrap();
+        assert!((sample.dir - Vec3(0.0, 1.0, 0.0)).length() < 1e-12);
+        assert_eq!(sample.distance, f64::INFINITY);
+        assert_eq!(sample.radiance.x(), 3.0);
+    }
+
+    #[test]
+    fn soft_light_gives_its_irradiance_to_a_surface_facing_it() {
+        // A disk 30 degrees across, wide enough for the cosine to matter.
+        let light = DirectionalLight::new(Vec3(0.0, 1.0, 0.0), Color(3.0, 3.0, 3.0), 30.0);
+        let cos_half_angle = 15.0_f64.to_radians().cos();
+        let n = 100000;
+        let mut irradiance = 0.0;
+        for _ in 0..n {
+            let sample = light.sample(&Point3(0.0, 0.0, 0.0)).unwrap();
+            assert!(sample.dir.y() >= cos_half_angle - 1e-12);
+            irradiance += sample.radiance.x() * sample.dir.y() / n as f64;
+        }
+        assert!((irradiance - 3.0).abs() < 1e-2, "{irradiance}");
+    }
+}
@@ -0,0 +1,89 @@
+use super::light::{Light, LightSample};
+use crate::types::ies_profile::IesProfile;
+use crate::types::onb::Onb;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+use Vec3 as Point3;
+
+/// A point light whose intensity varies with direction following a measured
+/// photometric profile, such as a downlight or a wall washer.
+pub struct GoniometricLight {
+    pub position: Point3,
+    /// Intensity in the profile's brightest direction.
+    pub intensity: Color,
+    profile: IesProfile,
+    /// `w` is the profile's straight down, `u` its zero horizontal angle.
+    frame: Onb,
+}
+
+impl GoniometricLight {
+    pub fn new(
+        position: Point3,
+        down: Vec3,
+        intensity: Color,
+        profile: IesProfile,
+    ) -> GoniometricLight {
+        GoniometricLight {
+            position,
+            intensity,
+            profile,
+            frame: Onb::new(&down),
+        }
+    }
+}
+
+impl Light for GoniometricLight {
+    fn sample(&self, p: &Point3) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        let distance = distance_squared.sqrt();
+        let dir = to_light / distance;
+
+        // Angles of the direction the light leaves in, in the profile's frame.
+        let out = -dir;
+        let vertical = out.dot(&self.frame.w).clamp(-1.0, 1.0).acos().to_degrees();
+        let horizontal = out
+            .dot(&self.frame.v)
+            .atan2(out.dot(&self.frame.u))
+            .to_degrees();
+
+        let max = self.profile.max_candela();
+        if max <= 0.0 {
+            return None;
+        }
+        let scale = self.profile.candela(vertical, horizontal) / max;
+        if scale <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            dir,
+            distance,
+            radiance: self.intensity * scale / distance_squared,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intensity_follows_the_profile() {
+        let profile =
+            IesProfile::parse("TILT=NONE\n1 1000 1 3 1 1 2 0 0 0\n1 1 0\n0 45 90\n0\n100 50 0\n")
+                .unwrap();
+        let light = GoniometricLight::new(
+            Point3(0.0, 2.0, 0.0),
+            Vec3(0.0, -1.0, 0.0),
+            Color(4.0, 4.0, 4.0),
+            profile,
+        );
+        let straight_down = light.sample(&Point3(0.0, 0.0, 0.0)).unwrap();
+        assert!((straight_down.radiance.x() - 1.0).abs() < 1e-12);
+        // 45 degrees off the axis the profile is at half strength.
+        let aside = light.sample(&Point3(2.0, 0.0, 0.0)).unwrap();
+        assert!((aside.radiance.x() - 4.0 * 0.5 / 8.0).abs() < 1e-12);
+        assert!(light.sample(&Point3(0.0, 4.0, 0.0)).is_none());
+    }
+}
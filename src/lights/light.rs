@@ -0,0 +1,23 @@
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+use Vec3 as Point3;
+
+/// Light arriving at a point from one sampled direction towards a light.
+pub struct LightSample {
+    /// Unit direction from the shaded point towards the light.
+    pub dir: Vec3,
+    /// Distance to the light along `dir`; infinite for directional lights.
+    pub distance: f64,
+    /// Incident radiance divided by the density the direction was picked
+    /// with, so the direct light is the BSDF times the cosine times this.
+    pub radiance: Color,
+}
+
+/// A light that is not part of the geometry. Rays bouncing around the scene
+/// never hit it, so it is only ever reached by sampling it directly.
+pub trait Light {
+    /// Picks a direction from `p` towards the light, or `None` if no light
+    /// from it reaches `p`.
+    fn sample(&self, p: &Point3) -> Option<LightSample>;
+}
@@ -0,0 +1,5 @@
+pub mod directional_light;
+pub mod goniometric_light;
+pub mod light;
+pub mod point_light;
+pub mod spot_light;
@@ -0,0 +1,51 @@
+use super::light::{Light, LightSample};
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+use Vec3 as Point3;
+
+/// Light shining equally in all directions from a single point, falling off
+/// with the square of the distance.
+pub struct PointLight {
+    pub position: Point3,
+    /// Radiant intensity, the power per unit solid angle.
+    pub intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point3, intensity: Color) -> PointLight {
+        PointLight {
+            position,
+            intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, p: &Point3) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        let distance = distance_squared.sqrt();
+        Some(LightSample {
+            dir: to_light / distance,
+            distance,
+            radiance: self.intensity / distance_squared,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_falls_off_with_the_square_of_the_distance() {
+        let light = PointLight::new(Point3(0.0, 4.0, 0.0), Color(8.0, 8.0, 8.0));
+        let near = light.sample(&Point3(0.0, 2.0, 0.0)).unwrap();
+        let far = light.sample(&Point3(0.0, 0.0, 0.0)).unwrap();
+        assert!((near.dir - Vec3(0.0, 1.0, 0.0)).length() < 1e-12);
+        assert_eq!((near.distance, far.distance), (2.0, 4.0));
+        assert_eq!(near.radiance.x(), 2.0);
+        assert_eq!(far.radiance.x(), 0.5);
+    }
+}
@@ -0,0 +1,100 @@
+use super::light::{Light, LightSample};
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+use Vec3 as Point3;
+
+/// A point light limited to a cone, at full strength up to `falloff_start`
+/// degrees off its axis and fading smoothly to nothing at `cone_angle`.
+pub struct SpotLight {
+    pub position: Point3,
+    /// Unit vector along the cone's axis.
+    pub axis: Vec3,
+    pub intensity: Color,
+    cos_cone_angle: f64,
+    cos_falloff_start: f64,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point3,
+        target: Point3,
+        intensity: Color,
+        cone_angle: f64,
+        falloff_start: f64,
+    ) -> SpotLight {
+        SpotLight {
+            position,
+            axis: (target - position).unit_vector(),
+            intensity,
+            cos_cone_angle: cone_angle.to_radians().cos(),
+            cos_falloff_start: falloff_start.min(cone_angle).to_radians().cos(),
+        }
+    }
+
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_falloff_start {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_cone_angle {
+            return 0.0;
+        }
+        let x = (cos_theta - self.cos_cone_angle) / (self.cos_falloff_start - self.cos_cone_angle);
+        x * x * (3.0 - 2.0 * x)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, p: &Point3) -> Option<LightSample> {
+        let to_light = self.position - *p;
+        let distance_squared = to_light.length_squared();
+        let distance = distance_squared.sqrt();
+        let dir = to_light / distance;
+
+        let falloff = self.falloff(-dir.dot(&self.axis));
+        if falloff <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            dir,
+            distance,
+            radiance: self.intensity * falloff / distance_squared,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Shines straight down from 1 unit up, fading from 20 to 40 degrees.
+    fn spot() -> SpotLight {
+        SpotLight::new(
+            Point3(0.0, 1.0, 0.0),
+            Point3(0.0, 0.0, 0.0),
+            Color(1.0, 1.0, 1.0),
+            40.0,
+            20.0,
+        )
+    }
+
+    // Radiance at the point on the ground `degrees` off the axis.
+    fn radiance_at(degrees: f64) -> f64 {
+        let offset = degrees.to_radians().tan();
+        let p = Point3(offset, 0.0, 0.0);
+        let distance_squared = 1.0 + offset * offset;
+        spot()
+            .sample(&p)
+            .map_or(0.0, |s| s.radiance.x() * distance_squared)
+    }
+
+    #[test]
+    fn cone_fades_smoothly_to_its_edge() {
+        assert!((radiance_at(0.0) - 1.0).abs() < 1e-12);
+        assert!((radiance_at(19.0) - 1.0).abs() < 1e-12);
+        assert!(radiance_at(25.0) < 1.0 && radiance_at(25.0) > radiance_at(35.0));
+        assert!(radiance_at(39.0) > 0.0 && radiance_at(39.0) < 0.02);
+        assert_eq!(radiance_at(41.0), 0.0);
+        assert!(spot().sample(&Point3(0.0, 2.0, 0.0)).is_none());
+    }
+}
@@ -1,73 +1,51 @@
+mod bvh;
 mod camera;
+mod cone;
+mod constant_medium;
+mod csg;
+mod cylinder;
 mod file_io;
+mod fog;
+mod heterogeneous_medium;
 mod hittable;
+mod hyperboloid;
+mod instance;
+mod light_sampler;
+mod lights;
 mod materials;
+mod math;
+mod paraboloid;
+mod perlin;
+mod quad;
 mod random;
 mod renderer;
+mod scenes;
+mod sky;
 mod sphere;
+mod subsurface_volume;
+mod textures;
+mod torus;
+mod triangle;
 mod types;
 
 use crate::file_io::create_file;
-use std::sync::Arc;
-
-use crate::materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
 use crate::renderer::Renderer;
-use crate::sphere::Sphere;
-use crate::types::vec3::Vec3;
-
-fn main() {
-    // World
-    let mut world = hittable::HittableList {
-        objects: Vec::new(),
-    };
-
-    world.random_scene();
-
-    let material1 = Dielectric::new(1.5);
-    world.add(Arc::new(Sphere::new(
-        Vec3(0.0, 1.0, 0.0),
-        1.0,
-        Box::new(material1),
-    )));
-    let materal2 = Lambertian::new(Vec3(0.4, 0.2, 0.1));
-    world.add(Arc::new(Sphere::new(
-        Vec3(-4.0, 1.0, 0.0),
-        1.0,
-        Box::new(materal2),
-    )));
-    let material3 = Metal::new(Vec3(0.7, 0.6, 0.5), 0.0);
-    world.add(Arc::new(Sphere::new(
-        Vec3(4.0, 1.0, 0.0),
-        1.0,
-        Box::new(material3),
-    )));
+use std::io;
+use std::sync::Arc;
 
-    let lookfrom = Vec3(13.0, 2.0, 3.0);
-    let lookat = Vec3(0.0, 0.0, 0.0);
-    let vup = Vec3(0.0, 1.0, 0.0);
-    let dist_to_focus = 10.0;
-    let aperture = 0.1;
+// Which demo scene to render, by its number in `scenes::build`.
+const SCENE: i32 = 0;
 
-    // Camera
-    let camera = camera::Camera::new(
-        20.0,
-        16.0 / 9.0,
-        400,
-        20,
-        50,
-        &lookfrom,
-        &lookat,
-        &vup,
-        aperture,
-        dist_to_focus,
-    );
+fn main() -> io::Result<()> {
+    let scene = scenes::build(SCENE)?;
 
     let mut renderer = Renderer::new(
         20,
-        Arc::new(world),
-        Arc::new(camera),
+        Arc::new(scene.world),
+        Arc::new(scene.camera),
         create_file("out2".to_string()),
     );
     renderer.render();
     renderer.output_image();
+    Ok(())
 }
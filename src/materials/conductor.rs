@@ -0,0 +1,228 @@
+use super::material::Material;
+use super::microfacet::{fresnel_conductor, reflect, TrowbridgeReitz};
+use super::thin_film::ThinFilm;
+use crate::{
+    hittable::HitRecord,
+    random::random_f64,
+    types::{ray::Ray, spectrum::reflectance_to_rgb, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// A metal with a rough GGX surface and the Fresnel reflectance of its
+/// complex index of refraction `eta + i k`, given per color channel.
+///
+/// Anisotropic roughness runs along the tangent of the shading frame, which
+/// follows the direction `u` grows in on the surface.
+pub struct Conductor {
+    pub eta: Color,
+    pub k: Color,
+    pub distribution: TrowbridgeReitz,
+    /// Coating on top, like oil or an oxide layer.
+    pub film: Option<ThinFilm>,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, alpha_x: f64, alpha_y: f64) -> Conductor {
+        Conductor {
+            eta,
+            k,
+            distribution: TrowbridgeReitz::new(alpha_x, alpha_y),
+            film: None,
+        }
+    }
+
+    /// Coats the metal with a thin film. Without a spectral ray the
+    /// interference colors are averaged into RGB.
+    pub fn with_film(mut self, film: ThinFilm) -> Conductor {
+        self.film = Some(film);
+        self
+    }
+
+    // Indices of refraction at 650, 550 and 450 nm.
+
+    pub fn gold(alpha: f64) -> Conductor {
+        Conductor::new(
+            Color(0.143, 0.374, 1.442),
+            Color(3.983, 2.385, 1.603),
+            alpha,
+            alpha,
+        )
+    }
+
+    pub fn copper(alpha: f64) -> Conductor {
+        Conductor::new(
+            Color(0.200, 0.924, 1.102),
+            Color(3.912, 2.452, 2.142),
+            alpha,
+            alpha,
+        )
+    }
+
+    pub fn aluminium(alpha: f64) -> Conductor {
+        Conductor::new(
+            Color(1.657, 0.880, 0.521),
+            Color(9.224, 6.270, 4.837),
+            alpha,
+            alpha,
+        )
+    }
+
+    pub fn silver(alpha: f64) -> Conductor {
+        Conductor::new(
+            Color(0.155, 0.117, 0.138),
+            Color(4.828, 3.122, 2.147),
+            alpha,
+            alpha,
+        )
+    }
+
+    fn fresnel(&self, r_in: &Ray, cos_theta: f64) -> Color {
+        if let Some(film) = self.film {
+            let film_reflectance = |wavelength: f64| {
+                let (eta, k) = self.index_at(wavelength);
+                film.reflectance(cos_theta, 1.0, eta, k, wavelength)
+            };
+            return match r_in.wavelength() {
+                Some(wavelength) => {
+                    let f = film_reflectance(wavelength);
+                    Color(f, f, f)
+                }
+                None => reflectance_to_rgb(film_reflectance),
+            };
+        }
+        Color(
+            fresnel_conductor(cos_theta, self.eta.x(), self.k.x()),
+            fresnel_conductor(cos_theta, self.eta.y(), self.k.y()),
+            fresnel_conductor(cos_theta, self.eta.z(), self.k.z()),
+        )
+    }
+
+    /// `eta` and `k` at `wavelength` in nm, interpolated between the color
+    /// channels and held constant past them.
+    fn index_at(&self, wavelength: f64) -> (f64, f64) {
+        let t = ((wavelength - 550.0) / 100.0).clamp(-1.0, 1.0);
+        let channel = |c: Color| match t > 0.0 {
+            true => c.y() + (c.x() - c.y()) * t,
+            false => c.y() - (c.z() - c.y()) * t,
+        };
+        (channel(self.eta), channel(self.k))
+    }
+
+    /// Directions towards the viewer and the light in the shading frame.
+    fn local_directions(r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> (Vec3, Vec3) {
+        let frame = hit_record.shading;
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        let wi = frame.to_local(&scattered.dir().unit_vector());
+        (wo, wi)
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let frame = hit_record.shading;
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        if self.distribution.effectively_smooth() {
+            let wi = Vec3(-wo.x(), -wo.y(), wo.z());
+            *scattered = Ray::new_with_tm(hit_record.p, frame.local(&wi), r_in.time());
+            *attenuation = self.fresnel(r_in, wo.z());
+            return true;
+        }
+
+        let m = self
+            .distribution
+            .sample_visible_normal(&wo, random_f64(), random_f64());
+        let wi = reflect(&wo, &m);
+        if wi.z() <= 0.0 {
+            return false;
+        }
+        *scattered = Ray::new_with_tm(hit_record.p, frame.local(&wi), r_in.time());
+        // f cos / pdf, with the normal distribution cancelling out.
+        *attenuation = self.fresnel(r_in, wo.dot(&m))
+            * (self.distribution.g(&wo, &wi) / self.distribution.g1(&wo));
+        true
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        if self.distribution.effectively_smooth() {
+            return Color(0.0, 0.0, 0.0);
+        }
+        let (wo, wi) = Conductor::local_directions(r_in, hit_record, scattered);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        let m = (wo + wi).unit_vector();
+        self.fresnel(r_in, wo.dot(&m))
+            * (self.distribution.d(&m) * self.distribution.g(&wo, &wi) / (4.0 * wo.z()))
+    }
+    fn pdf(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        if self.distribution.effectively_smooth() {
+            return 0.0;
+        }
+        let (wo, wi) = Conductor::local_directions(r_in, hit_record, scattered);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let m = (wo + wi).unit_vector();
+        self.distribution.visible_normal_pdf(&wo, &m) / (4.0 * wo.dot(&m).abs())
+    }
+    fn is_specular(&self) -> bool {
+        self.distribution.effectively_smooth()
+    }
+    fn is_dispersive(&self) -> bool {
+        self.film.is_some()
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(Conductor {
+            eta: self.eta,
+            k: self.k,
+            distribution: self.distribution,
+            film: self.film,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::testing::{assert_scatter_matches, hit_at};
+
+    #[test]
+    fn rough_scatter_follows_eval_and_pdf() {
+        for (alpha_x, alpha_y) in [(0.4, 0.4), (0.3, 0.8)] {
+            let mat = Conductor::new(Color(0.2, 0.9, 1.1), Color(3.9, 2.5, 2.1), alpha_x, alpha_y);
+            for degrees in [0.0, 70.0] {
+                let (r_in, rec) = hit_at(degrees);
+                let (pdf, albedo) = assert_scatter_matches(&mat, &r_in, &rec, 100000);
+                // Facets reflecting below the surface lose their light.
+                assert!(pdf <= 1.0 + 1e-2 && pdf > 0.7, "{pdf}");
+                assert!(albedo.x() <= 1.0 && albedo.x() > albedo.z(), "{albedo:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_metal_mirrors_with_its_fresnel_reflectance() {
+        let mat = Conductor::gold(0.0);
+        assert!(mat.is_specular());
+        let (r_in, rec) = hit_at(0.0);
+        let mut attenuation = Color(0.0, 0.0, 0.0);
+        let mut scattered = Ray::new(rec.p, Vec3(0.0, 0.0, 0.0));
+        assert!(mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+        assert!((scattered.dir().unit_vector() - Vec3(0.0, 0.0, 1.0)).length() < 1e-9);
+        let expected =
+            |eta: f64, k: f64| ((eta - 1.0).powi(2) + k * k) / ((eta + 1.0).powi(2) + k * k);
+        assert!((attenuation.x() - expected(mat.eta.x(), mat.k.x())).abs() < 1e-12);
+        assert!((attenuation.z() - expected(mat.eta.z(), mat.k.z())).abs() < 1e-12);
+        assert!(attenuation.x() > attenuation.z());
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use super::material::Material;
+use crate::{
+    hittable::HitRecord,
+    random::hash_f64,
+    textures::texture::Texture,
+    types::{ray::Ray, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// Cuts holes into `material` where its opacity, read from the first
+/// channel, is below one, for leaves, fences and decals.
+///
+/// By default a hit is kept with a probability of its opacity, so partly
+/// opaque surfaces let some light through on average. With a threshold
+/// every hit is either kept or not, for crisp edges.
+pub struct Cutout {
+    pub material: Box<dyn Material + Sync + Send>,
+    pub opacity: Arc<dyn Texture + Sync + Send>,
+    pub threshold: Option<f64>,
+}
+
+impl Cutout {
+    pub fn new(
+        material: Box<dyn Material + Sync + Send>,
+        opacity: Arc<dyn Texture + Sync + Send>,
+    ) -> Cutout {
+        Cutout {
+            material,
+            opacity,
+            threshold: None,
+        }
+    }
+
+    /// Keeps hits only where the opacity is at least `threshold`.
+    pub fn with_threshold(mut self, threshold: f64) -> Cutout {
+        self.threshold = Some(threshold);
+        self
+    }
+}
+
+impl Material for Cutout {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        self.material
+            .scatter(r_in, hit_record, attenuation, scattered)
+    }
+    fn emitted(&self, r_in: &Ray, hit_record: &HitRecord) -> Color {
+        self.material.emitted(r_in, hit_record)
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        self.material.eval(r_in, hit_record, scattered)
+    }
+    fn pdf(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        self.material.pdf(r_in, hit_record, scattered)
+    }
+    fn is_specular(&self) -> bool {
+        self.material.is_specular()
+    }
+    fn is_specular_sample(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> bool {
+        self.material
+            .is_specular_sample(r_in, hit_record, scattered)
+    }
+    fn is_dispersive(&self) -> bool {
+        self.material.is_dispersive()
+    }
+    fn ior(&self) -> f64 {
+        self.material.ior()
+    }
+    /// Random opacity is decided by a hash of the hit rather than a fresh
+    /// random number, so that asking twice gives the same answer. Instances
+    /// keep `t`, `u` and `v`, so they see the same holes.
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        let opacity = self
+            .opacity
+            .value(hit_record.u, hit_record.v, &hit_record.p)
+            .x();
+        match self.threshold {
+            Some(threshold) => opacity < threshold,
+            None => hash_f64(&[hit_record.t, hit_record.u, hit_record.v]) >= opacity,
+        }
+    }
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+    fn emission(&self) -> Color {
+        self.material.emission()
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(Cutout {
+            material: self.material.my_copy(),
+            opacity: self.opacity.clone(),
+            threshold: self.threshold,
+        })
+    }
+}
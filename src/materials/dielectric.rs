@@ -1,30 +1,217 @@
 use super::material::Material;
+use super::microfacet::{fresnel_dielectric, reflect, refract, TrowbridgeReitz};
+use super::thin_film::ThinFilm;
 use crate::{
     hittable::HitRecord,
     random,
-    types::{ray::Ray, vec3::Vec3},
+    types::{ray::Ray, spectrum::reflectance_to_rgb, vec3::Vec3},
 };
 
+use Vec3 as Color;
+
+/// Glass-like boundary that reflects and refracts by the exact Fresnel
+/// equations. Rough surfaces scatter through a GGX microfacet distribution
+/// ("Microfacet Models for Refraction through Rough Surfaces", Walter et al.
+/// 2007) and smooth ones act as a perfect mirror and window.
 pub struct Dielectric {
     pub ir: f64, // Index of Refraction
+    pub distribution: TrowbridgeReitz,
+    /// Fraction of light absorbed per unit of distance travelled inside,
+    /// per color channel (Beer-Lambert). Assumes nothing else sits inside.
+    pub absorption: Color,
+    pub dispersion: Option<Dispersion>,
+    /// Coating on the outside of the surface.
+    pub film: Option<ThinFilm>,
+}
+
+/// How a dielectric's index of refraction varies with wavelength, which
+/// splits white light into its colors when rendering spectrally.
+#[derive(Clone, Copy)]
+pub enum Dispersion {
+    /// `n = a + b / λ²`, with λ in micrometres.
+    Cauchy { a: f64, b: f64 },
+    /// `n² = 1 + Σ b λ² / (λ² - c)`, with λ in micrometres.
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    /// Schott BK7, the common borosilicate crown glass.
+    pub fn bk7() -> Dispersion {
+        Dispersion::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        }
+    }
+
+    /// Schott SF11, a dense flint glass with strong dispersion.
+    pub fn sf11() -> Dispersion {
+        Dispersion::Sellmeier {
+            b: [1.73759695, 0.313747346, 1.89878101],
+            c: [0.013188707, 0.0623068142, 155.23629],
+        }
+    }
+
+    /// Diamond, which bends light strongly and splits it into bright fire.
+    pub fn diamond() -> Dispersion {
+        Dispersion::Sellmeier {
+            b: [4.3356, 0.3306, 0.0],
+            c: [0.1060 * 0.1060, 0.1750 * 0.1750, 0.0],
+        }
+    }
+
+    /// Index of refraction at `wavelength` in nm.
+    pub fn ior(&self, wavelength: f64) -> f64 {
+        let l2 = (wavelength / 1000.0).powi(2);
+        match *self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => (1.0
+                + b.iter()
+                    .zip(c.iter())
+                    .map(|(b, c)| b * l2 / (l2 - c))
+                    .sum::<f64>())
+            .sqrt(),
+        }
+    }
 }
 
 impl Dielectric {
     pub fn new(ir: f64) -> Dielectric {
-        Dielectric { ir: ir }
+        Dielectric::rough(ir, 0.0)
+    }
+
+    /// Frosted glass, with GGX roughness `alpha`.
+    pub fn rough(ir: f64, alpha: f64) -> Dielectric {
+        Dielectric {
+            ir,
+            distribution: TrowbridgeReitz::new(alpha, alpha),
+            absorption: Color(0.0, 0.0, 0.0),
+            dispersion: None,
+            film: None,
+        }
+    }
+
+    /// Tints the inside of the glass, so light reaching a hit from inside is
+    /// dimmed by `exp(-absorption * distance)`.
+    pub fn with_absorption(mut self, absorption: Color) -> Dielectric {
+        self.absorption = absorption;
+        self
+    }
+
+    /// Makes the index of refraction depend on the wavelength of spectral
+    /// rays. `ir` becomes the index at the sodium D line, 589.3 nm, which is
+    /// what RGB rendering sees.
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Dielectric {
+        self.ir = dispersion.ior(589.3);
+        self.dispersion = Some(dispersion);
+        self
+    }
+
+    /// Coats the outside with a thin film, which makes a soap bubble when
+    /// `ir` is one. Without a spectral ray the interference colors are
+    /// averaged into RGB.
+    pub fn with_film(mut self, film: ThinFilm) -> Dielectric {
+        self.film = Some(film);
+        self
+    }
+
+    /// Index of refraction at `wavelength` in nm.
+    fn ior_at(&self, wavelength: f64) -> f64 {
+        match self.dispersion {
+            Some(dispersion) => dispersion.ior(wavelength),
+            None => self.ir,
+        }
+    }
+
+    /// Ratio of the index of refraction behind the surface to the one on the
+    /// side `r_in` arrives from.
+    fn relative_ir(&self, r_in: &Ray, hit_record: &HitRecord) -> f64 {
+        let ir = match r_in.wavelength() {
+            Some(wavelength) => self.ior_at(wavelength),
+            None => self.ir,
+        };
+        match hit_record.front_face {
+            true => ir,
+            false => 1.0 / ir,
+        }
+    }
+
+    /// Fraction of light reflected by a facet seen at `cos_theta`, where
+    /// `eta` is the relative index of refraction.
+    fn reflectance(&self, r_in: &Ray, hit_record: &HitRecord, cos_theta: f64, eta: f64) -> Color {
+        let film = match self.film {
+            Some(film) => film,
+            None => {
+                let f = fresnel_dielectric(cos_theta, eta);
+                return Color(f, f, f);
+            }
+        };
+        let film_reflectance = |wavelength: f64| {
+            let ir = self.ior_at(wavelength);
+            let (outside, substrate) = match hit_record.front_face {
+                true => (1.0, ir),
+                false => (ir, 1.0),
+            };
+            film.reflectance(cos_theta, outside, substrate, 0.0, wavelength)
+        };
+        match r_in.wavelength() {
+            Some(wavelength) => {
+                let f = film_reflectance(wavelength);
+                Color(f, f, f)
+            }
+            None => reflectance_to_rgb(film_reflectance),
+        }
+    }
+
+    /// Light surviving the trip through the inside up to this hit.
+    fn transmittance(&self, r_in: &Ray, hit_record: &HitRecord) -> Color {
+        if hit_record.front_face {
+            return Color(1.0, 1.0, 1.0);
+        }
+        let distance = hit_record.t * r_in.dir().length();
+        Color(
+            (-self.absorption.x() * distance).exp(),
+            (-self.absorption.y() * distance).exp(),
+            (-self.absorption.z() * distance).exp(),
+        )
     }
 
-    fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
-        // Use Schlick's approximation for reflectance.
-        let mut r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
-        r0 = r0 * r0;
-        return r0 + (1.0 - r0) * (1.0 - cosine).powf(5.0);
+    /// Directions towards the viewer and the light in the shading frame.
+    fn local_directions(r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> (Vec3, Vec3) {
+        let frame = hit_record.shading;
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        let wi = frame.to_local(&scattered.dir().unit_vector());
+        (wo, wi)
+    }
+
+    /// The microfacet normal that turns `wo` into `wi`, by reflection when
+    /// they are on the same side and by refraction otherwise, or `None` if
+    /// it would face away from either.
+    fn half_vector(wo: &Vec3, wi: &Vec3, eta: f64) -> Option<Vec3> {
+        let eta_i = if wi.z() > 0.0 { 1.0 } else { eta };
+        let m = *wi * eta_i + *wo;
+        if m.length_squared() == 0.0 {
+            return None;
+        }
+        let mut m = m.unit_vector();
+        if m.z() < 0.0 {
+            m = -m;
+        }
+        if m.dot(wi) * wi.z() < 0.0 || m.dot(wo) * wo.z() < 0.0 {
+            return None;
+        }
+        Some(m)
     }
 }
 
 impl Material for Dielectric {
     fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
-        Box::new(Dielectric { ir: self.ir })
+        Box::new(Dielectric {
+            ir: self.ir,
+            distribution: self.distribution,
+            absorption: self.absorption,
+            dispersion: self.dispersion,
+            film: self.film,
+        })
     }
 
     fn scatter(
@@ -34,24 +221,209 @@ impl Material for Dielectric {
         attenuation: &mut Vec3,
         scattered: &mut Ray,
     ) -> bool {
-        attenuation.set(1.0, 1.0, 1.0);
-        let refraction_ratio = match hit_record.front_face {
-            true => 1.0 / self.ir,
-            false => self.ir,
+        let eta = self.relative_ir(r_in, hit_record);
+        let frame = hit_record.shading;
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        if wo.z() <= 0.0 {
+            return false;
+        }
+
+        // A smooth surface is a single facet along the normal.
+        let smooth = self.distribution.effectively_smooth();
+        let m = match smooth {
+            true => Vec3(0.0, 0.0, 1.0),
+            false => self.distribution.sample_visible_normal(
+                &wo,
+                random::random_f64(),
+                random::random_f64(),
+            ),
+        };
+
+        // Total internal reflection has a reflectance of one, so `refract`
+        // only runs when it can succeed. Rough facets can still send the ray
+        // out to the wrong side of the surface, which ends the path.
+        let reflectance = self.reflectance(r_in, hit_record, wo.dot(&m), eta);
+        let p = reflection_probability(&reflectance);
+        let (wi, weight) = if p > random::random_f64() {
+            let wi = reflect(&wo, &m);
+            if wi.z() <= 0.0 {
+                return false;
+            }
+            (wi, reflectance / p)
+        } else {
+            match refract(&wo, &m, eta) {
+                Some(wi) if wi.z() < 0.0 => (wi, (Color(1.0, 1.0, 1.0) - reflectance) / (1.0 - p)),
+                _ => return false,
+            }
         };
 
-        let unit_dir = r_in.dir().unit_vector();
-        let cos_theta = (-unit_dir).dot(&hit_record.normal).min(1.0);
-        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        *scattered = Ray::new_with_tm(hit_record.p, frame.local(&wi), r_in.time());
+        // f cos / pdf. The Fresnel term cancels against the probability of
+        // picking reflection or refraction, unless a film colors it, and the
+        // normal distribution against the density of the sampled normal.
+        *attenuation = self.transmittance(r_in, hit_record) * weight;
+        if !smooth {
+            *attenuation =
+                *attenuation * (self.distribution.g(&wo, &wi) / self.distribution.g1(&wo));
+        }
+        true
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        if self.distribution.effectively_smooth() {
+            return Color(0.0, 0.0, 0.0);
+        }
+        let eta = self.relative_ir(r_in, hit_record);
+        let (wo, wi) = Dielectric::local_directions(r_in, hit_record, scattered);
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        let m = match Dielectric::half_vector(&wo, &wi, eta) {
+            Some(m) => m,
+            None => return Color(0.0, 0.0, 0.0),
+        };
 
-        let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction = match cannot_refract
-            || Dielectric::reflectance(cos_theta, refraction_ratio) > random::random_f64()
-        {
-            true => unit_dir.reflect(&hit_record.normal),
-            false => Vec3::refract(&unit_dir, &hit_record.normal, refraction_ratio),
+        let d = self.distribution.d(&m);
+        let g = self.distribution.g(&wo, &wi);
+        let f = self.reflectance(r_in, hit_record, wo.dot(&m), eta);
+        let value = if wi.z() > 0.0 {
+            f * (d * g / (4.0 * wo.z()))
+        } else {
+            let denom = (wi.dot(&m) + wo.dot(&m) / eta).powi(2);
+            (Color(1.0, 1.0, 1.0) - f)
+                * (d * g * (wi.dot(&m) * wo.dot(&m)).abs() / (wo.z() * denom))
         };
-        *scattered = Ray::new_with_tm(hit_record.p, direction, r_in.time());
-        return true;
+        self.transmittance(r_in, hit_record) * value
+    }
+    fn pdf(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        if self.distribution.effectively_smooth() {
+            return 0.0;
+        }
+        let eta = self.relative_ir(r_in, hit_record);
+        let (wo, wi) = Dielectric::local_directions(r_in, hit_record, scattered);
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return 0.0;
+        }
+        let m = match Dielectric::half_vector(&wo, &wi, eta) {
+            Some(m) => m,
+            None => return 0.0,
+        };
+
+        let normal_pdf = self.distribution.visible_normal_pdf(&wo, &m);
+        let f = reflection_probability(&self.reflectance(r_in, hit_record, wo.dot(&m), eta));
+        if wi.z() > 0.0 {
+            f * normal_pdf / (4.0 * wo.dot(&m).abs())
+        } else {
+            let denom = (wi.dot(&m) + wo.dot(&m) / eta).powi(2);
+            (1.0 - f) * normal_pdf * wi.dot(&m).abs() / denom
+        }
+    }
+    fn is_specular(&self) -> bool {
+        self.distribution.effectively_smooth()
+    }
+    fn is_dispersive(&self) -> bool {
+        self.dispersion.is_some() || self.film.is_some()
+    }
+    fn ior(&self) -> f64 {
+        self.ir
+    }
+}
+
+/// Chance of sampling reflection over refraction for a facet reflecting
+/// `reflectance`.
+fn reflection_probability(reflectance: &Color) -> f64 {
+    (reflectance.x() + reflectance.y() + reflectance.z()) / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::testing::{assert_scatter_matches, hit_at};
+
+    use Vec3 as Point3;
+
+    /// A hit at the origin on a surface whose outside faces +z, for light
+    /// leaving along `wo`, from either side.
+    fn hit_towards(wo: Vec3) -> (Ray, HitRecord) {
+        let r_in = Ray::new(Point3(0.0, 0.0, 0.0) + wo, -wo);
+        let mut rec = HitRecord::new();
+        rec.t = 1.0;
+        rec.set_face_normal(&r_in, &Vec3(0.0, 0.0, 1.0));
+        rec.set_tangents(Vec3(1.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0));
+        (r_in, rec)
+    }
+
+    // The BSDF without the cosine `eval` folds in.
+    fn bsdf(mat: &Dielectric, wo: Vec3, wi: Vec3) -> f64 {
+        let (r_in, rec) = hit_towards(wo);
+        mat.eval(&r_in, &rec, &Ray::new(rec.p, wi)).x() / wi.z().abs()
+    }
+
+    #[test]
+    fn rough_scatter_follows_eval_and_pdf() {
+        let mat = Dielectric::rough(1.5, 0.3);
+        // From outside, and from inside on either side of the critical angle.
+        let hits = [
+            hit_at(0.0),
+            hit_at(60.0),
+            hit_towards(Vec3(0.5, 0.0, -1.0)),
+            hit_towards(Vec3(1.0, 0.0, -0.6)),
+        ];
+        for (r_in, rec) in hits.iter() {
+            let (pdf, albedo) = assert_scatter_matches(&mat, r_in, rec, 100000);
+            assert!(pdf <= 1.0 + 1e-2 && pdf > 0.8, "{pdf}");
+            assert!(albedo.x() <= 1.0 + 1e-2, "{albedo:?}");
+        }
+    }
+
+    #[test]
+    fn transmission_is_reciprocal_up_to_the_squared_index() {
+        // Radiance is squeezed into the smaller solid angle inside, so light
+        // going in is brighter by (1.5 / 1)^2 than light coming back out.
+        let mat = Dielectric::rough(1.5, 0.3);
+        let pairs = [
+            (Vec3(0.3, 0.1, 0.9), Vec3(0.2, -0.2, -0.9)),
+            (Vec3(0.3, 0.1, 0.9), Vec3(-0.3, 0.0, -0.8)),
+            (Vec3(-0.6, 0.2, 0.5), Vec3(0.5, 0.1, -0.7)),
+        ];
+        for (wo, wi) in pairs.iter() {
+            let (wo, wi) = (wo.unit_vector(), wi.unit_vector());
+            let (into, out_of) = (bsdf(&mat, wo, wi), bsdf(&mat, wi, wo));
+            assert!(into > 0.0);
+            assert!((into / out_of - 1.5 * 1.5).abs() < 1e-9, "{into} {out_of}");
+        }
+    }
+
+    #[test]
+    fn reflection_is_reciprocal() {
+        let mat = Dielectric::rough(1.5, 0.3);
+        let directions = [Vec3(0.3, 0.1, 0.9), Vec3(-0.6, 0.2, 0.5)];
+        for sign in [1.0, -1.0] {
+            let (wo, wi) = (directions[0] * sign, directions[1] * sign);
+            let (wo, wi) = (wo.unit_vector(), wi.unit_vector());
+            let (there, back) = (bsdf(&mat, wo, wi), bsdf(&mat, wi, wo));
+            assert!(there > 0.0);
+            assert!((there - back).abs() < 1e-9 * there, "{there} {back}");
+        }
+    }
+
+    #[test]
+    fn smooth_glass_splits_by_fresnel() {
+        let mat = Dielectric::new(1.5);
+        assert!(mat.is_specular());
+        let (r_in, rec) = hit_at(0.0);
+        let n = 100000;
+        let mut reflected = 0;
+        for _ in 0..n {
+            let mut attenuation = Color(0.0, 0.0, 0.0);
+            let mut scattered = Ray::new(rec.p, Vec3(0.0, 0.0, 0.0));
+            assert!(mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+            assert!((attenuation - Color(1.0, 1.0, 1.0)).length() < 1e-9);
+            if scattered.dir().z() > 0.0 {
+                reflected += 1;
+            }
+        }
+        // A twenty-fifth of the light reflects straight back at 1.5.
+        let fraction = reflected as f64 / n as f64;
+        assert!((fraction - 0.04).abs() < 0.003, "{fraction}");
     }
 }
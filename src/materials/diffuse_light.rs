@@ -0,0 +1,41 @@
+use super::material::Material;
+use crate::{
+    hittable::HitRecord,
+    types::{ray::Ray, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// An emitter that gives off `emit` from the front side of its surface and
+/// does not reflect anything.
+pub struct DiffuseLight {
+    pub emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> DiffuseLight {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _: &Ray, _: &HitRecord, _: &mut Color, _: &mut Ray) -> bool {
+        false
+    }
+    fn emitted(&self, _r_in: &Ray, hit_record: &HitRecord) -> Color {
+        if hit_record.front_face {
+            self.emit
+        } else {
+            Color(0.0, 0.0, 0.0)
+        }
+    }
+    fn is_emissive(&self) -> bool {
+        true
+    }
+    fn emission(&self) -> Color {
+        self.emit
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(DiffuseLight { emit: self.emit })
+    }
+}
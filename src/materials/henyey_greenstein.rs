@@ -0,0 +1,98 @@
+use std::f64::consts::PI;
+
+use super::material::Material;
+use crate::{
+    hittable::HitRecord,
+    random::random_f64,
+    types::{onb::Onb, ray::Ray, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// Henyey-Greenstein phase function for participating media. `g` in (-1, 1)
+/// sets the mean scattering cosine: positive values scatter forwards like
+/// haze, negative values backwards, and zero is isotropic.
+pub struct HenyeyGreenstein {
+    pub albedo: Color,
+    pub g: f64,
+    /// Radiance added where a ray collides with the medium, e.g. for fire.
+    pub emission: Color,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> HenyeyGreenstein {
+        HenyeyGreenstein::new_emissive(albedo, g, Color::zero_vec3())
+    }
+
+    pub fn new_emissive(albedo: Color, g: f64, emission: Color) -> HenyeyGreenstein {
+        HenyeyGreenstein {
+            albedo,
+            g: g.clamp(-0.99, 0.99),
+            emission,
+        }
+    }
+
+    /// Probability density over the sphere of scattering by an angle whose
+    /// cosine is `cos_theta`, measured from the direction of travel.
+    pub fn phase(&self, cos_theta: f64) -> f64 {
+        let denom = 1.0 + self.g * self.g - 2.0 * self.g * cos_theta;
+        (1.0 - self.g * self.g) / (4.0 * PI * denom * denom.sqrt())
+    }
+
+    /// A direction scattered off one travelling along `direction`.
+    pub fn sample_direction(&self, direction: &Vec3) -> Vec3 {
+        let cos_theta = self.sample_cos_theta();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_f64();
+        let frame = Onb::new(direction);
+        frame.local(&Vec3(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+
+    fn sample_cos_theta(&self) -> f64 {
+        let u = random_f64();
+        if self.g.abs() < 1e-3 {
+            return 1.0 - 2.0 * u;
+        }
+        let sqr_term = (1.0 - self.g * self.g) / (1.0 - self.g + 2.0 * self.g * u);
+        (1.0 + self.g * self.g - sqr_term * sqr_term) / (2.0 * self.g)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let direction = self.sample_direction(&r_in.dir());
+        *scattered = Ray::new_with_tm(hit_record.p, direction, r_in.time());
+        *attenuation = self.albedo;
+        true
+    }
+    fn emitted(&self, _r_in: &Ray, _hit_record: &HitRecord) -> Color {
+        self.emission
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        self.albedo * self.pdf(r_in, hit_record, scattered)
+    }
+    fn pdf(&self, r_in: &Ray, _hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let cos_theta = r_in.dir().unit_vector().dot(&scattered.dir().unit_vector());
+        self.phase(cos_theta)
+    }
+    fn is_specular(&self) -> bool {
+        false
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(HenyeyGreenstein {
+            albedo: self.albedo,
+            g: self.g,
+            emission: self.emission,
+        })
+    }
+}
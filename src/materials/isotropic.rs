@@ -0,0 +1,63 @@
+use std::f64::consts::PI;
+
+use super::material::Material;
+use crate::{
+    hittable::HitRecord,
+    types::{ray::Ray, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// Phase function for participating media: scatters uniformly in all directions.
+pub struct Isotropic {
+    pub albedo: Color,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Isotropic {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        *scattered = Ray::new_with_tm(hit_record.p, Vec3::random_unit_vector(), r_in.time());
+        *attenuation = self.albedo;
+        true
+    }
+    fn eval(&self, _r_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> Color {
+        self.albedo / (4.0 * PI)
+    }
+    fn pdf(&self, _r_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+    fn is_specular(&self) -> bool {
+        false
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(Isotropic {
+            albedo: self.albedo,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::testing::{assert_scatter_matches, hit_at};
+
+    #[test]
+    fn scatter_follows_eval_and_pdf() {
+        let mat = Isotropic::new(Color(0.9, 0.5, 0.1));
+        let (r_in, rec) = hit_at(30.0);
+        let (pdf, albedo) = assert_scatter_matches(&mat, &r_in, &rec, 100000);
+        assert!((pdf - 1.0).abs() < 1e-3);
+        assert!((albedo - mat.albedo).length() < 1e-3);
+    }
+}
@@ -1,3 +1,5 @@
+use std::f64::consts::PI;
+
 use super::material::Material;
 use crate::{
     hittable::HitRecord,
@@ -24,18 +26,46 @@ impl Material for Lambertian {
         scattered: &mut Ray,
     ) -> bool {
         // Randomly generating a vector according to Lambertian distribution
-        let mut scatter_direction = hit_record.normal + Vec3::random_unit_vector();
+        let mut scatter_direction = hit_record.shading.w + Vec3::random_unit_vector();
 
         if scatter_direction.near_zero() {
-            scatter_direction = hit_record.normal;
+            scatter_direction = hit_record.shading.w;
         }
         *scattered = Ray::new_with_tm(hit_record.p, scatter_direction, r_in.time());
         attenuation.clone_from(&self.albedo);
         return true;
     }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        self.albedo * self.pdf(r_in, hit_record, scattered)
+    }
+    fn pdf(&self, _r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        // The scatter direction above is cosine distributed.
+        let cos_theta = hit_record.shading.w.dot(&scattered.dir().unit_vector());
+        cos_theta.max(0.0) / PI
+    }
+    fn is_specular(&self) -> bool {
+        false
+    }
     fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
         Box::new(Lambertian {
             albedo: self.albedo,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::testing::{assert_scatter_matches, hit_at};
+
+    #[test]
+    fn scatter_follows_eval_and_pdf() {
+        let mat = Lambertian::new(Color(0.2, 0.5, 0.8));
+        for degrees in [0.0, 60.0] {
+            let (r_in, rec) = hit_at(degrees);
+            let (pdf, albedo) = assert_scatter_matches(&mat, &r_in, &rec, 100000);
+            assert!((pdf - 1.0).abs() < 1e-3);
+            assert!((albedo - mat.albedo).length() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,577 @@
+use std::f64::consts::PI;
+
+use super::material::Material;
+use crate::{
+    hittable::HitRecord,
+    math::power_heuristic,
+    random::random_f64,
+    types::{onb::Onb, ray::Ray, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// One material coated over another, like varnished wood or car paint: a
+/// `top` layer, normally a `Dielectric`, over a `bottom` one of anything,
+/// with a thin tinted gap between them. Light bouncing between the layers
+/// is followed by a random walk, both to scatter and to evaluate the BSDF
+/// stochastically (pbrt-v4's `LayeredBxDF`, after "Position-Free Monte Carlo
+/// Simulation for Arbitrary Layered BSDFs", Guo et al. 2018).
+///
+/// Lights are sampled through the coat unless the bottom layer is
+/// specular. Light the bottom lets through leaves out of the back.
+pub struct Layered {
+    pub top: Box<dyn Material + Sync + Send>,
+    pub bottom: Box<dyn Material + Sync + Send>,
+    pub thickness: f64,
+    /// Fraction of light absorbed per unit of distance travelled between
+    /// the layers, per color channel.
+    pub absorption: Color,
+    /// Most bounces a walk between the layers takes.
+    pub max_depth: usize,
+    /// Walks averaged for each evaluation of the BSDF.
+    pub samples: usize,
+}
+
+/// A direction one of the layers scattered into, in the layers' frame.
+struct Sample {
+    wi: Vec3,
+    /// f cos / pdf.
+    weight: Color,
+    pdf: f64,
+    specular: bool,
+}
+
+/// A layer at the hit point, looked at from either side. Directions are in
+/// a frame whose z axis points out of the top of the layers.
+struct Interface<'a> {
+    material: &'a (dyn Material + Sync + Send),
+    frame: &'a Onb,
+    hit_record: &'a HitRecord,
+    r_in: &'a Ray,
+}
+
+impl Interface<'_> {
+    /// The hit seen by light leaving along `wo`.
+    fn hit(&self, wo: &Vec3) -> HitRecord {
+        let mut rec = HitRecord::new();
+        rec.p = self.hit_record.p;
+        rec.u = self.hit_record.u;
+        rec.v = self.hit_record.v;
+        rec.front_face = wo.z() > 0.0;
+        rec.shading = match rec.front_face {
+            true => *self.frame,
+            false => self.frame.flipped(),
+        };
+        rec.normal = rec.shading.w;
+        rec.dpdu = self.hit_record.dpdu;
+        rec.dpdv = self.hit_record.dpdv;
+        rec
+    }
+
+    /// A ray arriving at the hit from `wo`.
+    fn incoming(&self, wo: &Vec3) -> Ray {
+        let dir = self.frame.local(wo);
+        Ray::new_with_tm(self.hit_record.p + dir, -dir, self.r_in.time())
+            .with_wavelength(self.r_in.wavelength())
+    }
+
+    fn outgoing(&self, wi: &Vec3) -> Ray {
+        Ray::new_with_tm(self.hit_record.p, self.frame.local(wi), self.r_in.time())
+            .with_wavelength(self.r_in.wavelength())
+    }
+
+    /// The BSDF, without the cosine term.
+    fn f(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if wi.z() == 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        self.material
+            .eval(&self.incoming(wo), &self.hit(wo), &self.outgoing(wi))
+            / wi.z().abs()
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        self.material
+            .pdf(&self.incoming(wo), &self.hit(wo), &self.outgoing(wi))
+    }
+
+    fn sample(&self, wo: &Vec3) -> Option<Sample> {
+        let r_in = self.incoming(wo);
+        let rec = self.hit(wo);
+        let mut weight = Color(0.0, 0.0, 0.0);
+        let mut scattered = Ray::new(Vec3::zero_vec3(), Vec3::zero_vec3());
+        if !self
+            .material
+            .scatter(&r_in, &rec, &mut weight, &mut scattered)
+            || weight.near_zero()
+        {
+            return None;
+        }
+        let wi = self.frame.to_local(&scattered.dir().unit_vector());
+        if wi.z() == 0.0 {
+            return None;
+        }
+        let specular = self.material.is_specular_sample(&r_in, &rec, &scattered);
+        let pdf = match specular {
+            true => 0.0,
+            false => self.material.pdf(&r_in, &rec, &scattered),
+        };
+        Some(Sample {
+            wi,
+            weight,
+            pdf,
+            specular,
+        })
+    }
+
+    /// Like `sample`, but only keeps directions through the layer.
+    fn sample_transmission(&self, wo: &Vec3) -> Option<Sample> {
+        self.sample(wo).filter(|s| s.wi.z() * wo.z() < 0.0)
+    }
+
+    /// Like `sample`, but only keeps directions back off the layer.
+    fn sample_reflection(&self, wo: &Vec3) -> Option<Sample> {
+        self.sample(wo).filter(|s| s.wi.z() * wo.z() > 0.0)
+    }
+
+    /// `sample_transmission` for light arriving from `wo` rather than
+    /// leaving towards it, as seen from the other side.
+    fn sample_adjoint_transmission(&self, wo: &Vec3) -> Option<Sample> {
+        let mut sample = self.sample_transmission(wo)?;
+        let eta = match wo.z() > 0.0 {
+            true => 1.0 / self.material.ior(),
+            false => self.material.ior(),
+        };
+        sample.weight = sample.weight * (eta * eta);
+        Some(sample)
+    }
+
+    fn is_specular(&self) -> bool {
+        self.material.is_specular()
+    }
+}
+
+impl Layered {
+    pub fn new(
+        top: Box<dyn Material + Sync + Send>,
+        bottom: Box<dyn Material + Sync + Send>,
+    ) -> Layered {
+        Layered {
+            top,
+            bottom,
+            thickness: 0.01,
+            absorption: Color(0.0, 0.0, 0.0),
+            max_depth: 10,
+            samples: 1,
+        }
+    }
+
+    /// Tints the gap between the layers, `thickness` thick, so light
+    /// crossing it is dimmed by `exp(-absorption * distance)`.
+    pub fn with_absorption(mut self, absorption: Color, thickness: f64) -> Layered {
+        self.absorption = absorption;
+        self.thickness = thickness;
+        self
+    }
+
+    /// The layers' frame at the hit, with z pointing out of the top whichever
+    /// side `r_in` arrived from, and the direction back along `r_in` in it.
+    fn frame(r_in: &Ray, hit_record: &HitRecord) -> (Onb, Vec3) {
+        let frame = match hit_record.front_face {
+            true => hit_record.shading,
+            false => hit_record.shading.flipped(),
+        };
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        (frame, wo)
+    }
+
+    fn interfaces<'a>(
+        &'a self,
+        frame: &'a Onb,
+        r_in: &'a Ray,
+        hit_record: &'a HitRecord,
+    ) -> (Interface<'a>, Interface<'a>) {
+        let interface = |material: &'a (dyn Material + Sync + Send)| Interface {
+            material,
+            frame,
+            hit_record,
+            r_in,
+        };
+        (
+            interface(self.top.as_ref()),
+            interface(self.bottom.as_ref()),
+        )
+    }
+
+    /// Light surviving a crossing of the gap along `w`.
+    fn transmittance(&self, w: &Vec3) -> Color {
+        if w.z() == 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        let distance = self.thickness / w.z().abs();
+        Color(
+            (-self.absorption.x() * distance).exp(),
+            (-self.absorption.y() * distance).exp(),
+            (-self.absorption.z() * distance).exp(),
+        )
+    }
+}
+
+impl Material for Layered {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let (frame, wo) = Layered::frame(r_in, hit_record);
+        if wo.z() == 0.0 {
+            return false;
+        }
+        let (top, bottom) = self.interfaces(&frame, r_in, hit_record);
+        let entered_top = wo.z() > 0.0;
+
+        let enter = if entered_top { &top } else { &bottom };
+        let first = match enter.sample(&wo) {
+            Some(sample) => sample,
+            None => return false,
+        };
+        if first.wi.z() * wo.z() > 0.0 {
+            *scattered = enter.outgoing(&first.wi);
+            *attenuation = first.weight;
+            return true;
+        }
+
+        let mut throughput = first.weight;
+        let mut w = first.wi;
+        let mut at_top = entered_top;
+        for depth in 0..self.max_depth {
+            let most = throughput.x().max(throughput.y()).max(throughput.z());
+            if depth > 3 && most < 0.25 {
+                let q = (1.0 - most).max(0.0);
+                if random_f64() < q {
+                    return false;
+                }
+                throughput = throughput / (1.0 - q);
+            }
+
+            // Cross the gap to the other layer and scatter there; going
+            // through it leaves the layers.
+            at_top = !at_top;
+            throughput = throughput * self.transmittance(&w);
+            let interface = if at_top { &top } else { &bottom };
+            let sample = match interface.sample(&-w) {
+                Some(sample) => sample,
+                None => return false,
+            };
+            throughput = throughput * sample.weight;
+            if sample.wi.z() * w.z() > 0.0 {
+                *scattered = interface.outgoing(&sample.wi);
+                *attenuation = throughput;
+                return true;
+            }
+            w = sample.wi;
+        }
+        false
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let (frame, wo) = Layered::frame(r_in, hit_record);
+        let wi = frame.to_local(&scattered.dir().unit_vector());
+        if wo.z() == 0.0 || wi.z() == 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        let (top, bottom) = self.interfaces(&frame, r_in, hit_record);
+        let entered_top = wo.z() > 0.0;
+        let same_side = wo.z() * wi.z() > 0.0;
+
+        let enter = if entered_top { &top } else { &bottom };
+        // Light leaves through the layer it entered by if it is reflected,
+        // and through the other one if it is transmitted.
+        let (exit, non_exit) = match same_side == entered_top {
+            true => (&top, &bottom),
+            false => (&bottom, &top),
+        };
+        let exit_at_top = same_side == entered_top;
+
+        let mut f = match same_side {
+            true => enter.f(&wo, &wi) * self.samples as f64,
+            false => Color(0.0, 0.0, 0.0),
+        };
+        for _ in 0..self.samples {
+            // Paths in through the entrance, and a virtual light shining in
+            // from `wi` through the exit that they connect to.
+            let Some(wos) = enter.sample_transmission(&wo) else {
+                continue;
+            };
+            let Some(wis) = exit.sample_adjoint_transmission(&wi) else {
+                continue;
+            };
+
+            let mut beta = wos.weight;
+            let mut at_top = entered_top;
+            let mut w = wos.wi;
+            for depth in 0..self.max_depth {
+                let most = beta.x().max(beta.y()).max(beta.z());
+                if depth > 3 && most < 0.25 {
+                    let q = (1.0 - most).max(0.0);
+                    if random_f64() < q {
+                        break;
+                    }
+                    beta = beta / (1.0 - q);
+                }
+
+                at_top = !at_top;
+                beta = beta * self.transmittance(&w);
+                if at_top == exit_at_top {
+                    let Some(bs) = exit.sample_reflection(&-w) else {
+                        break;
+                    };
+                    beta = beta * bs.weight;
+                    w = bs.wi;
+                    continue;
+                }
+
+                // Connect to the virtual light through the exit.
+                if !non_exit.is_specular() {
+                    let weight = match exit.is_specular() {
+                        true => 1.0,
+                        false => power_heuristic(wis.pdf, non_exit.pdf(&-w, &-wis.wi)),
+                    };
+                    f += beta
+                        * non_exit.f(&-w, &-wis.wi)
+                        * self.transmittance(&wis.wi)
+                        * wis.weight
+                        * weight;
+                }
+
+                let Some(bs) = non_exit.sample_reflection(&-w) else {
+                    break;
+                };
+                beta = beta * bs.weight;
+                w = bs.wi;
+
+                // Or leave through the exit straight towards `wi`.
+                if !exit.is_specular() {
+                    let f_exit = exit.f(&-w, &wi);
+                    if !f_exit.near_zero() {
+                        let weight = match non_exit.is_specular() || bs.specular {
+                            true => 1.0,
+                            false => power_heuristic(bs.pdf, exit.pdf(&wi, &-w)),
+                        };
+                        f += beta * self.transmittance(&w) * f_exit * weight;
+                    }
+                }
+            }
+        }
+        f / self.samples as f64 * wi.z().abs()
+    }
+    /// A rough estimate from a few walks, mixed with a uniform density so
+    /// that it is never too small.
+    fn pdf(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let (frame, wo) = Layered::frame(r_in, hit_record);
+        let wi = frame.to_local(&scattered.dir().unit_vector());
+        if wo.z() == 0.0 || wi.z() == 0.0 {
+            return 0.0;
+        }
+        let (top, bottom) = self.interfaces(&frame, r_in, hit_record);
+        let entered_top = wo.z() > 0.0;
+        let same_side = wo.z() * wi.z() > 0.0;
+        let (enter, other) = match entered_top {
+            true => (&top, &bottom),
+            false => (&bottom, &top),
+        };
+
+        let mut pdf_sum = match same_side {
+            true => enter.pdf(&wo, &wi) * self.samples as f64,
+            false => 0.0,
+        };
+        for _ in 0..self.samples {
+            if same_side {
+                // In through the entrance, off the other layer and back out.
+                let (Some(wos), Some(wis)) = (
+                    enter.sample_transmission(&wo),
+                    enter.sample_transmission(&wi),
+                ) else {
+                    continue;
+                };
+                if enter.is_specular() {
+                    pdf_sum += other.pdf(&-wos.wi, &-wis.wi);
+                    continue;
+                }
+                let Some(rs) = other.sample(&-wos.wi) else {
+                    continue;
+                };
+                if other.is_specular() {
+                    pdf_sum += enter.pdf(&-rs.wi, &wi);
+                } else {
+                    let r_pdf = other.pdf(&-wos.wi, &-wis.wi);
+                    pdf_sum += power_heuristic(wis.pdf, r_pdf) * r_pdf;
+                    let t_pdf = enter.pdf(&-rs.wi, &wi);
+                    pdf_sum += power_heuristic(rs.pdf, t_pdf) * t_pdf;
+                }
+            } else {
+                // Through both layers.
+                let (Some(wos), Some(wis)) = (
+                    enter.sample_transmission(&wo),
+                    other.sample_transmission(&wi),
+                ) else {
+                    continue;
+                };
+                pdf_sum += if enter.is_specular() {
+                    other.pdf(&-wos.wi, &wi)
+                } else if other.is_specular() {
+                    enter.pdf(&wo, &-wis.wi)
+                } else {
+                    (enter.pdf(&wo, &-wis.wi) + other.pdf(&-wos.wi, &wi)) / 2.0
+                };
+            }
+        }
+        let uniform = 1.0 / (4.0 * PI);
+        0.1 * uniform + 0.9 * pdf_sum / self.samples as f64
+    }
+    fn is_specular(&self) -> bool {
+        self.bottom.is_specular()
+    }
+    /// The mirror reflection off a specular top, or off specular layers
+    /// parallel to it.
+    fn is_specular_sample(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> bool {
+        if self.is_specular() {
+            return true;
+        }
+        let (frame, wo) = Layered::frame(r_in, hit_record);
+        let wi = frame.to_local(&scattered.dir().unit_vector());
+        self.top.is_specular() && (wi - Vec3(-wo.x(), -wo.y(), wo.z())).length_squared() < 1e-12
+    }
+    fn is_dispersive(&self) -> bool {
+        self.top.is_dispersive() || self.bottom.is_dispersive()
+    }
+    /// A hole in either layer goes through the whole stack.
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        self.top.is_cut_out(hit_record) || self.bottom.is_cut_out(hit_record)
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(Layered {
+            top: self.top.my_copy(),
+            bottom: self.bottom.my_copy(),
+            thickness: self.thickness,
+            absorption: self.absorption,
+            max_depth: self.max_depth,
+            samples: self.samples,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::microfacet::fresnel_dielectric;
+    use crate::materials::testing::hit_at;
+    use crate::materials::{cutout::Cutout, dielectric::Dielectric, lambertian::Lambertian};
+    use crate::textures::solid_color::SolidColor;
+    use std::sync::Arc;
+
+    /// `f` integrated over the sphere of directions.
+    fn integrate(f: impl Fn(&Vec3) -> f64) -> f64 {
+        let (rings, steps) = (100, 100);
+        let mut sum = 0.0;
+        for i in 0..rings {
+            let theta = PI * (i as f64 + 0.5) / rings as f64;
+            for j in 0..steps {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / steps as f64;
+                let dir = Vec3(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                sum += f(&dir) * theta.sin();
+            }
+        }
+        sum * PI / rings as f64 * 2.0 * PI / steps as f64
+    }
+
+    fn varnished_white(alpha: f64) -> Layered {
+        let mut mat = Layered::new(
+            Box::new(Dielectric::rough(1.5, alpha)),
+            Box::new(Lambertian::new(Color(1.0, 1.0, 1.0))),
+        );
+        // Deep enough that light trapped under the coat always gets out,
+        // and enough walks per evaluation to integrate it in few directions.
+        mat.max_depth = 100;
+        mat.samples = 4;
+        mat
+    }
+
+    /// Light that `scatter` sends back on average, and `eval` integrated
+    /// over every direction.
+    fn albedo(mat: &Layered, r_in: &Ray, rec: &HitRecord) -> (f64, f64) {
+        let n = 20000;
+        let mut scattered_albedo = 0.0;
+        for _ in 0..n {
+            let mut attenuation = Color(0.0, 0.0, 0.0);
+            let mut scattered = Ray::new(rec.p, Vec3(0.0, 0.0, 0.0));
+            if mat.scatter(r_in, rec, &mut attenuation, &mut scattered) {
+                scattered_albedo += attenuation.x() / n as f64;
+            }
+        }
+        let eval = integrate(|dir| mat.eval(r_in, rec, &Ray::new(rec.p, *dir)).x());
+        (scattered_albedo, eval)
+    }
+
+    #[test]
+    fn smooth_coat_over_white_keeps_the_light() {
+        let mat = varnished_white(0.0);
+        for degrees in [0.0, 60.0] {
+            let (r_in, rec) = hit_at(degrees);
+            let (albedo, eval) = albedo(&mat, &r_in, &rec);
+            assert!((albedo - 1.0).abs() < 1e-2, "{albedo}");
+            // `eval` leaves out the mirror reflection off the coat.
+            let mirror = fresnel_dielectric(degrees.to_radians().cos(), 1.5);
+            assert!((eval + mirror - albedo).abs() < 0.04, "{eval} {albedo}");
+        }
+    }
+
+    #[test]
+    fn rough_coat_over_white_loses_but_never_gains() {
+        // Rough facets send some light into the surface instead of off it,
+        // which light trapped under the coat runs into again and again.
+        let mat = varnished_white(0.3);
+        for degrees in [0.0, 60.0] {
+            let (r_in, rec) = hit_at(degrees);
+            let (albedo, eval) = albedo(&mat, &r_in, &rec);
+            assert!(albedo < 1.0 && albedo > 0.6, "{albedo}");
+            assert!((eval - albedo).abs() < 0.04, "{eval} {albedo}");
+        }
+    }
+
+    #[test]
+    fn pdf_integrates_to_about_one() {
+        // The estimate follows one bounce under the coat and leaves out how
+        // solid angle changes through it, so it is only roughly normalized.
+        for alpha in [0.0, 0.3] {
+            let mat = varnished_white(alpha);
+            for degrees in [0.0, 60.0] {
+                let (r_in, rec) = hit_at(degrees);
+                let pdf = integrate(|dir| mat.pdf(&r_in, &rec, &Ray::new(rec.p, *dir)));
+                assert!(pdf > 0.8 && pdf < 1.35, "{pdf}");
+            }
+        }
+    }
+
+    #[test]
+    fn holes_go_through_the_coat() {
+        let base = |opacity: f64| {
+            Box::new(
+                Cutout::new(
+                    Box::new(Lambertian::new(Color(0.5, 0.5, 0.5))),
+                    Arc::new(SolidColor::scalar(opacity)),
+                )
+                .with_threshold(0.5),
+            )
+        };
+        let rec = HitRecord::new();
+        let coat = || Box::new(Dielectric::new(1.5));
+        assert!(Layered::new(coat(), base(0.0)).is_cut_out(&rec));
+        assert!(!Layered::new(coat(), base(1.0)).is_cut_out(&rec));
+    }
+}
@@ -11,6 +11,58 @@ pub trait Material {
         attenuation: &mut Color,
         scattered: &mut Ray,
     ) -> bool;
+    /// Light given off at the hit point, towards where `r_in` came from.
+    fn emitted(&self, _r_in: &Ray, _hit_record: &HitRecord) -> Color {
+        Color(0.0, 0.0, 0.0)
+    }
+    /// The BSDF times the cosine term, for light arriving along `scattered`
+    /// and leaving back towards `r_in`'s origin.
+    fn eval(&self, _r_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> Color {
+        Color(0.0, 0.0, 0.0)
+    }
+    /// Solid angle density with which `scatter` picks `scattered`'s direction.
+    fn pdf(&self, _r_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> f64 {
+        0.0
+    }
+    /// Whether light can only be found by following `scatter`, either because
+    /// the material reflects into a single direction or because it does not
+    /// implement `eval`. Lights are sampled explicitly only at non-specular hits.
+    fn is_specular(&self) -> bool {
+        true
+    }
+    /// Whether `scatter` picked `scattered` from a perfectly specular part of
+    /// the material, which `eval` leaves out, so lights found along it count
+    /// in full. Only materials that are not specular as a whole need this.
+    fn is_specular_sample(&self, _r_in: &Ray, _hit_record: &HitRecord, _scattered: &Ray) -> bool {
+        self.is_specular()
+    }
+    /// Whether the directions or weights `scatter` picks depend on the
+    /// wavelength of `r_in`, so a spectral path can only follow its hero
+    /// wavelength after.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
+    /// Index of refraction behind the surface relative to its front, for
+    /// materials that let light through. Reversing the direction light
+    /// crosses such a boundary in scales it by the square of this.
+    fn ior(&self) -> f64 {
+        1.0
+    }
+    /// Whether the surface has a hole at the hit, which every ray, shadow
+    /// rays included, passes through as though nothing were there. It must
+    /// give the same answer when asked again about the same hit.
+    fn is_cut_out(&self, _hit_record: &HitRecord) -> bool {
+        false
+    }
+    /// Whether objects made of this material should be sampled as lights.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+    /// Radiance given off evenly over the front of the surface, to estimate
+    /// how much power a light made of this material has.
+    fn emission(&self) -> Color {
+        Color(0.0, 0.0, 0.0)
+    }
     fn my_copy(&self) -> Box<dyn Material + Sync + Send>;
 }
 
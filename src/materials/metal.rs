@@ -1,3 +1,5 @@
+use std::f64::consts::PI;
+
 use super::material::Material;
 use crate::{
     hittable::HitRecord,
@@ -39,7 +41,7 @@ impl Material for Metal {
         scattered: &mut Ray,
     ) -> bool {
         // reflect the light like a mirror
-        let reflected = r_in.dir().unit_vector().reflect(&hit_record.normal);
+        let reflected = r_in.dir().unit_vector().reflect(&hit_record.shading.w);
 
         *scattered = Ray::new_with_tm(
             hit_record.p,
@@ -48,6 +50,60 @@ impl Material for Metal {
         );
 
         attenuation.clone_from(&self.albedo);
-        return scattered.dir().dot(&hit_record.normal) > 0.0;
+        return scattered.dir().dot(&hit_record.shading.w) > 0.0;
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        if scattered.dir().dot(&hit_record.shading.w) <= 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        self.albedo * self.pdf(r_in, hit_record, scattered)
+    }
+    fn pdf(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        if self.fuzz <= 0.0 {
+            return 0.0;
+        }
+        // `scatter` picks a point uniformly in the fuzz ball around the mirror
+        // direction. The density of a direction is the ball's volume along
+        // that line, s^2 ds from where it enters (t0) to where it leaves (t1).
+        let reflected = r_in.dir().unit_vector().reflect(&hit_record.shading.w);
+        let dir = scattered.dir().unit_vector();
+        let half_b = dir.dot(&reflected);
+        let discriminant = half_b * half_b - (1.0 - self.fuzz * self.fuzz);
+        if half_b <= 0.0 || discriminant <= 0.0 {
+            return 0.0;
+        }
+        let t0 = (half_b - discriminant.sqrt()).max(0.0);
+        let t1 = half_b + discriminant.sqrt();
+        let volume = 4.0 / 3.0 * PI * self.fuzz.powi(3);
+        (t1.powi(3) - t0.powi(3)) / (3.0 * volume)
+    }
+    fn is_specular(&self) -> bool {
+        self.fuzz == 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::testing::{assert_scatter_matches, hit_at};
+
+    #[test]
+    fn fuzzy_scatter_follows_eval_and_pdf() {
+        for fuzz in [0.3, 1.0] {
+            let mat = Metal::new(Color(0.9, 0.6, 0.3), fuzz);
+            for degrees in [0.0, 50.0] {
+                let (r_in, rec) = hit_at(degrees);
+                let (pdf, albedo) = assert_scatter_matches(&mat, &r_in, &rec, 100000);
+                // Fuzzed directions below the surface are absorbed.
+                assert!((pdf - 1.0).abs() < 1e-2, "{pdf}");
+                assert!(albedo.x() <= mat.albedo.x() + 5e-3, "{albedo:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn mirrors_are_specular() {
+        assert!(Metal::new(Color(0.9, 0.9, 0.9), 0.0).is_specular());
+        assert!(!Metal::new(Color(0.9, 0.9, 0.9), 0.1).is_specular());
     }
 }
@@ -0,0 +1,290 @@
+use std::f64::consts::PI;
+
+use crate::types::vec3::Vec3;
+
+/// Trowbridge-Reitz (GGX) microfacet distribution with Smith masking, in a
+/// local shading frame where the surface normal is +z. `alpha_x` and
+/// `alpha_y` are the roughness along the frame's x and y axes.
+#[derive(Clone, Copy)]
+pub struct TrowbridgeReitz {
+    pub alpha_x: f64,
+    pub alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> TrowbridgeReitz {
+        TrowbridgeReitz {
+            alpha_x: alpha_x.max(1e-4),
+            alpha_y: alpha_y.max(1e-4),
+        }
+    }
+
+    /// Too smooth to sample as a distribution; treat it as a perfect mirror.
+    pub fn effectively_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    /// Density of microfacet normals `m` per unit solid angle and area.
+    pub fn d(&self, m: &Vec3) -> f64 {
+        let x = m.x() / self.alpha_x;
+        let y = m.y() / self.alpha_y;
+        let e = x * x + y * y + m.z() * m.z();
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    fn lambda(&self, w: &Vec3) -> f64 {
+        if w.z() == 0.0 {
+            return f64::INFINITY;
+        }
+        let ax = self.alpha_x * w.x();
+        let ay = self.alpha_y * w.y();
+        let tan2 = (ax * ax + ay * ay) / (w.z() * w.z());
+        ((1.0 + tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of the microfacets facing `w` that are not hidden from it.
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of microfacets visible from both `wo` and `wi`
+    /// (height-correlated Smith).
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of the normals `sample_visible_normal` picks for `wo`, which
+    /// never face away from it.
+    pub fn visible_normal_pdf(&self, wo: &Vec3, m: &Vec3) -> f64 {
+        if wo.z() == 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) / wo.z().abs() * self.d(m) * wo.dot(m).max(0.0)
+    }
+
+    /// Microfacet normal distributed like the normals visible from `wo`, for
+    /// uniform `u1` and `u2` in [0,1) ("Sampling the GGX Distribution of
+    /// Visible Normals", Heitz 2018). `wo` must be above the surface.
+    pub fn sample_visible_normal(&self, wo: &Vec3, u1: f64, u2: f64) -> Vec3 {
+        // Stretch to the hemisphere configuration.
+        let vh = Vec3(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit_vector();
+        let len_sq = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if len_sq > 0.0 {
+            Vec3(-vh.y(), vh.x(), 0.0) / len_sq.sqrt()
+        } else {
+            Vec3(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        // Uniform point on the disk, squashed onto the visible half.
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        // Unstretch back to the ellipsoid.
+        Vec3(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit_vector()
+    }
+}
+
+/// Mirror image of `w` about `n`, with both pointing away from the surface.
+pub fn reflect(w: &Vec3, n: &Vec3) -> Vec3 {
+    2.0 * w.dot(n) * *n - *w
+}
+
+/// Unpolarized Fresnel reflectance of a conductor with complex index of
+/// refraction `eta + i k`, for light arriving at `cos_theta` to the normal.
+pub fn fresnel_conductor(cos_theta: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * a * cos2.sqrt();
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    (rs + rp) / 2.0
+}
+
+/// Direction `w` bends into when crossing a surface with normal `n` on its
+/// side, where `eta` is the ratio of the index of refraction on the far side
+/// to the one on `w`'s side. `None` under total internal reflection.
+pub fn refract(w: &Vec3, n: &Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = w.dot(n);
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-*w / eta + (cos_i / eta - cos_t) * *n)
+}
+
+/// Unpolarized Fresnel reflectance of a boundary between dielectrics, for
+/// light arriving at `cos_theta` to the normal from the side where `eta` is
+/// the ratio of the far index of refraction to the near one. A negative
+/// `cos_theta` means the light arrives from the other side.
+pub fn fresnel_dielectric(cos_theta: f64, eta: f64) -> f64 {
+    let (cos_i, eta) = if cos_theta < 0.0 {
+        (-cos_theta.max(-1.0), 1.0 / eta)
+    } else {
+        (cos_theta.min(1.0), eta)
+    };
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::random_f64;
+
+    // The hemisphere of normals is split into bands of cos(theta) and
+    // sectors of phi, integrated with this many rings and steps around each.
+    const BANDS: usize = 8;
+    const SECTORS: usize = 4;
+    const RINGS: usize = 400;
+    const STEPS: usize = 400;
+
+    fn cell(m: &Vec3) -> usize {
+        let band = ((m.z() * BANDS as f64) as usize).min(BANDS - 1);
+        let phi = m.y().atan2(m.x()) + PI;
+        let sector = ((phi / (2.0 * PI) * SECTORS as f64) as usize).min(SECTORS - 1);
+        band * SECTORS + sector
+    }
+
+    /// `f` integrated over each cell of the upper hemisphere.
+    fn integrate_cells(f: impl Fn(&Vec3) -> f64) -> Vec<f64> {
+        let mut cells = vec![0.0; BANDS * SECTORS];
+        let weight = 2.0 * PI / (RINGS * STEPS) as f64;
+        for i in 0..RINGS {
+            let z = (i as f64 + 0.5) / RINGS as f64;
+            let radius = (1.0 - z * z).sqrt();
+            for j in 0..STEPS {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / STEPS as f64 - PI;
+                let m = Vec3(radius * phi.cos(), radius * phi.sin(), z);
+                cells[cell(&m)] += f(&m) * weight;
+            }
+        }
+        cells
+    }
+
+    fn direction(theta_degrees: f64, phi_degrees: f64) -> Vec3 {
+        let (theta, phi) = (theta_degrees.to_radians(), phi_degrees.to_radians());
+        Vec3(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        )
+    }
+
+    #[test]
+    fn projected_normals_cover_the_surface_once() {
+        for distribution in [
+            TrowbridgeReitz::new(0.3, 0.3),
+            TrowbridgeReitz::new(0.2, 0.6),
+        ] {
+            let area: f64 = integrate_cells(|m| distribution.d(m) * m.z()).iter().sum();
+            assert!((area - 1.0).abs() < 1e-2, "{area}");
+        }
+    }
+
+    #[test]
+    fn visible_normals_follow_their_pdf() {
+        let n = 100000;
+        for (distribution, wo) in [
+            (TrowbridgeReitz::new(0.3, 0.3), direction(0.0, 0.0)),
+            (TrowbridgeReitz::new(0.5, 0.5), direction(60.0, 0.0)),
+            (TrowbridgeReitz::new(0.2, 0.6), direction(45.0, 30.0)),
+        ] {
+            let pdf_cells = integrate_cells(|m| distribution.visible_normal_pdf(&wo, m));
+            let total: f64 = pdf_cells.iter().sum();
+            assert!((total - 1.0).abs() < 1e-2, "{total}");
+
+            let mut shares = vec![0.0; BANDS * SECTORS];
+            for _ in 0..n {
+                let m = distribution.sample_visible_normal(&wo, random_f64(), random_f64());
+                assert!((m.length() - 1.0).abs() < 1e-9 && m.dot(&wo) >= -1e-9);
+                shares[cell(&m)] += 1.0 / n as f64;
+            }
+            for (i, (share, pdf)) in shares.iter().zip(pdf_cells.iter()).enumerate() {
+                assert!(
+                    (share - pdf).abs() < 0.005 + 0.05 * pdf,
+                    "cell {i}: {share} of the samples, pdf gives {pdf}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn masking_stays_between_zero_and_one() {
+        let distribution = TrowbridgeReitz::new(0.2, 0.6);
+        let up = Vec3(0.0, 0.0, 1.0);
+        assert!((distribution.g1(&up) - 1.0).abs() < 1e-12);
+        assert!(distribution.g1(&direction(89.99, 0.0)) < 0.05);
+        assert_eq!(distribution.g1(&Vec3(1.0, 0.0, 0.0)), 0.0);
+
+        let directions: Vec<Vec3> = (0..9)
+            .flat_map(|i| (0..6).map(move |j| direction(i as f64 * 10.0, j as f64 * 60.0)))
+            .collect();
+        for wo in directions.iter() {
+            let g1 = distribution.g1(wo);
+            assert!((0.0..=1.0).contains(&g1));
+            for wi in directions.iter() {
+                // Shadowing can only hide more of what masking already does.
+                let g = distribution.g(wo, wi);
+                assert!(g > 0.0 && g <= g1.min(distribution.g1(wi)) + 1e-12);
+                assert!(g + 1e-12 >= g1 * distribution.g1(wi));
+            }
+        }
+    }
+
+    #[test]
+    fn conductor_fresnel_at_normal_incidence() {
+        for (eta, k) in [(0.2_f64, 3.9), (1.657, 9.224), (1.5, 0.0)] {
+            let expected = ((eta - 1.0).powi(2) + k * k) / ((eta + 1.0).powi(2) + k * k);
+            assert!((fresnel_conductor(1.0, eta, k) - expected).abs() < 1e-12);
+            assert!((fresnel_conductor(0.0, eta, k) - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn conductor_fresnel_without_absorption_is_dielectric() {
+        for cos_theta in [0.05, 0.3, 0.7, 1.0] {
+            let conductor = fresnel_conductor(cos_theta, 1.5, 0.0);
+            let dielectric = fresnel_dielectric(cos_theta, 1.5);
+            assert!((conductor - dielectric).abs() < 1e-12, "{cos_theta}");
+        }
+    }
+
+    #[test]
+    fn refraction_follows_snells_law() {
+        let w = direction(40.0, 0.0);
+        let n = Vec3(0.0, 0.0, 1.0);
+        let t = refract(&w, &n, 1.5).unwrap();
+        let sin_t = (t.x() * t.x() + t.y() * t.y()).sqrt() / t.length();
+        assert!((1.5 * sin_t - 40.0_f64.to_radians().sin()).abs() < 1e-12);
+        assert!(t.z() < 0.0);
+        // Past the critical angle from inside glass there is no way out.
+        assert!(refract(&direction(45.0, 0.0), &n, 1.0 / 1.5).is_none());
+        assert_eq!(
+            fresnel_dielectric(45.0_f64.to_radians().cos(), 1.0 / 1.5),
+            1.0
+        );
+    }
+}
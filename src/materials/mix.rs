@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use super::material::Material;
+use crate::{
+    hittable::HitRecord,
+    random::hash_f64,
+    textures::{solid_color::SolidColor, texture::Texture},
+    types::{ray::Ray, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// Blends two materials, taking `b` where the mask is one and `a` where it
+/// is zero. Each hit is one of them, picked by the mask with a hash of where
+/// it is, so that everything asked about the hit sees the same material.
+/// Averaged over the samples of a pixel, they blend.
+///
+/// If either material is specular, so is the blend, and lights are only
+/// found by following `scatter`.
+pub struct Mix {
+    pub a: Box<dyn Material + Sync + Send>,
+    pub b: Box<dyn Material + Sync + Send>,
+    /// Read from the first channel.
+    pub mask: Arc<dyn Texture + Sync + Send>,
+}
+
+impl Mix {
+    pub fn new(
+        a: Box<dyn Material + Sync + Send>,
+        b: Box<dyn Material + Sync + Send>,
+        amount: f64,
+    ) -> Mix {
+        Mix::with_mask(a, b, Arc::new(SolidColor::scalar(amount)))
+    }
+
+    pub fn with_mask(
+        a: Box<dyn Material + Sync + Send>,
+        b: Box<dyn Material + Sync + Send>,
+        mask: Arc<dyn Texture + Sync + Send>,
+    ) -> Mix {
+        Mix { a, b, mask }
+    }
+
+    fn amount(&self, hit_record: &HitRecord) -> f64 {
+        self.mask
+            .value(hit_record.u, hit_record.v, &hit_record.p)
+            .x()
+            .clamp(0.0, 1.0)
+    }
+
+    /// The material at the hit, the same every time it is asked.
+    fn pick(&self, hit_record: &HitRecord) -> &(dyn Material + Sync + Send) {
+        let pick = hash_f64(&[hit_record.u, hit_record.v, hit_record.t]);
+        match pick < self.amount(hit_record) {
+            true => self.b.as_ref(),
+            false => self.a.as_ref(),
+        }
+    }
+}
+
+impl Material for Mix {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        self.pick(hit_record)
+            .scatter(r_in, hit_record, attenuation, scattered)
+    }
+    fn emitted(&self, r_in: &Ray, hit_record: &HitRecord) -> Color {
+        self.pick(hit_record).emitted(r_in, hit_record)
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        self.pick(hit_record).eval(r_in, hit_record, scattered)
+    }
+    fn pdf(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        self.pick(hit_record).pdf(r_in, hit_record, scattered)
+    }
+    fn is_specular(&self) -> bool {
+        self.a.is_specular() || self.b.is_specular()
+    }
+    fn is_specular_sample(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> bool {
+        self.pick(hit_record)
+            .is_specular_sample(r_in, hit_record, scattered)
+    }
+    fn is_dispersive(&self) -> bool {
+        self.a.is_dispersive() || self.b.is_dispersive()
+    }
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        self.pick(hit_record).is_cut_out(hit_record)
+    }
+    fn is_emissive(&self) -> bool {
+        self.a.is_emissive() || self.b.is_emissive()
+    }
+    /// Judged by the mask at the middle of the surface.
+    fn emission(&self) -> Color {
+        let t = self
+            .mask
+            .value(0.5, 0.5, &Vec3(0.0, 0.0, 0.0))
+            .x()
+            .clamp(0.0, 1.0);
+        self.a.emission() * (1.0 - t) + self.b.emission() * t
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(Mix {
+            a: self.a.my_copy(),
+            b: self.b.my_copy(),
+            mask: self.mask.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::testing::{assert_scatter_matches, hit_at};
+    use crate::materials::{cutout::Cutout, lambertian::Lambertian, metal::Metal};
+
+    fn red_and_blue(amount: f64) -> Mix {
+        Mix::new(
+            Box::new(Lambertian::new(Color(0.8, 0.1, 0.1))),
+            Box::new(Lambertian::new(Color(0.1, 0.1, 0.8))),
+            amount,
+        )
+    }
+
+    #[test]
+    fn each_hit_is_one_material_throughout() {
+        let mat = red_and_blue(0.3);
+        let (r_in, mut rec) = hit_at(30.0);
+        let n = 10000;
+        let mut blue = 0;
+        for i in 0..n {
+            rec.u = i as f64 / n as f64;
+            let mut attenuation = Color(0.0, 0.0, 0.0);
+            let mut scattered = Ray::new(rec.p, Vec3(0.0, 0.0, 0.0));
+            assert!(mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered));
+            let f = mat.eval(&r_in, &rec, &scattered) / mat.pdf(&r_in, &rec, &scattered);
+            assert!((f - attenuation).length() < 1e-9);
+            if attenuation.z() > attenuation.x() {
+                blue += 1;
+            }
+        }
+        let share = blue as f64 / n as f64;
+        assert!((share - 0.3).abs() < 0.02, "{share}");
+    }
+
+    #[test]
+    fn scatter_follows_eval_and_pdf() {
+        let glossy = Mix::new(
+            Box::new(Lambertian::new(Color(0.8, 0.1, 0.1))),
+            Box::new(Metal::new(Color(0.9, 0.9, 0.9), 0.5)),
+            0.5,
+        );
+        for mat in [red_and_blue(0.5), glossy] {
+            let (r_in, rec) = hit_at(30.0);
+            assert_scatter_matches(&mat, &r_in, &rec, 100000);
+        }
+    }
+
+    #[test]
+    fn holes_follow_the_mask() {
+        let hole = || {
+            Box::new(
+                Cutout::new(
+                    Box::new(Lambertian::new(Color(0.5, 0.5, 0.5))),
+                    Arc::new(SolidColor::scalar(0.0)),
+                )
+                .with_threshold(0.5),
+            )
+        };
+        let solid = || Box::new(Lambertian::new(Color(0.5, 0.5, 0.5)));
+        let rec = HitRecord::new();
+        assert!(Mix::new(hole(), solid(), 0.0).is_cut_out(&rec));
+        assert!(!Mix::new(hole(), solid(), 1.0).is_cut_out(&rec));
+        assert!(!Mix::new(solid(), hole(), 0.0).is_cut_out(&rec));
+        assert!(Mix::new(solid(), hole(), 1.0).is_cut_out(&rec));
+    }
+}
@@ -1,4 +1,18 @@
+pub mod conductor;
+pub mod cutout;
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod henyey_greenstein;
+pub mod isotropic;
 pub mod lambertian;
+pub mod layered;
 pub mod material;
 pub mod metal;
+pub mod microfacet;
+pub mod mix;
+pub mod normal_map;
+pub mod principled;
+pub mod subsurface;
+#[cfg(test)]
+pub mod testing;
+pub mod thin_film;
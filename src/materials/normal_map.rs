@@ -0,0 +1,254 @@
+use std::sync::Arc;
+
+use super::material::Material;
+use crate::{
+    hittable::HitRecord,
+    textures::texture::Texture,
+    types::{ray::Ray, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// How far across the surface the height is looked up again to find its
+/// slope.
+const BUMP_STEP: f64 = 1e-3;
+
+/// Where the detail comes from.
+#[derive(Clone)]
+enum Detail {
+    /// Tangent space normals encoded as colors, `(0.5, 0.5, 1.0)` being flat.
+    Normals(Arc<dyn Texture + Sync + Send>),
+    /// Heights, read from the first channel, raising the surface along its
+    /// normal by `scale` times their value.
+    Heights(Arc<dyn Texture + Sync + Send>, f64),
+}
+
+/// Adds surface detail to `material` by tilting its shading normal, without
+/// changing the geometry.
+///
+/// The tangent space follows the surface's `u` and `v`, so shapes without
+/// a parameterization, and the poles of a sphere, show no detail. Light is
+/// never let through between a tilted normal and the true surface, which
+/// shows as darkening where the detail is steep rather than as leaks.
+pub struct NormalMap {
+    pub material: Box<dyn Material + Sync + Send>,
+    detail: Detail,
+}
+
+impl NormalMap {
+    pub fn new(
+        material: Box<dyn Material + Sync + Send>,
+        normals: Arc<dyn Texture + Sync + Send>,
+    ) -> NormalMap {
+        NormalMap {
+            material,
+            detail: Detail::Normals(normals),
+        }
+    }
+
+    pub fn bump(
+        material: Box<dyn Material + Sync + Send>,
+        heights: Arc<dyn Texture + Sync + Send>,
+        scale: f64,
+    ) -> NormalMap {
+        NormalMap {
+            material,
+            detail: Detail::Heights(heights, scale),
+        }
+    }
+
+    /// The hit with its shading frame tilted by the detail.
+    fn shade(&self, r_in: &Ray, hit_record: &HitRecord) -> HitRecord {
+        let mut rec = hit_record.clone();
+        if hit_record.dpdu.near_zero() || hit_record.dpdv.near_zero() {
+            return rec;
+        }
+        // Work on the outside of the surface, as the detail is authored.
+        let frame = match hit_record.front_face {
+            true => hit_record.shading,
+            false => hit_record.shading.flipped(),
+        };
+        let n = frame.w;
+
+        let normal = match &self.detail {
+            Detail::Normals(normals) => {
+                let mut bitangent = n.cross(&frame.u);
+                if bitangent.dot(&hit_record.dpdv) < 0.0 {
+                    bitangent = -bitangent;
+                }
+                let c = normals.value(hit_record.u, hit_record.v, &hit_record.p) * 2.0
+                    - Vec3(1.0, 1.0, 1.0);
+                frame.u * c.x() + bitangent * c.y() + n * c.z()
+            }
+            Detail::Heights(heights, scale) => {
+                let height = |du: f64, dv: f64| {
+                    let p = hit_record.p + hit_record.dpdu * du + hit_record.dpdv * dv;
+                    scale * heights.value(hit_record.u + du, hit_record.v + dv, &p).x()
+                };
+                let du = BUMP_STEP / hit_record.dpdu.length();
+                let dv = BUMP_STEP / hit_record.dpdv.length();
+                let displace = height(0.0, 0.0);
+                let dpdu = hit_record.dpdu + n * ((height(du, 0.0) - displace) / du);
+                let dpdv = hit_record.dpdv + n * ((height(0.0, dv) - displace) / dv);
+                let normal = dpdu.cross(&dpdv);
+                match normal.dot(&n) < 0.0 {
+                    true => -normal,
+                    false => normal,
+                }
+            }
+        };
+        if normal.near_zero() {
+            return rec;
+        }
+        rec.set_shading_normal(r_in, &normal);
+        rec
+    }
+}
+
+impl Material for NormalMap {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let rec = self.shade(r_in, hit_record);
+        self.material.scatter(r_in, &rec, attenuation, scattered) && !rec.leaks(&scattered.dir())
+    }
+    fn emitted(&self, r_in: &Ray, hit_record: &HitRecord) -> Color {
+        self.material.emitted(r_in, hit_record)
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let rec = self.shade(r_in, hit_record);
+        if rec.leaks(&scattered.dir()) {
+            return Color(0.0, 0.0, 0.0);
+        }
+        self.material.eval(r_in, &rec, scattered)
+    }
+    fn pdf(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let rec = self.shade(r_in, hit_record);
+        if rec.leaks(&scattered.dir()) {
+            return 0.0;
+        }
+        self.material.pdf(r_in, &rec, scattered)
+    }
+    fn is_specular(&self) -> bool {
+        self.material.is_specular()
+    }
+    fn is_specular_sample(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> bool {
+        let rec = self.shade(r_in, hit_record);
+        self.material.is_specular_sample(r_in, &rec, scattered)
+    }
+    fn is_dispersive(&self) -> bool {
+        self.material.is_dispersive()
+    }
+    fn ior(&self) -> f64 {
+        self.material.ior()
+    }
+    fn is_cut_out(&self, hit_record: &HitRecord) -> bool {
+        self.material.is_cut_out(hit_record)
+    }
+    fn is_emissive(&self) -> bool {
+        self.material.is_emissive()
+    }
+    fn emission(&self) -> Color {
+        self.material.emission()
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(NormalMap {
+            material: self.material.my_copy(),
+            detail: self.detail.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+    use crate::materials::testing::hit_at;
+    use crate::textures::solid_color::SolidColor;
+
+    use Vec3 as Point3;
+
+    /// Heights rising by `slope` per unit of `u`.
+    struct Ramp {
+        slope: f64,
+    }
+
+    impl Texture for Ramp {
+        fn value(&self, u: f64, _v: f64, _p: &Point3) -> Color {
+            Color(self.slope * u, 0.0, 0.0)
+        }
+    }
+
+    fn lambertian() -> Box<dyn Material + Sync + Send> {
+        Box::new(Lambertian::new(Color(0.5, 0.5, 0.5)))
+    }
+
+    fn normals(color: Color) -> NormalMap {
+        NormalMap::new(lambertian(), Arc::new(SolidColor::new(color)))
+    }
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-6, "{a:?} {b:?}");
+    }
+
+    #[test]
+    fn flat_detail_leaves_the_frame_alone() {
+        let (r_in, rec) = hit_at(30.0);
+        let flat = normals(Color(0.5, 0.5, 1.0));
+        let level = NormalMap::bump(lambertian(), Arc::new(SolidColor::scalar(0.7)), 2.0);
+        for mat in [flat, level] {
+            let shaded = mat.shade(&r_in, &rec);
+            assert_close(shaded.shading.u, rec.shading.u);
+            assert_close(shaded.shading.v, rec.shading.v);
+            assert_close(shaded.shading.w, rec.shading.w);
+        }
+    }
+
+    #[test]
+    fn tangent_space_normals_tilt_along_u_and_v() {
+        let (r_in, mut rec) = hit_at(0.0);
+        let (s, c) = (30.0_f64.to_radians().sin(), 30.0_f64.to_radians().cos());
+        let towards_u = normals(Color(0.5 + 0.5 * s, 0.5, 0.5 + 0.5 * c));
+        assert_close(towards_u.shade(&r_in, &rec).shading.w, Vec3(s, 0.0, c));
+        let towards_v = normals(Color(0.5, 0.5 + 0.5 * s, 0.5 + 0.5 * c));
+        assert_close(towards_v.shade(&r_in, &rec).shading.w, Vec3(0.0, s, c));
+
+        // Green follows `v` even when it runs the other way.
+        rec.set_tangents(Vec3(1.0, 0.0, 0.0), Vec3(0.0, -1.0, 0.0));
+        assert_close(towards_v.shade(&r_in, &rec).shading.w, Vec3(0.0, -s, c));
+    }
+
+    #[test]
+    fn bumps_tilt_away_from_the_uphill_side() {
+        // A surface rising by 0.5 per unit along x faces back towards -x.
+        let (r_in, rec) = hit_at(0.0);
+        let mat = NormalMap::bump(lambertian(), Arc::new(Ramp { slope: 0.25 }), 2.0);
+        let expected = Vec3(-0.5, 0.0, 1.0).unit_vector();
+        assert_close(mat.shade(&r_in, &rec).shading.w, expected);
+    }
+
+    #[test]
+    fn light_never_passes_between_the_normals() {
+        let (r_in, rec) = hit_at(0.0);
+        let steep = 80.0_f64.to_radians();
+        let mat = normals(Color(0.5 + 0.5 * steep.sin(), 0.5, 0.5 + 0.5 * steep.cos()));
+
+        // Above the tilted normal's horizon but below the surface.
+        let under = Ray::new(rec.p, Vec3(1.0, 0.0, -0.1));
+        assert!(mat.shade(&r_in, &rec).leaks(&under.dir()));
+        assert_eq!(mat.eval(&r_in, &rec, &under).x(), 0.0);
+        assert_eq!(mat.pdf(&r_in, &rec, &under), 0.0);
+
+        for _ in 0..1000 {
+            let mut attenuation = Color(0.0, 0.0, 0.0);
+            let mut scattered = Ray::new(rec.p, Vec3(0.0, 0.0, 0.0));
+            if mat.scatter(&r_in, &rec, &mut attenuation, &mut scattered) {
+                assert!(scattered.dir().z() > 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,413 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use super::dielectric::Dielectric;
+use super::material::Material;
+use super::microfacet::{reflect, TrowbridgeReitz};
+use crate::{
+    hittable::HitRecord,
+    random::random_f64,
+    textures::{solid_color::SolidColor, texture::Texture},
+    types::{color::luminance, ray::Ray, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// Disney's principled BSDF ("Physically Based Shading at Disney", Burley
+/// 2012), one material covering plastics, metals, fabrics, lacquer and glass.
+/// Every parameter except `sheen_tint`, `clearcoat_gloss` and `ior` can vary
+/// over the surface; scalar ones read their texture's first channel and run
+/// from 0 to 1 unless noted.
+///
+/// The clearcoat uses a GGX lobe in place of Disney's GTR1 so it can share
+/// visible normal sampling with the main specular lobe. Transmission is rough
+/// glass tinted by the base color as light enters, which reflects by its own
+/// Fresnel term in place of the specular lobe; hits from inside see only the
+/// glass.
+#[derive(Clone)]
+pub struct Principled {
+    pub base_color: Arc<dyn Texture + Sync + Send>,
+    /// Blends from a dielectric to a metal whose reflectance is the base color.
+    pub metallic: Arc<dyn Texture + Sync + Send>,
+    pub roughness: Arc<dyn Texture + Sync + Send>,
+    /// Reflectance at normal incidence, where 0.5 is 4% like most dielectrics.
+    pub specular: Arc<dyn Texture + Sync + Send>,
+    /// Tints the dielectric specular towards the base color's hue.
+    pub specular_tint: Arc<dyn Texture + Sync + Send>,
+    /// Extra reflection at grazing angles, for cloth.
+    pub sheen: Arc<dyn Texture + Sync + Send>,
+    pub sheen_tint: f64,
+    /// Strength of a second, white specular layer on top.
+    pub clearcoat: Arc<dyn Texture + Sync + Send>,
+    pub clearcoat_gloss: f64,
+    /// Blends from an opaque surface to glass.
+    pub transmission: Arc<dyn Texture + Sync + Send>,
+    /// Index of refraction of the glass.
+    pub ior: f64,
+    /// Light given off from the front of the surface.
+    pub emission: Option<Arc<dyn Texture + Sync + Send>>,
+}
+
+/// The parameters at one hit, and how likely each lobe is to be sampled.
+struct Shading {
+    base: Color,
+    roughness: f64,
+    diffuse_weight: f64,
+    transmission_weight: f64,
+    specular_weight: f64,
+    specular_color: Color,
+    sheen_color: Color,
+    clearcoat: f64,
+    specular: TrowbridgeReitz,
+    coat: TrowbridgeReitz,
+    glass: Dielectric,
+    /// Chance of picking the diffuse, specular, clearcoat and glass lobes.
+    probabilities: [f64; 4],
+}
+
+impl Principled {
+    /// A rough white-specular plastic of `base_color`, to adjust from there.
+    pub fn new(base_color: Color) -> Principled {
+        let scalar = |value: f64| Arc::new(SolidColor::scalar(value));
+        Principled {
+            base_color: Arc::new(SolidColor::new(base_color)),
+            metallic: scalar(0.0),
+            roughness: scalar(0.5),
+            specular: scalar(0.5),
+            specular_tint: scalar(0.0),
+            sheen: scalar(0.0),
+            sheen_tint: 0.5,
+            clearcoat: scalar(0.0),
+            clearcoat_gloss: 1.0,
+            transmission: scalar(0.0),
+            ior: 1.5,
+            emission: None,
+        }
+    }
+
+    fn shading(&self, hit_record: &HitRecord, wo: &Vec3) -> Shading {
+        let at = |texture: &Arc<dyn Texture + Sync + Send>| {
+            texture.value(hit_record.u, hit_record.v, &hit_record.p)
+        };
+        let white = Color(1.0, 1.0, 1.0);
+        let base = at(&self.base_color);
+        let metallic = at(&self.metallic).x().clamp(0.0, 1.0);
+        let roughness = at(&self.roughness).x().clamp(0.0, 1.0);
+        let specular = at(&self.specular).x().max(0.0);
+        let specular_tint = at(&self.specular_tint).x().clamp(0.0, 1.0);
+        let sheen = at(&self.sheen).x().max(0.0);
+        let clearcoat = at(&self.clearcoat).x().max(0.0);
+        let transmission = at(&self.transmission).x().clamp(0.0, 1.0);
+
+        let base_luminance = luminance(&base);
+        let tint = if base_luminance > 0.0 {
+            base / base_luminance
+        } else {
+            white
+        };
+        let specular_color = lerp(
+            lerp(white, tint, specular_tint) * (0.08 * specular),
+            base,
+            metallic,
+        );
+        let diffuse_weight = (1.0 - metallic) * (1.0 - transmission);
+        let transmission_weight = (1.0 - metallic) * transmission;
+        // The glass reflects off its own surface, so the specular lobe only
+        // covers the rest.
+        let specular_weight = 1.0 - transmission_weight;
+
+        // Pick lobes roughly by how much light they reflect towards `wo`.
+        let mut probabilities = [
+            diffuse_weight * base_luminance,
+            specular_weight * luminance(&schlick(specular_color, wo.z())),
+            0.25 * clearcoat * schlick(Color(0.04, 0.04, 0.04), wo.z()).x(),
+            transmission_weight * base_luminance,
+        ];
+        let total: f64 = probabilities.iter().sum();
+        if total > 0.0 {
+            probabilities.iter_mut().for_each(|p| *p /= total);
+        } else {
+            probabilities = [1.0, 0.0, 0.0, 0.0];
+        }
+
+        // Keep the lobes from becoming perfect mirrors, which could not be
+        // mixed with the others.
+        let alpha = (roughness * roughness).max(1e-3);
+        let coat_alpha = (0.1 + (0.001 - 0.1) * self.clearcoat_gloss).max(1e-3);
+        Shading {
+            base,
+            roughness,
+            diffuse_weight,
+            transmission_weight,
+            specular_weight,
+            specular_color,
+            sheen_color: lerp(white, tint, self.sheen_tint) * sheen,
+            clearcoat,
+            specular: TrowbridgeReitz::new(alpha, alpha),
+            coat: TrowbridgeReitz::new(coat_alpha, coat_alpha),
+            glass: Dielectric::rough(self.ior, alpha),
+            probabilities,
+        }
+    }
+
+    /// Directions towards the viewer and the light in the shading frame.
+    fn local_directions(r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> (Vec3, Vec3) {
+        let frame = hit_record.shading;
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        let wi = frame.to_local(&scattered.dir().unit_vector());
+        (wo, wi)
+    }
+
+    /// `eval` for the parameters in `shading`, with `wo` above the surface
+    /// and `wi` off it.
+    fn lobes_eval(
+        shading: &Shading,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        scattered: &Ray,
+        wo: &Vec3,
+        wi: &Vec3,
+    ) -> Color {
+        if !hit_record.front_face {
+            return shading.glass.eval(r_in, hit_record, scattered);
+        }
+        let glass = shading.glass.eval(r_in, hit_record, scattered) * shading.transmission_weight;
+        if wi.z() < 0.0 {
+            return glass * shading.base;
+        }
+
+        let h = (*wo + *wi).unit_vector();
+        let cos_d = wi.dot(&h);
+        let fd90 = 0.5 + 2.0 * shading.roughness * cos_d * cos_d;
+        let retro = |cos: f64| 1.0 + (fd90 - 1.0) * (1.0 - cos).powi(5);
+        let diffuse = (shading.base * (retro(wi.z()) * retro(wo.z()) / PI)
+            + shading.sheen_color * (1.0 - cos_d).powi(5))
+            * shading.diffuse_weight;
+
+        let lobe = |distribution: &TrowbridgeReitz| {
+            distribution.d(&h) * distribution.g(wo, wi) / (4.0 * wo.z() * wi.z())
+        };
+        let specular = schlick(shading.specular_color, cos_d)
+            * (shading.specular_weight * lobe(&shading.specular));
+        let coat = schlick(Color(0.04, 0.04, 0.04), cos_d)
+            * (0.25 * shading.clearcoat * lobe(&shading.coat));
+
+        // The glass reflects untinted.
+        (diffuse + specular + coat) * wi.z() + glass
+    }
+
+    /// `pdf` for the parameters in `shading`, with `wo` above the surface
+    /// and `wi` off it.
+    fn lobes_pdf(
+        shading: &Shading,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        scattered: &Ray,
+        wo: &Vec3,
+        wi: &Vec3,
+    ) -> f64 {
+        if !hit_record.front_face {
+            return shading.glass.pdf(r_in, hit_record, scattered);
+        }
+        let p = shading.probabilities;
+        let glass = if p[3] > 0.0 {
+            p[3] * shading.glass.pdf(r_in, hit_record, scattered)
+        } else {
+            0.0
+        };
+        if wi.z() < 0.0 {
+            return glass;
+        }
+
+        let h = (*wo + *wi).unit_vector();
+        let lobe = |distribution: &TrowbridgeReitz| {
+            distribution.visible_normal_pdf(wo, &h) / (4.0 * wo.dot(&h).abs())
+        };
+        p[0] * wi.z() / PI + p[1] * lobe(&shading.specular) + p[2] * lobe(&shading.coat) + glass
+    }
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        let frame = hit_record.shading;
+        let wo = frame.to_local(&-r_in.dir().unit_vector());
+        if wo.z() <= 0.0 {
+            return false;
+        }
+        let shading = self.shading(hit_record, &wo);
+        if !hit_record.front_face {
+            return shading
+                .glass
+                .scatter(r_in, hit_record, attenuation, scattered);
+        }
+
+        let p = shading.probabilities;
+        let u = random_f64();
+        let wi = if u < p[0] {
+            let wi = Vec3(0.0, 0.0, 1.0) + Vec3::random_unit_vector();
+            if wi.near_zero() {
+                Vec3(0.0, 0.0, 1.0)
+            } else {
+                wi.unit_vector()
+            }
+        } else if u < p[0] + p[1] {
+            let m = shading
+                .specular
+                .sample_visible_normal(&wo, random_f64(), random_f64());
+            reflect(&wo, &m)
+        } else if u < p[0] + p[1] + p[2] {
+            let m = shading
+                .coat
+                .sample_visible_normal(&wo, random_f64(), random_f64());
+            reflect(&wo, &m)
+        } else {
+            let mut glass_attenuation = Color(0.0, 0.0, 0.0);
+            if !shading
+                .glass
+                .scatter(r_in, hit_record, &mut glass_attenuation, scattered)
+            {
+                return false;
+            }
+            frame.to_local(&scattered.dir().unit_vector())
+        };
+        if wi.z() == 0.0 {
+            return false;
+        }
+        let next = Ray::new_with_tm(hit_record.p, frame.local(&wi), r_in.time());
+
+        // Weigh by the density of all lobes together, so any lobe could have
+        // picked this direction. Reflections sent below the surface have none.
+        let pdf = Principled::lobes_pdf(&shading, r_in, hit_record, &next, &wo, &wi);
+        if pdf <= 0.0 {
+            return false;
+        }
+        *attenuation = Principled::lobes_eval(&shading, r_in, hit_record, &next, &wo, &wi) / pdf;
+        *scattered = next;
+        true
+    }
+    fn emitted(&self, _r_in: &Ray, hit_record: &HitRecord) -> Color {
+        match &self.emission {
+            Some(emission) if hit_record.front_face => {
+                emission.value(hit_record.u, hit_record.v, &hit_record.p)
+            }
+            _ => Color(0.0, 0.0, 0.0),
+        }
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        let (wo, wi) = Principled::local_directions(r_in, hit_record, scattered);
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        let shading = self.shading(hit_record, &wo);
+        Principled::lobes_eval(&shading, r_in, hit_record, scattered, &wo, &wi)
+    }
+    fn pdf(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        let (wo, wi) = Principled::local_directions(r_in, hit_record, scattered);
+        if wo.z() <= 0.0 || wi.z() == 0.0 {
+            return 0.0;
+        }
+        let shading = self.shading(hit_record, &wo);
+        Principled::lobes_pdf(&shading, r_in, hit_record, scattered, &wo, &wi)
+    }
+    fn is_specular(&self) -> bool {
+        false
+    }
+    fn is_emissive(&self) -> bool {
+        self.emission.is_some()
+    }
+    /// Judged by the emission texture at the middle of the surface.
+    fn emission(&self) -> Color {
+        match &self.emission {
+            Some(emission) => emission.value(0.5, 0.5, &Vec3(0.0, 0.0, 0.0)),
+            None => Color(0.0, 0.0, 0.0),
+        }
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(self.clone())
+    }
+}
+
+fn lerp(a: Color, b: Color, t: f64) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+/// Schlick's approximation to the Fresnel reflectance for a normal incidence
+/// reflectance of `f0`.
+fn schlick(f0: Color, cos_theta: f64) -> Color {
+    f0 + (Color(1.0, 1.0, 1.0) - f0) * (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::testing::{assert_scatter_matches, hit_at};
+
+    use Vec3 as Point3;
+
+    fn scalar(value: f64) -> Arc<dyn Texture + Sync + Send> {
+        Arc::new(SolidColor::scalar(value))
+    }
+
+    fn glass(roughness: f64) -> Principled {
+        let mut glass = Principled::new(Color(1.0, 1.0, 1.0));
+        glass.transmission = scalar(1.0);
+        glass.roughness = scalar(roughness);
+        glass
+    }
+
+    #[test]
+    fn scatter_follows_eval_and_pdf() {
+        let mut metal = Principled::new(Color(1.0, 0.78, 0.34));
+        metal.metallic = scalar(1.0);
+        metal.roughness = scalar(0.6);
+        let mut lacquer = Principled::new(Color(0.15, 0.05, 0.3));
+        lacquer.sheen = scalar(1.0);
+        lacquer.clearcoat = scalar(1.0);
+        lacquer.clearcoat_gloss = 0.5;
+        let mut frosted = glass(0.6);
+        frosted.base_color = Arc::new(SolidColor::new(Color(0.7, 0.85, 1.0)));
+
+        let materials = [
+            Principled::new(Color(0.8, 0.3, 0.2)),
+            metal,
+            lacquer,
+            frosted,
+        ];
+        for mat in materials.iter() {
+            for degrees in [0.0, 60.0] {
+                let (r_in, rec) = hit_at(degrees);
+                let (pdf, _) = assert_scatter_matches(mat, &r_in, &rec, 100000);
+                assert!(pdf <= 1.0 + 1e-2 && pdf > 0.7, "{pdf}");
+            }
+        }
+    }
+
+    #[test]
+    fn glass_scatters_from_inside() {
+        let r_in = Ray::new(Point3(-0.5, 0.0, -1.0), Vec3(0.5, 0.0, 1.0));
+        let mut rec = HitRecord::new();
+        rec.t = 1.0;
+        rec.set_face_normal(&r_in, &Vec3(0.0, 0.0, 1.0));
+        rec.set_tangents(Vec3(1.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0));
+        assert!(!rec.front_face);
+        assert_scatter_matches(&glass(0.6), &r_in, &rec, 100000);
+    }
+
+    #[test]
+    fn clear_white_glass_keeps_its_reflection() {
+        // Rough glass only loses the light its facets shadow, however much
+        // of the rest reflects off the surface at a grazing angle.
+        let mat = glass(0.3);
+        for degrees in [0.0, 75.0] {
+            let (r_in, rec) = hit_at(degrees);
+            let (_, albedo) = assert_scatter_matches(&mat, &r_in, &rec, 100000);
+            assert!(albedo.x() > 0.95 && albedo.x() < 1.0 + 1e-2, "{albedo:?}");
+        }
+    }
+}
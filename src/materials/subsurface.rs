@@ -0,0 +1,345 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use super::dielectric::Dielectric;
+use super::henyey_greenstein::HenyeyGreenstein;
+use super::material::Material;
+use crate::{
+    hittable::{HitRecord, Hittable, RAY_EPSILON},
+    random::random_f64,
+    types::{interval::Interval, ray::Ray, vec3::Vec3},
+};
+
+use Vec3 as Color;
+
+/// Most scattering events a walk takes before it is given up as lost.
+const MAX_STEPS: usize = 256;
+
+/// Translucent material for skin, wax, marble and milk: light refracts in
+/// through a dielectric surface, random walks through a scattering medium
+/// inside and leaves again somewhere else ("Practical and Controllable
+/// Subsurface Scattering for Production Path Tracing", Chiang et al. 2016).
+///
+/// The walk needs to know the shape it is inside of, so the material only
+/// works on an object wrapped in a `SubsurfaceVolume`, which must be closed.
+/// Coefficients are per unit of scene distance and per color channel.
+#[derive(Clone)]
+pub struct Subsurface {
+    pub ir: f64,
+    /// GGX roughness of the surface.
+    pub roughness: f64,
+    pub sigma_a: Color,
+    pub sigma_s: Color,
+    /// Henyey-Greenstein asymmetry of the scattering inside.
+    pub g: f64,
+    boundary: Option<Arc<dyn Hittable + Sync + Send>>,
+}
+
+impl Subsurface {
+    /// A material that looks `color` from afar, where light travels about
+    /// `mean_free_path` between collisions inside, per channel. Longer paths
+    /// make it more translucent and blur its shading more.
+    pub fn new(ir: f64, color: Color, mean_free_path: Color) -> Subsurface {
+        // Single-scattering albedo that gives the walk an overall albedo of
+        // `color`, fitted by Chiang et al.
+        let single_albedo = |a: f64| {
+            let a = a.clamp(0.0, 0.999);
+            let x = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+            1.0 - x * x
+        };
+        let sigma_t = Color(
+            1.0 / mean_free_path.x(),
+            1.0 / mean_free_path.y(),
+            1.0 / mean_free_path.z(),
+        );
+        let albedo = Color(
+            single_albedo(color.x()),
+            single_albedo(color.y()),
+            single_albedo(color.z()),
+        );
+        let sigma_s = sigma_t * albedo;
+        Subsurface::from_coefficients(ir, sigma_t - sigma_s, sigma_s)
+    }
+
+    /// A material with absorption `sigma_a` and scattering `sigma_s`
+    /// coefficients, as measured.
+    pub fn from_coefficients(ir: f64, sigma_a: Color, sigma_s: Color) -> Subsurface {
+        Subsurface {
+            ir,
+            roughness: 0.0,
+            sigma_a,
+            sigma_s,
+            g: 0.0,
+            boundary: None,
+        }
+    }
+
+    // Measured by Jensen et al. 2001 in mm⁻¹, scaled to a scene where one
+    // unit is `mm_per_unit` millimetres. The scattering coefficients are the
+    // reduced ones, σs' = σs (1 - g), for isotropic scattering;
+    // `with_anisotropy` turns them into the real ones.
+
+    pub fn skin(mm_per_unit: f64) -> Subsurface {
+        Subsurface::from_coefficients(
+            1.3,
+            Color(0.032, 0.17, 0.48) * mm_per_unit,
+            Color(0.74, 0.88, 1.01) * mm_per_unit,
+        )
+    }
+
+    pub fn marble(mm_per_unit: f64) -> Subsurface {
+        Subsurface::from_coefficients(
+            1.5,
+            Color(0.0021, 0.0041, 0.0071) * mm_per_unit,
+            Color(2.19, 2.62, 3.00) * mm_per_unit,
+        )
+    }
+
+    pub fn whole_milk(mm_per_unit: f64) -> Subsurface {
+        Subsurface::from_coefficients(
+            1.3,
+            Color(0.0011, 0.0024, 0.014) * mm_per_unit,
+            Color(2.55, 3.21, 3.77) * mm_per_unit,
+        )
+    }
+
+    pub fn with_roughness(mut self, roughness: f64) -> Subsurface {
+        self.roughness = roughness;
+        self
+    }
+
+    /// Scatters with Henyey-Greenstein asymmetry `g`, keeping the reduced
+    /// scattering coefficient `sigma_s * (1 - g)` the same. Deep inside, light
+    /// then spreads out as it did before, so the material keeps its color and
+    /// translucency while single bounces near the surface point forwards.
+    /// Set `g` directly to keep `sigma_s` as it is instead.
+    pub fn with_anisotropy(mut self, g: f64) -> Subsurface {
+        self.sigma_s = self.sigma_s * ((1.0 - self.g) / (1.0 - g));
+        self.g = g;
+        self
+    }
+
+    /// The same material walking inside `boundary`.
+    pub fn filling(mut self, boundary: Arc<dyn Hittable + Sync + Send>) -> Subsurface {
+        self.boundary = Some(boundary);
+        self
+    }
+
+    /// The glossy boundary. It is kept from becoming a perfect mirror so
+    /// that lights can be sampled at the surface, for the walk's exits.
+    fn surface(&self) -> Dielectric {
+        Dielectric::rough(self.ir, (self.roughness * self.roughness).max(1e-3))
+    }
+
+    /// Follows `ray` from just inside `boundary` to the surface, returning
+    /// the last stretch of the walk, which ends where it leaves, and the
+    /// fraction of light it carries. Distances are sampled from one channel
+    /// and weighted by their density over all of them, so the walk works for
+    /// media that scatter differently per color.
+    fn walk(&self, boundary: &(dyn Hittable + Sync + Send), ray: &Ray) -> Option<(Ray, Color)> {
+        let sigma_t = self.sigma_a + self.sigma_s;
+        let phase = HenyeyGreenstein::new(Color(1.0, 1.0, 1.0), self.g);
+        let mut throughput = Color(1.0, 1.0, 1.0);
+        let mut ray = Ray::new_with_tm(ray.origin(), ray.dir().unit_vector(), ray.time())
+            .with_wavelength(ray.wavelength());
+        // Only the first stretch starts on the surface. Later ones start
+        // inside, where skipping ahead could pass a nearby boundary.
+        let mut t_min = RAY_EPSILON;
+
+        for _ in 0..MAX_STEPS {
+            let mut rec = HitRecord::new();
+            if !boundary.hit(&ray, &Interval::new(t_min, f64::INFINITY), &mut rec) {
+                return None;
+            }
+
+            // Favor the channels still carrying the most light, so their
+            // weights stay close to one.
+            let total = throughput.x() + throughput.y() + throughput.z();
+            let weights = throughput / total;
+            let u = random_f64();
+            let channel = if u < weights.x() {
+                sigma_t.x()
+            } else if u < weights.x() + weights.y() {
+                sigma_t.y()
+            } else {
+                sigma_t.z()
+            };
+            let distance = -(1.0 - random_f64()).ln() / channel;
+            if distance >= rec.t {
+                let transmittance = exp(-sigma_t * rec.t);
+                let pdf = weights.dot(&transmittance);
+                // Start the last stretch far enough short of the exit for
+                // the next hit to find it.
+                let last =
+                    Ray::new_with_tm(ray.at(rec.t - 2.0 * RAY_EPSILON), ray.dir(), ray.time())
+                        .with_wavelength(ray.wavelength());
+                return Some((last, throughput * transmittance / pdf));
+            }
+
+            let transmittance = exp(-sigma_t * distance);
+            let pdf = weights.dot(&(sigma_t * transmittance));
+            throughput = throughput * self.sigma_s * transmittance / pdf;
+            let direction = phase.sample_direction(&ray.dir());
+            ray = Ray::new_with_tm(ray.at(distance), direction, ray.time())
+                .with_wavelength(ray.wavelength());
+            t_min = 0.0;
+
+            let survival = throughput
+                .x()
+                .max(throughput.y())
+                .max(throughput.z())
+                .min(1.0);
+            if random_f64() >= survival {
+                return None;
+            }
+            throughput = throughput / survival;
+        }
+        None
+    }
+}
+
+impl Material for Subsurface {
+    /// From outside, light reflects off the surface or refracts in and walks
+    /// to where it leaves, stopping just short. The next hit, from inside,
+    /// sends it out diffusely like Cycles does, so that lights can be
+    /// sampled at the exit.
+    fn scatter(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        attenuation: &mut Color,
+        scattered: &mut Ray,
+    ) -> bool {
+        if !hit_record.front_face {
+            let mut direction = -hit_record.normal + Vec3::random_unit_vector();
+            if direction.near_zero() {
+                direction = -hit_record.normal;
+            }
+            *scattered = Ray::new_with_tm(hit_record.p, direction, r_in.time());
+            *attenuation = Color(1.0, 1.0, 1.0);
+            return true;
+        }
+
+        if !self
+            .surface()
+            .scatter(r_in, hit_record, attenuation, scattered)
+        {
+            return false;
+        }
+        let boundary = match &self.boundary {
+            Some(boundary) => boundary,
+            None => return true,
+        };
+        if scattered.dir().dot(&hit_record.normal) > 0.0 {
+            return true;
+        }
+
+        match self.walk(boundary.as_ref(), scattered) {
+            Some((last, throughput)) => {
+                *scattered = last;
+                *attenuation = *attenuation * throughput;
+                true
+            }
+            None => false,
+        }
+    }
+    fn eval(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> Color {
+        if hit_record.front_face {
+            return self.surface().eval(r_in, hit_record, scattered);
+        }
+        let cos_theta = -scattered.dir().unit_vector().dot(&hit_record.normal);
+        Color(1.0, 1.0, 1.0) * (cos_theta.max(0.0) / PI)
+    }
+    fn pdf(&self, r_in: &Ray, hit_record: &HitRecord, scattered: &Ray) -> f64 {
+        if hit_record.front_face {
+            return self.surface().pdf(r_in, hit_record, scattered);
+        }
+        let cos_theta = -scattered.dir().unit_vector().dot(&hit_record.normal);
+        cos_theta.max(0.0) / PI
+    }
+    fn is_specular(&self) -> bool {
+        false
+    }
+    fn my_copy(&self) -> Box<dyn Material + Sync + Send> {
+        Box::new(self.clone())
+    }
+}
+
+fn exp(v: Color) -> Color {
+    Color(v.x().exp(), v.y().exp(), v.z().exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::material::Nothing;
+    use crate::sphere::Sphere;
+
+    use Vec3 as Point3;
+
+    // A ball with its top at the origin.
+    fn ball(radius: f64) -> Sphere {
+        Sphere::new(Point3(0.0, 0.0, -radius), radius, Box::new(Nothing {}))
+    }
+
+    /// Light coming back out of `boundary` on average, for walks entering at
+    /// its top from all around, which is what the albedo fit assumes.
+    fn mean_walk(mat: &Subsurface, boundary: &Sphere, n: usize) -> Color {
+        let mut sum = Color(0.0, 0.0, 0.0);
+        for _ in 0..n {
+            let mut dir = Vec3(0.0, 0.0, -1.0) + Vec3::random_unit_vector();
+            if dir.near_zero() {
+                dir = Vec3(0.0, 0.0, -1.0);
+            }
+            let entry = Ray::new(Point3(0.0, 0.0, 0.0), dir);
+            if let Some((last, throughput)) = mat.walk(boundary, &entry) {
+                // The last stretch must reach the surface again from inside.
+                let mut rec = HitRecord::new();
+                let ray_t = Interval::new(RAY_EPSILON, f64::INFINITY);
+                assert!(boundary.hit(&last, &ray_t, &mut rec) && !rec.front_face);
+                sum += throughput;
+            }
+        }
+        sum / n as f64
+    }
+
+    #[test]
+    fn walks_without_absorption_keep_their_energy() {
+        // Each channel scatters at its own rate, so the walk has to weigh
+        // the distances it samples from one by their density in the others.
+        let boundary = ball(1.0);
+        for g in [0.0, 0.5] {
+            let mat = Subsurface::from_coefficients(
+                1.0,
+                Color(0.0, 0.0, 0.0),
+                Color(0.5, 1.0, 2.0) * (1.0 - g),
+            )
+            .with_anisotropy(g);
+            let albedo = mean_walk(&mat, &boundary, 20000);
+            for channel in [albedo.x(), albedo.y(), albedo.z()] {
+                assert!((channel - 1.0).abs() < 0.03, "{g}: {albedo:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn walks_come_back_with_the_requested_color() {
+        // A ball a hundred mean free paths across is as good as a half space.
+        let color = Color(0.2, 0.5, 0.8);
+        let mat = Subsurface::new(1.0, color, Color(0.01, 0.01, 0.01));
+        let albedo = mean_walk(&mat, &ball(1.0), 20000);
+        assert!((albedo - color).length() < 0.03, "{albedo:?}");
+    }
+
+    #[test]
+    fn anisotropy_keeps_the_reduced_scattering() {
+        let milk = Subsurface::whole_milk(1.0);
+        let forwards = Subsurface::whole_milk(1.0).with_anisotropy(0.7);
+        let reduced = forwards.sigma_s * (1.0 - forwards.g);
+        assert!((reduced - milk.sigma_s).length() < 1e-12);
+        assert_eq!(forwards.sigma_a.x(), milk.sigma_a.x());
+        // Changing it again starts from the same reduced coefficients.
+        let back = forwards.with_anisotropy(0.0);
+        assert!((back.sigma_s - milk.sigma_s).length() < 1e-12);
+    }
+}
@@ -0,0 +1,124 @@
+//! Checks shared by the material tests, that `scatter`, `eval` and `pdf`
+//! describe the same distribution of directions.
+
+use std::f64::consts::PI;
+
+use super::material::Material;
+use crate::hittable::HitRecord;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+
+// The sphere of directions is split into bands of cos(theta) around +z and
+// sectors of phi, integrated with this many rings and steps around each.
+const BANDS: usize = 8;
+const SECTORS: usize = 4;
+const RINGS: usize = 400;
+const STEPS: usize = 400;
+
+/// A hit at the origin on a surface facing +z, with its tangent along x, for
+/// a ray arriving `degrees` away from the normal.
+pub fn hit_at(degrees: f64) -> (Ray, HitRecord) {
+    let theta = degrees.to_radians();
+    let dir = Vec3(theta.sin(), 0.0, -theta.cos());
+    let r_in = Ray::new(-dir, dir);
+    let mut rec = HitRecord::new();
+    rec.t = 1.0;
+    rec.set_face_normal(&r_in, &Vec3(0.0, 0.0, 1.0));
+    rec.set_tangents(Vec3(1.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0));
+    (r_in, rec)
+}
+
+fn cell(dir: &Vec3) -> usize {
+    let dir = dir.unit_vector();
+    let band = (((1.0 - dir.z()) / 2.0 * BANDS as f64) as usize).min(BANDS - 1);
+    let phi = dir.y().atan2(dir.x()) + PI;
+    let sector = ((phi / (2.0 * PI) * SECTORS as f64) as usize).min(SECTORS - 1);
+    band * SECTORS + sector
+}
+
+/// `f` integrated over each cell of the sphere of directions.
+fn integrate_cells(f: impl Fn(&Vec3) -> Color) -> Vec<Color> {
+    let mut cells = vec![Color(0.0, 0.0, 0.0); BANDS * SECTORS];
+    // Even steps in theta, so lobes as narrow as a refraction straight
+    // through the surface still span many rings.
+    for i in 0..RINGS {
+        let theta = PI * (i as f64 + 0.5) / RINGS as f64;
+        let (z, radius) = (theta.cos(), theta.sin());
+        let weight = radius * PI / RINGS as f64 * 2.0 * PI / STEPS as f64;
+        for j in 0..STEPS {
+            let phi = 2.0 * PI * (j as f64 + 0.5) / STEPS as f64 - PI;
+            let dir = Vec3(radius * phi.cos(), radius * phi.sin(), z);
+            cells[cell(&dir)] += f(&dir) * weight;
+        }
+    }
+    cells
+}
+
+/// Checks over every cell of the sphere that the share of the directions
+/// `scatter` picks matches `pdf` integrated over it, and that their summed
+/// attenuation matches `eval` integrated over it, as it does when the
+/// attenuation is `eval / pdf`. Directions from specular parts of the
+/// material, which `pdf` and `eval` leave out, are skipped, and a scatter
+/// that fails still counts where its direction went.
+///
+/// Returns `pdf` and `eval` integrated over the whole sphere.
+pub fn assert_scatter_matches(
+    mat: &dyn Material,
+    r_in: &Ray,
+    rec: &HitRecord,
+    samples: usize,
+) -> (f64, Color) {
+    let towards = |dir: &Vec3| Ray::new_with_tm(rec.p, *dir, r_in.time());
+    let pdf_cells = integrate_cells(|dir| {
+        let pdf = mat.pdf(r_in, rec, &towards(dir));
+        Color(pdf, pdf, pdf)
+    });
+    let eval_cells = integrate_cells(|dir| mat.eval(r_in, rec, &towards(dir)));
+
+    let mut shares = vec![0.0; BANDS * SECTORS];
+    let mut weights = vec![Color(0.0, 0.0, 0.0); BANDS * SECTORS];
+    for _ in 0..samples {
+        let mut attenuation = Color(0.0, 0.0, 0.0);
+        let mut scattered = Ray::new(rec.p, Vec3(0.0, 0.0, 0.0));
+        let scatters = mat.scatter(r_in, rec, &mut attenuation, &mut scattered);
+        if scattered.dir().near_zero() || mat.is_specular_sample(r_in, rec, &scattered) {
+            continue;
+        }
+        let i = cell(&scattered.dir());
+        shares[i] += 1.0 / samples as f64;
+        if scatters {
+            weights[i] += attenuation / samples as f64;
+        }
+    }
+
+    for i in 0..BANDS * SECTORS {
+        let (share, pdf) = (shares[i], pdf_cells[i].x());
+        assert!(
+            (share - pdf).abs() < 0.01 + 0.05 * pdf,
+            "cell {i}: {share} of the samples, pdf gives {pdf}"
+        );
+        let (weight, eval) = (weights[i], eval_cells[i]);
+        for (w, e) in [
+            (weight.x(), eval.x()),
+            (weight.y(), eval.y()),
+            (weight.z(), eval.z()),
+        ] {
+            assert!(
+                (w - e).abs() < 0.01 + 0.05 * e,
+                "cell {i}: {weight:?} from the samples, eval gives {eval:?}"
+            );
+        }
+    }
+
+    // Errors too small to show in any one cell still add up over the sphere.
+    let pdf_total = pdf_cells.iter().map(|c| c.x()).sum();
+    let sum = |cells: &[Color]| cells.iter().fold(Color(0.0, 0.0, 0.0), |sum, c| sum + *c);
+    let (weight_total, eval_total) = (sum(&weights), sum(&eval_cells));
+    assert!(
+        (weight_total - eval_total).length() < 0.005 + 0.02 * eval_total.length(),
+        "{weight_total:?} from the samples, eval gives {eval_total:?}"
+    );
+    (pdf_total, eval_total)
+}
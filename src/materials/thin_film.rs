@@ -0,0 +1,161 @@
+use std::f64::consts::PI;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// A thin transparent coating, like soap or oil, whose reflections off its
+/// top and bottom interfere and color the surface depending on the angle.
+#[derive(Clone, Copy)]
+pub struct ThinFilm {
+    /// Thickness in nm.
+    pub thickness: f64,
+    pub ior: f64,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: f64, ior: f64) -> ThinFilm {
+        ThinFilm { thickness, ior }
+    }
+
+    /// Unpolarized reflectance at `wavelength` in nm, summing every bounce
+    /// inside the film (Airy). Light arrives at `cos_theta` to the normal from
+    /// a medium of index `outside` onto the film, which lies over a substrate
+    /// with complex index of refraction `eta + i k`.
+    pub fn reflectance(
+        &self,
+        cos_theta: f64,
+        outside: f64,
+        eta: f64,
+        k: f64,
+        wavelength: f64,
+    ) -> f64 {
+        let n0 = Complex::real(outside);
+        let n1 = Complex::real(self.ior);
+        let n2 = Complex(eta, k);
+        let cos0 = Complex::real(cos_theta.clamp(0.0, 1.0));
+        let sin2 = Complex::real(1.0 - cos_theta * cos_theta);
+        // Snell's law, n sin is conserved; complex cosines describe waves
+        // that die out past total internal reflection or in a metal.
+        let cos_in = |n: Complex| (Complex::real(1.0) - sin2 * (n0 * n0) / (n * n)).sqrt();
+        let cos1 = cos_in(n1);
+        let cos2 = cos_in(n2);
+
+        // Phase the wave picks up crossing the film and back.
+        let delta = n1 * cos1 * (4.0 * PI * self.thickness / wavelength);
+        let phase = (Complex(0.0, 1.0) * delta).exp();
+        let airy = |r01: Complex, r12: Complex| {
+            ((r01 + r12 * phase) / (Complex::real(1.0) + r01 * r12 * phase)).norm_sqr()
+        };
+
+        let rs = |na: Complex, ca: Complex, nb: Complex, cb: Complex| {
+            (na * ca - nb * cb) / (na * ca + nb * cb)
+        };
+        let rp = |na: Complex, ca: Complex, nb: Complex, cb: Complex| {
+            (nb * ca - na * cb) / (nb * ca + na * cb)
+        };
+        let s = airy(rs(n0, cos0, n1, cos1), rs(n1, cos1, n2, cos2));
+        let p = airy(rp(n0, cos0, n1, cos1), rp(n1, cos1, n2, cos2));
+        ((s + p) / 2.0).clamp(0.0, 1.0)
+    }
+}
+
+/// Just enough complex arithmetic for the Fresnel amplitudes.
+#[derive(Clone, Copy)]
+struct Complex(f64, f64);
+
+impl Complex {
+    fn real(x: f64) -> Complex {
+        Complex(x, 0.0)
+    }
+
+    fn norm_sqr(self) -> f64 {
+        self.0 * self.0 + self.1 * self.1
+    }
+
+    fn exp(self) -> Complex {
+        let r = self.0.exp();
+        Complex(r * self.1.cos(), r * self.1.sin())
+    }
+
+    /// Principal square root, with a non-negative real part.
+    fn sqrt(self) -> Complex {
+        let r = self.norm_sqr().sqrt();
+        let re = ((r + self.0) / 2.0).max(0.0).sqrt();
+        let im = ((r - self.0) / 2.0).max(0.0).sqrt();
+        Complex(re, if self.1 < 0.0 { -im } else { im })
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, o: Complex) -> Complex {
+        Complex(self.0 + o.0, self.1 + o.1)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, o: Complex) -> Complex {
+        Complex(self.0 - o.0, self.1 - o.1)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, o: Complex) -> Complex {
+        Complex(self.0 * o.0 - self.1 * o.1, self.0 * o.1 + self.1 * o.0)
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Complex;
+    fn mul(self, s: f64) -> Complex {
+        Complex(self.0 * s, self.1 * s)
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, o: Complex) -> Complex {
+        let d = o.norm_sqr();
+        Complex(
+            (self.0 * o.0 + self.1 * o.1) / d,
+            (self.1 * o.0 - self.0 * o.1) / d,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::microfacet::{fresnel_conductor, fresnel_dielectric};
+
+    const COSINES: [f64; 5] = [1.0, 0.8, 0.5, 0.2, 0.05];
+
+    #[test]
+    fn vanishing_film_leaves_plain_fresnel() {
+        let film = ThinFilm::new(0.0, 1.33);
+        for cos_theta in COSINES {
+            let glass = film.reflectance(cos_theta, 1.0, 1.5, 0.0, 550.0);
+            assert!((glass - fresnel_dielectric(cos_theta, 1.5)).abs() < 1e-9);
+            let gold = film.reflectance(cos_theta, 1.0, 0.374, 2.385, 550.0);
+            assert!((gold - fresnel_conductor(cos_theta, 0.374, 2.385)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn film_matching_the_outside_leaves_plain_fresnel() {
+        let film = ThinFilm::new(300.0, 1.0);
+        for cos_theta in COSINES {
+            let glass = film.reflectance(cos_theta, 1.0, 1.5, 0.0, 550.0);
+            assert!((glass - fresnel_dielectric(cos_theta, 1.5)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn quarter_wave_coating_cancels_reflection() {
+        let ior = 1.5_f64.sqrt();
+        let film = ThinFilm::new(550.0 / (4.0 * ior), ior);
+        assert!(film.reflectance(1.0, 1.0, 1.5, 0.0, 550.0) < 1e-9);
+        // Away from its design wavelength some light comes back.
+        assert!(film.reflectance(1.0, 1.0, 1.5, 0.0, 400.0) > 1e-3);
+    }
+}
@@ -0,0 +1,273 @@
+use std::f64::consts::PI;
+
+use crate::types::interval::Interval;
+use crate::types::vec3::Vec3;
+
+// Coefficients closer to zero than this are treated as zero by the solvers.
+const EQN_EPS: f64 = 1e-9;
+
+fn is_zero(x: f64) -> bool {
+    x > -EQN_EPS && x < EQN_EPS
+}
+
+/// Real roots of `a t^2 + b t + c = 0` in ascending order.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None;
+        }
+        let t = -c / b;
+        return Some((t, t));
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    // Avoid cancellation by never subtracting two values of similar magnitude.
+    let root = discriminant.sqrt();
+    let q = if b < 0.0 {
+        -0.5 * (b - root)
+    } else {
+        -0.5 * (b + root)
+    };
+    let (mut t0, mut t1) = (q / a, if q != 0.0 { c / q } else { q / a });
+    if t0 > t1 {
+        std::mem::swap(&mut t0, &mut t1);
+    }
+    Some((t0, t1))
+}
+
+/// Real roots of `c[3] x^3 + c[2] x^2 + c[1] x + c[0] = 0`, unsorted.
+pub fn solve_cubic(c: [f64; 4]) -> Vec<f64> {
+    // Normal form: x^3 + Ax^2 + Bx + C = 0
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let cc = c[0] / c[3];
+
+    // Substitute x = y - A/3 to eliminate the quadratic term: y^3 + py + q = 0
+    let sq_a = a * a;
+    let p = 1.0 / 3.0 * (-1.0 / 3.0 * sq_a + b);
+    let q = 1.0 / 2.0 * (2.0 / 27.0 * a * sq_a - 1.0 / 3.0 * a * b + cc);
+
+    let cb_p = p * p * p;
+    let d = q * q + cb_p;
+
+    let mut roots = Vec::with_capacity(3);
+    if is_zero(d) {
+        if is_zero(q) {
+            roots.push(0.0);
+        } else {
+            let u = (-q).cbrt();
+            roots.push(2.0 * u);
+            roots.push(-u);
+        }
+    } else if d < 0.0 {
+        // Casus irreducibilis: three real solutions.
+        let phi = 1.0 / 3.0 * (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos();
+        let t = 2.0 * (-p).sqrt();
+        roots.push(t * phi.cos());
+        roots.push(-t * (phi + PI / 3.0).cos());
+        roots.push(-t * (phi - PI / 3.0).cos());
+    } else {
+        let sqrt_d = d.sqrt();
+        let u = (sqrt_d - q).cbrt();
+        let v = -(sqrt_d + q).cbrt();
+        roots.push(u + v);
+    }
+
+    let sub = 1.0 / 3.0 * a;
+    roots.iter().map(|x| x - sub).collect()
+}
+
+/// Real roots of `c[4] x^4 + c[3] x^3 + c[2] x^2 + c[1] x + c[0] = 0`, unsorted.
+///
+/// Uses Ferrari's method and refines each root with a few Newton steps, which
+/// matters for the torus where the coefficients span many orders of magnitude.
+pub fn solve_quartic(c: [f64; 5]) -> Vec<f64> {
+    // Normal form: x^4 + Ax^3 + Bx^2 + Cx + D = 0
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let cc = c[1] / c[4];
+    let d = c[0] / c[4];
+
+    // Substitute x = y - A/4 to eliminate the cubic term: y^4 + py^2 + qy + r = 0
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = 1.0 / 8.0 * sq_a * a - 1.0 / 2.0 * a * b + cc;
+    let r = -3.0 / 256.0 * sq_a * sq_a + 1.0 / 16.0 * sq_a * b - 1.0 / 4.0 * a * cc + d;
+
+    let mut roots;
+    if is_zero(r) {
+        // No absolute term: y(y^3 + py + q) = 0
+        roots = solve_cubic([q, p, 0.0, 1.0]);
+        roots.push(0.0);
+    } else {
+        // Take one real solution of the resolvent cubic ...
+        let z = solve_cubic([
+            1.0 / 2.0 * r * p - 1.0 / 8.0 * q * q,
+            -r,
+            -1.0 / 2.0 * p,
+            1.0,
+        ])[0];
+
+        // ... to build two quadratic equations.
+        let mut u = z * z - r;
+        let mut v = 2.0 * z - p;
+        if is_zero(u) {
+            u = 0.0;
+        } else if u > 0.0 {
+            u = u.sqrt();
+        } else {
+            return Vec::new();
+        }
+        if is_zero(v) {
+            v = 0.0;
+        } else if v > 0.0 {
+            v = v.sqrt();
+        } else {
+            return Vec::new();
+        }
+
+        roots = Vec::with_capacity(4);
+        let sign = if q < 0.0 { -1.0 } else { 1.0 };
+        for (k0, k1) in [(z - u, sign * v), (z + u, -sign * v)] {
+            if let Some((t0, t1)) = solve_quadratic(1.0, k1, k0) {
+                roots.push(t0);
+                if t1 != t0 {
+                    roots.push(t1);
+                }
+            }
+        }
+    }
+
+    let sub = 1.0 / 4.0 * a;
+    roots
+        .iter()
+        .map(|x| {
+            let mut x = x - sub;
+            for _ in 0..2 {
+                let f = (((c[4] * x + c[3]) * x + c[2]) * x + c[1]) * x + c[0];
+                let df = ((4.0 * c[4] * x + 3.0 * c[3]) * x + 2.0 * c[2]) * x + c[1];
+                if df == 0.0 {
+                    break;
+                }
+                x -= f / df;
+            }
+            x
+        })
+        .collect()
+}
+
+/// Angle of the point `(x, z)` around the vertical axis, in `[0, 2π)`.
+pub fn azimuth(x: f64, z: f64) -> f64 {
+    let phi = z.atan2(x);
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+/// Intersects a horizontal disk at height `y` centered on the local vertical
+/// axis, returning the ray parameter and local hit point when the hit lies
+/// within `radius`, inside the `phi_max` sweep and strictly inside `ray_t`.
+pub fn hit_disk(
+    origin: &Vec3,
+    dir: &Vec3,
+    y: f64,
+    radius: f64,
+    phi_max: f64,
+    ray_t: &Interval,
+) -> Option<(f64, Vec3)> {
+    if dir.y() == 0.0 {
+        return None;
+    }
+    let t = (y - origin.y()) / dir.y();
+    if !ray_t.surrounds(t) {
+        return None;
+    }
+    let p = *origin + t * *dir;
+    if p.x() * p.x() + p.z() * p.z() > radius * radius || azimuth(p.x(), p.z()) > phi_max {
+        return None;
+    }
+    Some((t, p))
+}
+
+/// How a point `p` on such a disk moves as its `v`, the distance from the
+/// center over `radius`, grows.
+pub fn disk_dpdv(p: &Vec3, radius: f64) -> Vec3 {
+    let rho = (p.x() * p.x() + p.z() * p.z()).sqrt();
+    if rho == 0.0 {
+        return Vec3(0.0, 0.0, 0.0);
+    }
+    Vec3(p.x(), 0.0, p.z()) * (radius / rho)
+}
+
+/// Solid angles outside this range are sampled by area instead: tiny ones
+/// lose precision and near-hemispheres are badly conditioned.
+pub const MIN_SPHERICAL_SAMPLE_AREA: f64 = 3e-4;
+pub const MAX_SPHERICAL_SAMPLE_AREA: f64 = 6.22;
+
+/// MIS weight for a sample drawn with density `pdf` when `other_pdf` could
+/// also have produced it (Veach's power heuristic with beta = 2).
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let a = pdf * pdf;
+    let b = other_pdf * other_pdf;
+    if a + b == 0.0 {
+        return 0.0;
+    }
+    a / (a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(mut roots: Vec<f64>, expected: &[f64]) {
+        roots.sort_by(f64::total_cmp);
+        assert_eq!(roots.len(), expected.len(), "{roots:?}");
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-9, "{roots:?}");
+        }
+    }
+
+    #[test]
+    fn quadratic_roots_are_ascending() {
+        assert_eq!(solve_quadratic(1.0, -3.0, 2.0), Some((1.0, 2.0)));
+        assert_eq!(solve_quadratic(-1.0, 3.0, -2.0), Some((1.0, 2.0)));
+        assert_eq!(solve_quadratic(1.0, 0.0, 1.0), None);
+    }
+
+    #[test]
+    fn cubic_with_three_real_roots() {
+        // (x - 1)(x + 2)(x - 3)
+        assert_roots(solve_cubic([6.0, -5.0, -2.0, 1.0]), &[-2.0, 1.0, 3.0]);
+    }
+
+    #[test]
+    fn cubic_with_one_real_root() {
+        // (x - 2)(x^2 + 1)
+        assert_roots(solve_cubic([-2.0, 1.0, -2.0, 1.0]), &[2.0]);
+    }
+
+    #[test]
+    fn quartic_with_four_real_roots() {
+        // (x - 1)(x + 1)(x - 2)(x + 3)
+        assert_roots(
+            solve_quartic([6.0, -1.0, -7.0, 1.0, 1.0]),
+            &[-3.0, -1.0, 1.0, 2.0],
+        );
+    }
+
+    #[test]
+    fn quartic_with_two_real_roots() {
+        // (x - 0.5)(x - 4)(x^2 + 1)
+        assert_roots(solve_quartic([2.0, -4.5, 3.0, -4.5, 1.0]), &[0.5, 4.0]);
+    }
+
+    #[test]
+    fn quartic_without_real_roots() {
+        // (x^2 + 1)(x^2 + 4)
+        assert!(solve_quartic([4.0, 0.0, 5.0, 0.0, 1.0]).is_empty());
+    }
+}
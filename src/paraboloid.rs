@@ -0,0 +1,93 @@
+use crate::hittable::{HitRecord, Hittable};
+use crate::materials::material::Material;
+use crate::math::{azimuth, solve_quadratic};
+use crate::types::aabb::Aabb;
+use crate::types::interval::Interval;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// A paraboloid opening upwards from its vertex at `center`, reaching `radius`
+/// at height `y_max`, clipped to `y_min..y_max` and swept `phi_max` radians.
+pub struct Paraboloid {
+    pub radius: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+    pub phi_max: f64,
+    pub mat: Box<dyn Material + Sync + Send>,
+    center: Point3,
+}
+
+impl Paraboloid {
+    /// `phi_max` is given in degrees, like the camera's field of view.
+    pub fn new(
+        center: Point3,
+        radius: f64,
+        y_min: f64,
+        y_max: f64,
+        phi_max: f64,
+        mat: Box<dyn Material + Sync + Send>,
+    ) -> Paraboloid {
+        let y0 = y_min.min(y_max).max(0.0);
+        let y1 = y_min.max(y_max).max(0.0);
+        Paraboloid {
+            radius,
+            y_min: y0,
+            y_max: y1,
+            phi_max: phi_max.clamp(0.0, 360.0).to_radians(),
+            mat,
+            center,
+        }
+    }
+}
+
+impl Hittable for Paraboloid {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let origin = r.origin() - self.center;
+        let dir = r.dir();
+
+        // x^2 + z^2 = k y with k = radius^2 / y_max
+        let k = self.radius * self.radius / self.y_max;
+        let a = dir.x() * dir.x() + dir.z() * dir.z();
+        let b = 2.0 * (origin.x() * dir.x() + origin.z() * dir.z()) - k * dir.y();
+        let c = origin.x() * origin.x() + origin.z() * origin.z() - k * origin.y();
+        let (t0, t1) = match solve_quadratic(a, b, c) {
+            Some(roots) => roots,
+            None => return false,
+        };
+
+        for t in [t0, t1] {
+            if !ray_t.surrounds(t) {
+                continue;
+            }
+            let p = origin + t * dir;
+            let phi = azimuth(p.x(), p.z());
+            if p.y() < self.y_min || p.y() > self.y_max || phi > self.phi_max {
+                continue;
+            }
+            rec.t = t;
+            rec.p = r.at(t);
+            let outward_normal = Vec3(2.0 * p.x(), -k, 2.0 * p.z()).unit_vector();
+            rec.set_face_normal(r, &outward_normal);
+            rec.u = phi / self.phi_max;
+            rec.v = (p.y() - self.y_min) / (self.y_max - self.y_min);
+            // The radius grows as sqrt(k y), so d(x, z)/dy = (x, z) k / (2 r^2).
+            let slope = k / (2.0 * (p.x() * p.x() + p.z() * p.z())).max(1e-12);
+            rec.set_tangents(
+                self.phi_max * Vec3(-p.z(), 0.0, p.x()),
+                (self.y_max - self.y_min) * Vec3(p.x() * slope, 1.0, p.z() * slope),
+            );
+            rec.mat = self.mat.my_copy();
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::from_points(
+            self.center + Vec3(-self.radius, self.y_min, -self.radius),
+            self.center + Vec3(self.radius, self.y_max, self.radius),
+        )
+    }
+}
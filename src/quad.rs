@@ -0,0 +1,308 @@
+use std::f64::consts::PI;
+
+use crate::hittable::{HitRecord, Hittable, RAY_EPSILON};
+use crate::materials::material::Material;
+use crate::math::{MAX_SPHERICAL_SAMPLE_AREA, MIN_SPHERICAL_SAMPLE_AREA};
+use crate::random::random_f64;
+use crate::types::aabb::Aabb;
+use crate::types::color::luminance;
+use crate::types::interval::Interval;
+use crate::types::light_bounds::LightBounds;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// A parallelogram with one corner at `q` and sides `u` and `v`.
+pub struct Quad {
+    pub q: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub mat: Box<dyn Material + Sync + Send>,
+    normal: Vec3,
+    /// Plane offset, `normal · p` for every point on the plane.
+    d: f64,
+    /// `n / (n · n)` for the unnormalized normal, to find planar coordinates.
+    w: Vec3,
+    area: f64,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, mat: Box<dyn Material + Sync + Send>) -> Quad {
+        let n = u.cross(&v);
+        let normal = n.unit_vector();
+        Quad {
+            q,
+            u,
+            v,
+            mat,
+            normal,
+            d: normal.dot(&q),
+            w: n / n.dot(&n),
+            area: n.length(),
+        }
+    }
+
+    /// The spherical rectangle the quad subtends from `origin`, if it is a
+    /// rectangle and the solid angle is suitable for sampling directly.
+    fn spherical_rectangle(&self, origin: &Point3) -> Option<SphericalRectangle> {
+        let u_len = self.u.length();
+        let v_len = self.v.length();
+        if self.u.dot(&self.v).abs() > 1e-9 * u_len * v_len {
+            return None;
+        }
+        let rect = SphericalRectangle::new(origin, &self.q, &self.u, &self.v);
+        if rect.solid_angle < MIN_SPHERICAL_SAMPLE_AREA
+            || rect.solid_angle > MAX_SPHERICAL_SAMPLE_AREA
+        {
+            return None;
+        }
+        Some(rect)
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let denom = self.normal.dot(&r.dir());
+        if denom.abs() < 1e-8 {
+            return false;
+        }
+        let t = (self.d - self.normal.dot(&r.origin())) / denom;
+        if !ray_t.surrounds(t) {
+            return false;
+        }
+
+        let p = r.at(t);
+        let planar = p - self.q;
+        let alpha = self.w.dot(&planar.cross(&self.v));
+        let beta = self.w.dot(&self.u.cross(&planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.set_face_normal(r, &self.normal);
+        rec.u = alpha;
+        rec.v = beta;
+        rec.set_tangents(self.u, self.v);
+        rec.mat = self.mat.my_copy();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let diagonal1 = Aabb::from_points(self.q, self.q + self.u + self.v);
+        let diagonal2 = Aabb::from_points(self.q + self.u, self.q + self.v);
+        Aabb::surrounding(&diagonal1, &diagonal2).pad()
+    }
+
+    fn is_light(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        let mut rec = HitRecord::new();
+        if !self.hit(r, &Interval::new(RAY_EPSILON, f64::INFINITY), &mut rec) {
+            return 0.0;
+        }
+        if let Some(rect) = self.spherical_rectangle(&r.origin()) {
+            return 1.0 / rect.solid_angle;
+        }
+        let distance_squared = rec.t * rec.t * r.dir().length_squared();
+        let cosine = r.dir().unit_vector().dot(&self.normal).abs();
+        distance_squared / (cosine.max(1e-8) * self.area)
+    }
+
+    fn random(&self, origin: &Point3, _time: f64) -> Vec3 {
+        match self.spherical_rectangle(origin) {
+            Some(rect) => rect.sample(random_f64(), random_f64()) - *origin,
+            None => self.q + random_f64() * self.u + random_f64() * self.v - *origin,
+        }
+    }
+
+    fn light_bounds(&self) -> LightBounds {
+        let power = PI * self.area * luminance(&self.mat.emission());
+        LightBounds::new(self.bounding_box(), self.normal, power, 1.0, 0.0, false)
+    }
+}
+
+/// The solid angle a rectangle subtends from a point, set up for uniform
+/// sampling ("An Area-Preserving Parametrization for Spherical Rectangles",
+/// Ureña et al. 2013). Coordinates are in a frame with the rectangle's sides
+/// along x and y and the rectangle in the plane z = z0 < 0.
+struct SphericalRectangle {
+    origin: Point3,
+    x: Vec3,
+    y: Vec3,
+    z: Vec3,
+    x0: f64,
+    x1: f64,
+    y0: f64,
+    y1: f64,
+    z0: f64,
+    b0: f64,
+    b1: f64,
+    k: f64,
+    solid_angle: f64,
+}
+
+impl SphericalRectangle {
+    fn new(origin: &Point3, corner: &Point3, u: &Vec3, v: &Vec3) -> SphericalRectangle {
+        let x = u.unit_vector();
+        let y = v.unit_vector();
+        let mut z = x.cross(&y);
+        let d = *corner - *origin;
+        let mut z0 = d.dot(&z);
+        if z0 > 0.0 {
+            z = -z;
+            z0 = -z0;
+        }
+        let x0 = d.dot(&x);
+        let y0 = d.dot(&y);
+        let x1 = x0 + u.length();
+        let y1 = y0 + v.length();
+
+        // Normals of the planes through the origin and each edge.
+        let n0 = Vec3(0.0, z0, -y0).unit_vector();
+        let n1 = Vec3(-z0, 0.0, x1).unit_vector();
+        let n2 = Vec3(0.0, -z0, y1).unit_vector();
+        let n3 = Vec3(z0, 0.0, -x0).unit_vector();
+        // Interior angles at the corners; their excess over 2π is the solid angle.
+        let g0 = (-n0.dot(&n1)).clamp(-1.0, 1.0).acos();
+        let g1 = (-n1.dot(&n2)).clamp(-1.0, 1.0).acos();
+        let g2 = (-n2.dot(&n3)).clamp(-1.0, 1.0).acos();
+        let g3 = (-n3.dot(&n0)).clamp(-1.0, 1.0).acos();
+        let k = 2.0 * PI - g2 - g3;
+
+        SphericalRectangle {
+            origin: *origin,
+            x,
+            y,
+            z,
+            x0,
+            x1,
+            y0,
+            y1,
+            z0,
+            b0: n0.z(),
+            b1: n2.z(),
+            k,
+            solid_angle: g0 + g1 - k,
+        }
+    }
+
+    /// Point on the rectangle whose direction from the origin is uniformly
+    /// distributed over the solid angle, for uniform `s` and `t` in [0,1).
+    fn sample(&self, s: f64, t: f64) -> Point3 {
+        // Pick the x coordinate by the fraction of solid angle to its left.
+        let au = s * self.solid_angle + self.k;
+        let fu = (au.cos() * self.b0 - self.b1) / au.sin();
+        let cu = (1.0 / (fu * fu + self.b0 * self.b0).sqrt())
+            .copysign(fu)
+            .clamp(-1.0, 1.0);
+        let xu = (-(cu * self.z0) / (1.0 - cu * cu).max(1e-12).sqrt()).clamp(self.x0, self.x1);
+
+        // Then y along that vertical line, uniform in the sine of elevation.
+        let d = (xu * xu + self.z0 * self.z0).sqrt();
+        let h0 = self.y0 / (d * d + self.y0 * self.y0).sqrt();
+        let h1 = self.y1 / (d * d + self.y1 * self.y1).sqrt();
+        let hv = h0 + t * (h1 - h0);
+        let hv2 = hv * hv;
+        let yv = if hv2 < 1.0 - 1e-6 {
+            hv * d / (1.0 - hv2).sqrt()
+        } else {
+            self.y1
+        };
+
+        self.origin + xu * self.x + yv * self.y + self.z0 * self.z
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn quad(q: Point3, u: Vec3, v: Vec3) -> Quad {
+        Quad::new(q, u, v, Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))))
+    }
+
+    fn hits(object: &Quad, origin: &Point3, dir: Vec3) -> bool {
+        let r = Ray::new(*origin, dir);
+        object.hit(
+            &r,
+            &Interval::new(0.0, f64::INFINITY),
+            &mut HitRecord::new(),
+        )
+    }
+
+    #[test]
+    fn square_below_its_center_covers_a_sixth_of_the_sphere() {
+        let rect = SphericalRectangle::new(
+            &Point3(0.0, 0.0, 0.0),
+            &Point3(-1.0, -1.0, -1.0),
+            &Vec3(2.0, 0.0, 0.0),
+            &Vec3(0.0, 2.0, 0.0),
+        );
+        assert!((rect.solid_angle - 2.0 * PI / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_over_the_sphere() {
+        let light = quad(
+            Point3(0.3, -0.5, -1.0),
+            Vec3(1.5, 0.0, 0.5),
+            Vec3(0.0, 1.0, 0.0),
+        );
+        let origin = Point3(0.1, 0.2, 0.3);
+        assert!(light.spherical_rectangle(&origin).is_some());
+
+        // Directions on rings of equal area, all weighing the same.
+        let (rings, steps) = (500, 1000);
+        let mut total = 0.0;
+        for i in 0..rings {
+            let z = 1.0 - 2.0 * (i as f64 + 0.5) / rings as f64;
+            let radius = (1.0 - z * z).sqrt();
+            for j in 0..steps {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / steps as f64;
+                let dir = Vec3(radius * phi.cos(), radius * phi.sin(), z);
+                total += light.pdf_value(&Ray::new(origin, dir));
+            }
+        }
+        let integral = total * 4.0 * PI / (rings * steps) as f64;
+        assert!((integral - 1.0).abs() < 5e-3, "{integral}");
+    }
+
+    #[test]
+    fn samples_spread_evenly_over_the_solid_angle() {
+        let (q, u, v) = (
+            Point3(0.3, -0.5, -1.0),
+            Vec3(1.5, 0.0, 0.5),
+            Vec3(0.0, 1.0, 0.0),
+        );
+        let light = quad(q, u, v);
+        let origin = Point3(0.1, 0.2, 0.3);
+        let rect = light.spherical_rectangle(&origin).unwrap();
+
+        // The quarter at the corner holds a share of the samples matching its
+        // share of the solid angle.
+        let quarter = quad(q, u / 2.0, v / 2.0);
+        let share = quarter.spherical_rectangle(&origin).unwrap().solid_angle / rect.solid_angle;
+
+        let n = 200;
+        let mut inside_quarter = 0;
+        for i in 0..n {
+            for j in 0..n {
+                let s = (i as f64 + 0.5) / n as f64;
+                let t = (j as f64 + 0.5) / n as f64;
+                let dir = rect.sample(s, t) - origin;
+                assert!(hits(&light, &origin, dir));
+                if hits(&quarter, &origin, dir) {
+                    inside_quarter += 1;
+                }
+            }
+        }
+        let fraction = inside_quarter as f64 / (n * n) as f64;
+        assert!((fraction - share).abs() < 5e-3, "{fraction} vs {share}");
+    }
+}
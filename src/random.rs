@@ -9,3 +9,46 @@ pub(crate) fn random_f64_with_bounds(min: f64, max: f64) -> f64 {
     // Returns a random real in [min,max).
     min + (max - min) * random_f64()
 }
+
+/// A real in [0,1) that looks random but is the same every time for the
+/// same `values`, for decisions that must not change when repeated.
+pub(crate) fn hash_f64(values: &[f64]) -> f64 {
+    let mut h: u64 = 0x9e37_79b9_7f4a_7c15;
+    for value in values {
+        // SplitMix64's finalizer over each value in turn.
+        h = (h ^ value.to_bits()).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^= h >> 31;
+    }
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_repeats_for_the_same_values() {
+        let values = [1.5, -0.25, 3e-7];
+        assert_eq!(hash_f64(&values), hash_f64(&values));
+        assert_ne!(hash_f64(&values), hash_f64(&[1.5, -0.25, 3.0000001e-7]));
+        assert_ne!(hash_f64(&values), hash_f64(&[-0.25, 1.5, 3e-7]));
+    }
+
+    #[test]
+    fn hash_spreads_evenly_over_the_unit_interval() {
+        // Nearby inputs, like the hits on one surface.
+        let n = 100_000;
+        let mut buckets = [0; 10];
+        for i in 0..n {
+            let x = i as f64 * 1e-5;
+            let h = hash_f64(&[x, 0.5, 1.0 - x]);
+            assert!((0.0..1.0).contains(&h));
+            buckets[(h * 10.0) as usize] += 1;
+        }
+        for count in buckets {
+            assert!((count as f64 / n as f64 - 0.1).abs() < 0.005, "{buckets:?}");
+        }
+    }
+}
@@ -7,11 +7,13 @@ use std::sync::Arc;
 use std::thread;
 
 use crate::hittable::HittableList;
+use crate::light_sampler::LightSampler;
 use crate::types::render_task::RenderTask;
 
 pub struct Renderer {
     threads: i32,
     world: Arc<HittableList>,
+    lights: Arc<LightSampler>,
     camera: Arc<crate::camera::Camera>,
     image: Box<Image>,
     sub_images: Vec<Box<Image>>,
@@ -28,6 +30,7 @@ impl Renderer {
     ) -> Renderer {
         Renderer {
             threads,
+            lights: Arc::new(LightSampler::new(world.lights(), camera.light_selection)),
             world,
             image: Image::new(camera.image_width, camera.image_height),
             camera,
@@ -101,6 +104,9 @@ impl Renderer {
         }
     }
     pub fn output_image(&mut self) {
+        if self.camera.spectral {
+            self.image.xyz_to_srgb();
+        }
         self.file
             .write_fmt(format_args!(
                 "P3\n{} {}\n255\n",
@@ -114,6 +120,7 @@ impl Renderer {
         self.current_task_id += 1;
         let camera = Arc::clone(&self.camera);
         let world = Arc::clone(&self.world);
+        let lights = Arc::clone(&self.lights);
 
         thread::spawn(move || {
             println!("thread {} start render", id);
@@ -122,7 +129,7 @@ impl Renderer {
                 task.end_id - task.start_id,
                 task.samples_per_pixel
             );
-            let sub_image = camera.render(world.deref(), task);
+            let sub_image = camera.render(world.deref(), lights.deref(), task);
             println!("thread {} end render", id);
             tx.send(sub_image).unwrap();
         });
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use super::Scene;
+use crate::fog::Fog;
+use crate::hittable::HittableList;
+use crate::materials::material::Material;
+use crate::random::random_f64;
+use crate::types::vec3::Vec3;
+use Vec3 as Point3;
+
+/// Small spheres of every material around three big ones, as on the cover of
+/// "Ray Tracing in One Weekend".
+pub fn book_cover() -> Scene {
+    use crate::materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
+    use crate::sphere::Sphere;
+
+    let mut world = HittableList::new();
+    random_spheres(&mut world);
+
+    let material1 = Dielectric::new(1.5);
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 1.0, 0.0),
+        1.0,
+        Box::new(material1),
+    )));
+    let materal2 = Lambertian::new(Vec3(0.4, 0.2, 0.1));
+    world.add(Arc::new(Sphere::new(
+        Vec3(-4.0, 1.0, 0.0),
+        1.0,
+        Box::new(materal2),
+    )));
+    let material3 = Metal::new(Vec3(0.7, 0.6, 0.5), 0.0);
+    world.add(Arc::new(Sphere::new(
+        Vec3(4.0, 1.0, 0.0),
+        1.0,
+        Box::new(material3),
+    )));
+    Scene::new(world)
+}
+
+/// The cover in low-lying fog, thinning out upwards.
+pub fn foggy_cover() -> Scene {
+    let mut scene = book_cover();
+    scene.camera.fog = Some(Fog::height(0.08, 1.5, 0.0, Vec3(0.9, 0.9, 0.9)));
+    scene
+}
+
+/// The cover in afternoon daylight.
+pub fn daylight_cover() -> Scene {
+    book_cover().in_daylight()
+}
+
+fn random_spheres(world: &mut HittableList) {
+    use crate::materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
+    use crate::random::random_f64_with_bounds;
+    use crate::sphere::Sphere;
+    use Vec3 as Color;
+
+    let material_ground = Lambertian {
+        albedo: Vec3(0.5, 0.5, 0.5),
+    };
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, -1.0),
+        1000.0,
+        Box::new(material_ground),
+    )));
+
+    let mut a = -11;
+    while a < 11 {
+        let mut b = -11;
+        while b < 11 {
+            let choose_mat = random_f64();
+            let center = Point3::new(
+                a as f64 + 0.9 * random_f64(),
+                0.2,
+                b as f64 + 0.9 * random_f64(),
+            );
+
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                let sphere_material: Box<dyn Material + Sync + Send>;
+
+                if choose_mat < 0.8 {
+                    // diffuse
+                    let aldebo = Color::random() * Color::random();
+                    sphere_material = Box::new(Lambertian::new(aldebo));
+                    let center2 = center + Vec3::new(0., random_f64_with_bounds(0., 0.5), 0.);
+
+                    world.add(Arc::new(Sphere::new_moving(
+                        center,
+                        center2,
+                        0.2,
+                        sphere_material,
+                    )));
+                } else if choose_mat < 0.95 {
+                    // metal
+                    let aldebo = Color::random_with_bounds(0.5, 1.0);
+                    let fuzz = random_f64_with_bounds(0.0, 0.5);
+                    sphere_material = Box::new(Metal::new(aldebo, fuzz));
+                    world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
+                } else {
+                    // glass
+                    sphere_material = Box::new(Dielectric::new(1.5));
+                    world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
+                }
+            }
+            b += 1;
+        }
+        a += 1;
+    }
+}
@@ -0,0 +1,285 @@
+use std::io;
+use std::sync::Arc;
+
+use super::{asset, Scene};
+use crate::camera::Background;
+use crate::fog::Fog;
+use crate::hittable::HittableList;
+use crate::light_sampler::LightSelection;
+use crate::lights::{
+    directional_light::DirectionalLight, goniometric_light::GoniometricLight,
+    point_light::PointLight, spot_light::SpotLight,
+};
+use crate::random::random_f64;
+use crate::types::ies_profile::IesProfile;
+use crate::types::vec3::Vec3;
+
+/// Spheres at night, lit by a large dim light and a few small bright lamps
+/// through a thin mist.
+pub fn lamps() -> Scene {
+    use crate::materials::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
+    };
+    use crate::sphere::Sphere;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 2.2;
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 1.0, 0.0) - across,
+        1.0,
+        Box::new(Lambertian::new(Vec3(0.7, 0.3, 0.2))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 1.0, 0.0),
+        1.0,
+        Box::new(Dielectric::new(1.5)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 1.0, 0.0) + across,
+        1.0,
+        Box::new(Metal::new(Vec3(0.8, 0.8, 0.8), 0.05)),
+    )));
+
+    // A large, dim light behind the spheres that shows up sharply in
+    // the glossy metal.
+    world.add(Arc::new(Sphere::new(
+        Vec3(-12.0, 7.0, -3.0),
+        4.0,
+        Box::new(DiffuseLight::new(Vec3(1.5, 1.5, 1.8))),
+    )));
+
+    // Small, bright lamps that random bounces would almost never find.
+    for (offset, color) in [
+        (-1.5, Vec3(120.0, 60.0, 24.0)),
+        (-0.5, Vec3(24.0, 90.0, 120.0)),
+        (0.5, Vec3(120.0, 120.0, 120.0)),
+        (1.5, Vec3(90.0, 24.0, 90.0)),
+    ] {
+        world.add(Arc::new(Sphere::new(
+            across * offset + Vec3(3.0, 2.2, 0.0),
+            0.12,
+            Box::new(DiffuseLight::new(color)),
+        )));
+    }
+    let mut scene = Scene::new(world).at_night();
+    // Night mist, glowing around the lamps. Uniform fog hides the sky, so it
+    // only suits scenes lit by their own lights.
+    scene.camera.fog = Some(Fog::uniform(0.02, Vec3(0.9, 0.9, 0.9)));
+    // The tiny lamps give off little power next to the big light, but matter
+    // as much up close.
+    scene.camera.light_selection = LightSelection::Uniform;
+    scene
+}
+
+/// Objects on a pedestal for lighting with the camera's punctual lights,
+/// among them a downlight shaped by `assets/downlight.ies`.
+pub fn product() -> io::Result<Scene> {
+    use crate::cylinder::Cylinder;
+    use crate::materials::{
+        conductor::Conductor, dielectric::Dielectric, lambertian::Lambertian,
+        microfacet::TrowbridgeReitz,
+    };
+    use crate::sphere::Sphere;
+    use crate::torus::Torus;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.6, 0.6, 0.6))),
+    )));
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 2.2;
+    world.add(Arc::new(Cylinder::new(
+        Vec3(0.0, 0.0, 0.0),
+        0.9,
+        0.0,
+        0.6,
+        360.0,
+        true,
+        Box::new(Lambertian::new(Vec3(0.8, 0.8, 0.8))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 1.3, 0.0),
+        0.7,
+        Box::new(Conductor::gold(0.15)),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 0.7, 0.0) - across,
+        0.7,
+        Box::new(Lambertian::new(Vec3(0.2, 0.4, 0.7))),
+    )));
+    world.add(Arc::new(Torus::new(
+        Vec3(0.0, 0.25, 0.0) + across,
+        0.6,
+        0.25,
+        360.0,
+        // Copper brushed along one direction.
+        Box::new(Conductor {
+            distribution: TrowbridgeReitz::new(0.05, 0.4),
+            ..Conductor::copper(0.0)
+        }),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(2.2, 0.35, 0.0) - across * 0.6,
+        0.35,
+        // Frosted green glass.
+        Box::new(Dielectric::rough(1.5, 0.2).with_absorption(Vec3(2.0, 0.4, 1.6))),
+    )));
+    let mut scene = Scene::new(world);
+    scene.camera.background = Background::Solid(Vec3(0.02, 0.02, 0.03));
+    // A key spot on the pedestal, a warm fill on the left, a soft low sun
+    // from behind and a measured downlight over the torus.
+    let lights = &mut scene.camera.punctual_lights;
+    lights.push(Box::new(SpotLight::new(
+        Vec3(6.0, 6.0, 2.0),
+        Vec3(0.0, 1.0, 0.0),
+        Vec3(60.0, 60.0, 55.0),
+        14.0,
+        9.0,
+    )));
+    lights.push(Box::new(PointLight::new(
+        Vec3(2.0, 2.5, 0.0) - across * 2.0,
+        Vec3(6.0, 4.0, 2.0),
+    )));
+    lights.push(Box::new(DirectionalLight::new(
+        Vec3(-1.0, 0.6, -0.3),
+        Vec3(0.6, 0.65, 0.8),
+        5.0,
+    )));
+    lights.push(Box::new(GoniometricLight::new(
+        Vec3(0.0, 4.0, 0.0) + across,
+        Vec3(0.0, -1.0, 0.0),
+        Vec3(30.0, 28.0, 24.0),
+        IesProfile::load(asset("downlight.ies"))?,
+    )));
+    Ok(scene)
+}
+
+/// Large lights close to the objects: a rectangle overhead, a triangle to
+/// one side and a glowing sphere, for soft shadows.
+pub fn area_lights() -> Scene {
+    use crate::materials::{diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal};
+    use crate::quad::Quad;
+    use crate::sphere::Sphere;
+    use crate::triangle::Triangle;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 2.2;
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 0.8, 0.0) - across,
+        0.8,
+        Box::new(Lambertian::new(Vec3(0.7, 0.3, 0.2))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 0.8, 0.0),
+        0.8,
+        Box::new(Lambertian::new(Vec3(0.8, 0.8, 0.8))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 0.8, 0.0) + across,
+        0.8,
+        Box::new(Metal::new(Vec3(0.8, 0.8, 0.8), 0.2)),
+    )));
+
+    // The light faces along u x v, so this one shines down.
+    world.add(Arc::new(Quad::new(
+        Vec3(-1.5, 3.0, -2.5),
+        Vec3(3.0, 0.0, 0.0),
+        Vec3(0.0, 0.0, 5.0),
+        Box::new(DiffuseLight::new(Vec3(3.0, 3.0, 3.0))),
+    )));
+    world.add(Arc::new(Triangle::new(
+        Vec3(-3.0, 0.0, 4.0),
+        Vec3(-3.0, 0.0, 1.0),
+        Vec3(-3.0, 2.5, 2.5),
+        Box::new(DiffuseLight::new(Vec3(1.0, 3.0, 6.0))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(1.5, 0.4, 0.0) + across * 1.5,
+        0.4,
+        Box::new(DiffuseLight::new(Vec3(8.0, 5.0, 2.0))),
+    )));
+    let mut scene = Scene::new(world).at_night();
+    // A few large lights, all in reach of everything.
+    scene.camera.light_selection = LightSelection::Power;
+    scene
+}
+
+/// A night-time plaza lit by thousands of small lamps of varying
+/// brightness, most of them far from any given point.
+pub fn many_lights() -> Scene {
+    use crate::bvh::BvhNode;
+    use crate::materials::{diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal};
+    use crate::random::random_f64_with_bounds;
+    use crate::sphere::Sphere;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 2.2;
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 1.0, 0.0) - across,
+        1.0,
+        Box::new(Lambertian::new(Vec3(0.7, 0.3, 0.2))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 1.0, 0.0),
+        1.0,
+        Box::new(Lambertian::new(Vec3(0.8, 0.8, 0.8))),
+    )));
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 1.0, 0.0) + across,
+        1.0,
+        Box::new(Metal::new(Vec3(0.8, 0.8, 0.8), 0.3)),
+    )));
+
+    // Lamps on a grid, a few of them much brighter than the rest.
+    let mut lamps = HittableList::new();
+    for i in -30..30 {
+        for j in -30..30 {
+            let center = Vec3(
+                i as f64 * 1.5 + random_f64_with_bounds(-0.4, 0.4),
+                random_f64_with_bounds(0.1, 2.5),
+                j as f64 * 1.5 + random_f64_with_bounds(-0.4, 0.4),
+            );
+            // Keep clear of the spheres and out from in front of the camera.
+            if (center - Vec3(0.0, center.y(), 0.0)).length() < 3.5 || center.x() > 5.0 {
+                continue;
+            }
+            let brightness = if random_f64() < 0.02 { 400.0 } else { 20.0 };
+            let color = Vec3(
+                random_f64_with_bounds(0.3, 1.0),
+                random_f64_with_bounds(0.3, 1.0),
+                random_f64_with_bounds(0.3, 1.0),
+            );
+            lamps.add(Arc::new(Sphere::new(
+                center,
+                0.05,
+                Box::new(DiffuseLight::new(color * brightness)),
+            )));
+        }
+    }
+    world.add(Arc::new(BvhNode::new(lamps)));
+    Scene::new(world).at_night()
+}
@@ -0,0 +1,114 @@
+pub mod cover;
+pub mod lighting;
+pub mod motion;
+pub mod shapes;
+pub mod surfaces;
+pub mod volumes;
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::camera::{Background, Camera};
+use crate::hittable::HittableList;
+use crate::sky::Sky;
+use crate::types::vec3::Vec3;
+
+/// A demo world together with the camera that frames and lights it.
+pub struct Scene {
+    pub world: HittableList,
+    pub camera: Camera,
+}
+
+impl Scene {
+    /// `world` seen the way most scenes are: from `(13, 2, 3)` towards the
+    /// origin, focused 10 units away, against a gradient sky.
+    fn new(world: HittableList) -> Scene {
+        Scene {
+            world,
+            camera: camera_at(Vec3(13.0, 2.0, 3.0), 10.0),
+        }
+    }
+
+    /// Lit by a Preetham sky on a clear afternoon and its sun.
+    fn in_daylight(mut self) -> Scene {
+        let sky = Sky::new(Vec3(0.6, 0.35, 0.5), 3.0, Vec3(0.3, 0.3, 0.3));
+        self.camera.background = Background::Sky(Box::new(sky));
+        self.camera.punctual_lights.push(Box::new(sky.sun_light()));
+        self
+    }
+
+    /// Against a nearly black sky, lit only by the scene's own lights.
+    fn at_night(mut self) -> Scene {
+        self.camera.background = Background::Solid(Vec3(0.01, 0.01, 0.02));
+        self
+    }
+}
+
+/// A camera looking at the origin from `lookfrom`, focused `focus_dist` away.
+fn camera_at(lookfrom: Vec3, focus_dist: f64) -> Camera {
+    let lookat = Vec3(0.0, 0.0, 0.0);
+    let vup = Vec3(0.0, 1.0, 0.0);
+    let aperture = 0.1;
+
+    Camera::new(
+        20.0,
+        16.0 / 9.0,
+        400,
+        20,
+        100,
+        &lookfrom,
+        &lookat,
+        &vup,
+        aperture,
+        focus_dist,
+    )
+}
+
+/// Path of `name` in the repository's `assets` directory, wherever the
+/// renderer is run from.
+fn asset(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "assets", name]
+        .iter()
+        .collect()
+}
+
+/// The demo scene numbered `id`: 0 is the book cover, 1 shows the quadric
+/// shapes, 2 a field of instanced pawns, 3 a rolling wheel, 4 solids built
+/// with CSG, 5 smoke and fog volumes, 6 voxel grid smoke and fire, 7 the book
+/// cover in low-lying fog, 8 small lamps in night mist, 9 a product shot with
+/// punctual lights, 10 large area lights, 11 thousands of small lamps, 12 the
+/// book cover in afternoon daylight, 13 spheres of the principled material,
+/// 14 a prism splitting light, rendered spectrally, 15 soap bubbles over oily
+/// steel, 16 translucent materials, 17 layered and blended materials, 18 bump
+/// mapped, normal mapped and smooth shaded surfaces, 19 surfaces with holes
+/// cut out, 20 the field of pawns in an orthographic isometric view and 21 the
+/// rolling wheel through a rolling shutter. Any other number gives the cover.
+///
+/// Fails if a scene cannot read the assets it needs.
+pub fn build(id: i32) -> io::Result<Scene> {
+    let scene = match id {
+        1 => shapes::quadrics(),
+        2 => shapes::instances(),
+        3 => motion::motion_blur(),
+        4 => shapes::csg(),
+        5 => volumes::media(),
+        6 => volumes::fire()?,
+        7 => cover::foggy_cover(),
+        8 => lighting::lamps(),
+        9 => lighting::product()?,
+        10 => lighting::area_lights(),
+        11 => lighting::many_lights(),
+        12 => cover::daylight_cover(),
+        13 => surfaces::principled(),
+        14 => surfaces::prism(),
+        15 => surfaces::bubbles(),
+        16 => surfaces::subsurface(),
+        17 => surfaces::layered(),
+        18 => surfaces::normal_map(),
+        19 => surfaces::cutout(),
+        20 => shapes::isometric_instances(),
+        21 => motion::rolling_shutter(),
+        _ => cover::book_cover(),
+    };
+    Ok(scene)
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use super::Scene;
+use crate::camera::ShutterCurve;
+use crate::hittable::{Hittable, HittableList};
+use crate::types::vec3::Vec3;
+
+/// A wheel rolling along the ground beside a bouncing ball, seen through a
+/// leaf shutter open for the middle half of the frame.
+pub fn motion_blur() -> Scene {
+    use crate::cylinder::Cylinder;
+    use crate::instance::Instance;
+    use crate::materials::{lambertian::Lambertian, metal::Metal};
+    use crate::sphere::Sphere;
+    use crate::torus::Torus;
+    use crate::types::animated_transform::{AnimatedTransform, Keyframe};
+    use crate::types::quaternion::Quaternion;
+    use crate::types::transform::Transform;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    // A wheel facing the default camera, with its axle along x.
+    let mut wheel = HittableList::new();
+    let tire: Arc<dyn Hittable + Sync + Send> = Arc::new(Torus::new(
+        Vec3(0.0, 0.0, 0.0),
+        0.85,
+        0.15,
+        360.0,
+        Box::new(Lambertian::new(Vec3(0.1, 0.1, 0.1))),
+    ));
+    wheel.add(Arc::new(Instance::new(
+        tire,
+        Transform::identity().rotate_z(90.0),
+    )));
+    let spoke: Arc<dyn Hittable + Sync + Send> = Arc::new(Cylinder::new(
+        Vec3(0.0, 0.0, 0.0),
+        0.05,
+        -0.8,
+        0.8,
+        360.0,
+        false,
+        Box::new(Metal::new(Vec3(0.8, 0.8, 0.8), 0.1)),
+    ));
+    for angle in [0.0, 60.0, 120.0] {
+        wheel.add(Arc::new(Instance::new(
+            spoke.clone(),
+            Transform::identity().rotate_x(angle),
+        )));
+    }
+    let wheel: Arc<dyn Hittable + Sync + Send> = Arc::new(wheel);
+
+    // Roll 30 degrees without slipping while the shutter is open.
+    let axle = Vec3(1.0, 0.0, 0.0);
+    let roll = |time: f64| {
+        Keyframe::new(
+            time,
+            Vec3(0.0, 1.0, time * 30.0_f64.to_radians()),
+            Quaternion::from_axis_angle(30.0 * time, axle),
+            Vec3(1.0, 1.0, 1.0),
+        )
+    };
+    world.add(Arc::new(Instance::new_animated(
+        wheel,
+        AnimatedTransform::new(vec![roll(0.0), roll(0.5), roll(1.0)]),
+    )));
+
+    // A ball bouncing off the ground beside it, squashing without turning.
+    let ball: Arc<dyn Hittable + Sync + Send> = Arc::new(Sphere::new(
+        Vec3(0.0, 0.0, 0.0),
+        0.5,
+        Box::new(Lambertian::new(Vec3(0.8, 0.3, 0.1))),
+    ));
+    let bounce = |time: f64, height: f64, squash: f64| {
+        Keyframe::new(
+            time,
+            Vec3(0.0, height, -2.0),
+            Quaternion::identity(),
+            Vec3(1.0 + squash, 1.0 - squash, 1.0 + squash),
+        )
+    };
+    world.add(Arc::new(Instance::new_animated(
+        ball,
+        AnimatedTransform::new(vec![
+            bounce(0.0, 1.4, 0.0),
+            bounce(0.5, 0.35, 0.3),
+            bounce(1.0, 1.4, 0.0),
+        ]),
+    )));
+    let mut scene = Scene::new(world);
+    // Keep the shutter open for the middle half of the frame, easing in and
+    // out like a leaf shutter.
+    scene.camera.shutter_open = 0.25;
+    scene.camera.shutter_close = 0.75;
+    scene.camera.shutter_curve = ShutterCurve::Trapezoid {
+        open: 0.2,
+        close: 0.2,
+    };
+    scene
+}
+
+/// The same motion through a rolling shutter, which skews the wheel.
+pub fn rolling_shutter() -> Scene {
+    let mut scene = motion_blur();
+    // Rows read out over most of the frame, each exposed briefly with a
+    // shutter that opens and closes gradually.
+    scene.camera.shutter_open = 0.0;
+    scene.camera.shutter_close = 1.0;
+    scene.camera.rolling_shutter = 0.8;
+    scene.camera.shutter_curve = ShutterCurve::Triangle;
+    scene
+}
@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use super::{camera_at, Scene};
+use crate::camera::Projection;
+use crate::hittable::{Hittable, HittableList};
+use crate::types::vec3::Vec3;
+
+/// The quadric shapes in a row: cylinder, cone, torus, paraboloid and
+/// hyperboloid, some of them cut open.
+pub fn quadrics() -> Scene {
+    use crate::cone::Cone;
+    use crate::cylinder::Cylinder;
+    use crate::hyperboloid::Hyperboloid;
+    use crate::materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
+    use crate::paraboloid::Paraboloid;
+    use crate::sphere::Sphere;
+    use crate::torus::Torus;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    // Lay the shapes out on a line across the default camera's view.
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 1.6;
+    world.add(Arc::new(Cylinder::new(
+        across * -2.0,
+        0.5,
+        0.0,
+        1.2,
+        360.0,
+        true,
+        Box::new(Lambertian::new(Vec3(0.8, 0.3, 0.2))),
+    )));
+    world.add(Arc::new(Cone::new(
+        across * -1.0,
+        0.6,
+        1.4,
+        270.0,
+        true,
+        Box::new(Metal::new(Vec3(0.8, 0.7, 0.4), 0.1)),
+    )));
+    world.add(Arc::new(Torus::new(
+        Vec3(0.0, 0.25, 0.0),
+        0.5,
+        0.25,
+        360.0,
+        Box::new(Dielectric::new(1.5)),
+    )));
+    world.add(Arc::new(Paraboloid::new(
+        across,
+        0.6,
+        0.0,
+        1.2,
+        360.0,
+        Box::new(Lambertian::new(Vec3(0.2, 0.5, 0.8))),
+    )));
+    world.add(Arc::new(Hyperboloid::new(
+        across * 2.0 + Vec3(0.0, 0.7, 0.0),
+        0.3,
+        0.4,
+        -0.7,
+        0.7,
+        300.0,
+        Box::new(Lambertian::new(Vec3(0.3, 0.7, 0.3))),
+    )));
+    Scene::new(world)
+}
+
+/// A field of leaning pawns, all instances of one shared model.
+pub fn instances() -> Scene {
+    use crate::bvh::BvhNode;
+    use crate::cone::Cone;
+    use crate::cylinder::Cylinder;
+    use crate::instance::Instance;
+    use crate::materials::{lambertian::Lambertian, metal::Metal};
+    use crate::random::random_f64_with_bounds;
+    use crate::sphere::Sphere;
+    use crate::types::transform::{Matrix4, Transform};
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    // One pawn, built once and shared by every instance below.
+    let mut pawn = HittableList::new();
+    pawn.add(Arc::new(Cylinder::new(
+        Vec3(0.0, 0.0, 0.0),
+        0.3,
+        0.0,
+        0.1,
+        360.0,
+        true,
+        Box::new(Metal::new(Vec3(0.8, 0.6, 0.3), 0.2)),
+    )));
+    pawn.add(Arc::new(Cone::new(
+        Vec3(0.0, 0.1, 0.0),
+        0.22,
+        0.6,
+        360.0,
+        false,
+        Box::new(Lambertian::new(Vec3(0.7, 0.1, 0.1))),
+    )));
+    pawn.add(Arc::new(Sphere::new(
+        Vec3(0.0, 0.65, 0.0),
+        0.14,
+        Box::new(Lambertian::new(Vec3(0.9, 0.9, 0.9))),
+    )));
+    let pawn: Arc<dyn Hittable + Sync + Send> = Arc::new(pawn);
+
+    let mut pawns = HittableList::new();
+    for a in -15..15 {
+        for b in -15..15 {
+            // Lean each pawn by shearing it, which keeps its base flat.
+            let lean = random_f64_with_bounds(-0.15, 0.15);
+            let transform = Transform::new(Matrix4([
+                [1.0, lean, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ]))
+            .uniform_scale(random_f64_with_bounds(0.6, 1.2))
+            .rotate_y(random_f64_with_bounds(0.0, 360.0))
+            .translate(Vec3(a as f64 * 0.8, 0.0, b as f64 * 0.8));
+            pawns.add(Arc::new(Instance::new(pawn.clone(), transform)));
+        }
+    }
+    world.add(Arc::new(BvhNode::new(pawns)));
+    Scene::new(world)
+}
+
+/// The field of pawns from above at an angle, in an orthographic view
+/// that keeps the rows parallel.
+pub fn isometric_instances() -> Scene {
+    let mut scene = instances();
+    let lookfrom = Vec3(20.0, 20.0, 20.0);
+    scene.camera = camera_at(lookfrom, lookfrom.length());
+    scene.camera.projection = Projection::Orthographic { view_height: 18.0 };
+    scene
+}
+
+/// Solids built with CSG: a lens, a shell with a window and a machined
+/// puck.
+pub fn csg() -> Scene {
+    use crate::csg::Csg;
+    use crate::cylinder::Cylinder;
+    use crate::instance::Instance;
+    use crate::materials::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
+    use crate::sphere::Sphere;
+    use crate::types::transform::Transform;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 2.2;
+
+    // A biconvex lens facing the camera: the overlap of two spheres.
+    let lens_center = Vec3(0.0, 1.2, 0.0) - across;
+    let glass = || Box::new(Dielectric::new(1.5));
+    world.add(Arc::new(Csg::intersection(
+        Arc::new(Sphere::new(lens_center + Vec3(1.6, 0.0, 0.0), 2.0, glass())),
+        Arc::new(Sphere::new(lens_center - Vec3(1.6, 0.0, 0.0), 2.0, glass())),
+    )));
+
+    // A hollow shell with a window cut towards the camera.
+    let shell_center = Vec3(0.0, 0.9, 0.0);
+    let red = || Box::new(Lambertian::new(Vec3(0.8, 0.2, 0.2)));
+    let shell = Csg::difference(
+        Arc::new(Sphere::new(shell_center, 0.9, red())),
+        Arc::new(Sphere::new(shell_center, 0.8, red())),
+    );
+    world.add(Arc::new(Csg::difference(
+        Arc::new(shell),
+        Arc::new(Sphere::new(shell_center + Vec3(0.9, 0.3, 0.2), 0.5, red())),
+    )));
+
+    // A machined puck with a bore through the top and a cross-drilled hole.
+    let steel = || Box::new(Metal::new(Vec3(0.7, 0.7, 0.75), 0.2));
+    let drill = |radius: f64| -> Arc<dyn Hittable + Sync + Send> {
+        Arc::new(Cylinder::new(
+            Vec3(0.0, 0.0, 0.0),
+            radius,
+            -1.0,
+            1.0,
+            360.0,
+            true,
+            steel(),
+        ))
+    };
+    let blank = Cylinder::new(Vec3(0.0, 0.0, 0.0), 0.8, 0.0, 0.6, 360.0, true, steel());
+    let holes = Csg::union(
+        drill(0.3),
+        Arc::new(Instance::new(
+            drill(0.12),
+            Transform::identity()
+                .rotate_z(90.0)
+                .translate(Vec3(0.0, 0.3, 0.0)),
+        )),
+    );
+    let part = Csg::difference(Arc::new(blank), Arc::new(holes));
+    world.add(Arc::new(Instance::new(
+        Arc::new(part),
+        Transform::identity().translate(across),
+    )));
+    Scene::new(world)
+}
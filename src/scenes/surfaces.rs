@@ -0,0 +1,483 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use super::Scene;
+use crate::hittable::{Hittable, HittableList};
+use crate::materials::material::Material;
+use crate::types::vec3::Vec3;
+
+/// A row of spheres showing off the principled material, from lacquered
+/// plastic through metal, velvet and marble to frosted glass.
+pub fn principled() -> Scene {
+    use crate::materials::principled::Principled;
+    use crate::sphere::Sphere;
+    use crate::textures::{checker::Checker, noise::Noise, solid_color::SolidColor};
+
+    let mut world = HittableList::new();
+
+    let scalar = |value: f64| Arc::new(SolidColor::scalar(value));
+
+    let mut ground = Principled::new(Vec3(0.0, 0.0, 0.0));
+    ground.base_color = Arc::new(Checker::new(
+        1.0,
+        Arc::new(SolidColor::new(Vec3(0.2, 0.3, 0.1))),
+        Arc::new(SolidColor::new(Vec3(0.9, 0.9, 0.9))),
+    ));
+    ground.roughness = scalar(0.7);
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(ground),
+    )));
+
+    let mut lacquer = Principled::new(Vec3(0.7, 0.05, 0.05));
+    lacquer.roughness = scalar(0.6);
+    lacquer.clearcoat = scalar(1.0);
+
+    let mut gold = Principled::new(Vec3(1.0, 0.78, 0.34));
+    gold.metallic = scalar(1.0);
+    gold.roughness = scalar(0.3);
+
+    let mut velvet = Principled::new(Vec3(0.15, 0.05, 0.3));
+    velvet.roughness = scalar(1.0);
+    velvet.sheen = scalar(1.0);
+
+    let mut marble = Principled::new(Vec3(0.0, 0.0, 0.0));
+    marble.base_color = Arc::new(Noise::new(4.0, Vec3(0.3, 0.3, 0.35)));
+    marble.roughness = scalar(0.1);
+
+    let mut frosted = Principled::new(Vec3(0.7, 0.85, 1.0));
+    frosted.transmission = scalar(1.0);
+    frosted.roughness = scalar(0.25);
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 1.6;
+    for (i, material) in [lacquer, gold, velvet, marble, frosted]
+        .into_iter()
+        .enumerate()
+    {
+        world.add(Arc::new(Sphere::new(
+            Vec3(0.0, 0.7, 0.0) + across * (i as f64 - 2.0),
+            0.7,
+            Box::new(material),
+        )));
+    }
+    Scene::new(world).in_daylight()
+}
+
+/// A flint glass prism in front of a thin strip light, seen through the
+/// prism so that spectral rendering spreads the strip into a rainbow.
+pub fn prism() -> Scene {
+    use crate::materials::dielectric::{Dielectric, Dispersion};
+    use crate::materials::{diffuse_light::DiffuseLight, lambertian::Lambertian};
+    use crate::quad::Quad;
+    use crate::sphere::Sphere;
+    use crate::triangle::Triangle;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.3, 0.3, 0.3))),
+    )));
+
+    // Lay the prism out in the vertical plane through the view direction,
+    // with its length running across the view.
+    let forward = Vec3(-13.0, 0.0, -3.0).unit_vector();
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector();
+    let center = Vec3(0.0, 1.2, 0.0);
+    let at = |x: f64, y: f64, s: f64| center + forward * x + Vec3(0.0, y, 0.0) + across * s;
+
+    // Turn the prism 10 degrees short of minimum deviation for the rays
+    // from the camera. Each row then sees out of the prism at a different
+    // angle, so a thin strip shows up in a different color on each row.
+    let dispersion = Dispersion::sf11();
+    let incidence = (dispersion.ior(589.3) * 0.5).asin();
+    let turn = incidence - PI / 6.0 - 10.0_f64.to_radians();
+    let corner = |angle: f64| {
+        let angle = angle + turn;
+        (angle.cos(), angle.sin())
+    };
+    let corners = [corner(-PI / 2.0), corner(PI / 6.0), corner(5.0 * PI / 6.0)];
+    let half_length = 1.5;
+
+    let glass = || Box::new(Dielectric::new(1.5).with_dispersion(dispersion));
+    for i in 0..3 {
+        let (x0, y0) = corners[i];
+        let (x1, y1) = corners[(i + 1) % 3];
+        // Corners run counterclockwise seen from the far end, so this
+        // order makes the normal face out.
+        world.add(Arc::new(Quad::new(
+            at(x0, y0, -half_length),
+            at(x1, y1, 0.0) - at(x0, y0, 0.0),
+            across * (2.0 * half_length),
+            glass(),
+        )));
+    }
+    let cap = |s: f64| corners.map(|(x, y)| at(x, y, s));
+    let [a, b, c] = cap(half_length);
+    world.add(Arc::new(Triangle::new(a, b, c, glass())));
+    let [a, b, c] = cap(-half_length);
+    world.add(Arc::new(Triangle::new(a, c, b, glass())));
+
+    // Balls of less dispersive glasses beside it for comparison: crown
+    // glass and fused silica, given by its Cauchy fit, on one side and
+    // diamond on the other.
+    let ball = |s: f64, dispersion: Dispersion| {
+        Arc::new(Sphere::new(
+            Vec3(0.0, 0.4, 0.0) + across * s,
+            0.4,
+            Box::new(Dielectric::new(1.5).with_dispersion(dispersion)),
+        ))
+    };
+    world.add(ball(-3.4, Dispersion::bk7()));
+    world.add(ball(
+        -2.4,
+        Dispersion::Cauchy {
+            a: 1.4580,
+            b: 0.00354,
+        },
+    ));
+    world.add(ball(2.6, Dispersion::diamond()));
+
+    // The strip lies across the rays leaving the middle of the far face
+    // at 62.5 degrees, which is where orange light comes out of the top
+    // rows and cyan out of the bottom ones, facing back at the prism.
+    let exit = 62.5_f64.to_radians();
+    let (dx, dy) = (exit.cos(), exit.sin());
+    let (width, distance) = (0.1, 4.0);
+    let strip = at(
+        0.5 + distance * dx + dy * width / 2.0,
+        distance * dy - dx * width / 2.0,
+        -2.5,
+    );
+    world.add(Arc::new(Quad::new(
+        strip,
+        across * 5.0,
+        at(-dy * width, dx * width, 0.0) - center,
+        Box::new(DiffuseLight::new(Vec3(15.0, 15.0, 15.0))),
+    )));
+    let mut scene = Scene::new(world).at_night();
+    scene.camera.spectral = true;
+    scene.camera.samples_per_pixel = 100;
+    scene
+}
+
+/// Soap bubbles floating over a sheet of steel with a film of oil on it.
+pub fn bubbles() -> Scene {
+    use crate::materials::{conductor::Conductor, dielectric::Dielectric, thin_film::ThinFilm};
+    use crate::sphere::Sphere;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(
+            Conductor::new(Vec3(2.9, 2.9, 2.9), Vec3(3.1, 3.1, 3.1), 0.05, 0.05)
+                .with_film(ThinFilm::new(450.0, 1.47)),
+        ),
+    )));
+
+    // Air on both sides of the film, so light passes straight through.
+    let bubbles = [
+        (Vec3(0.0, 1.2, 0.0), 1.0, 380.0),
+        (Vec3(-1.5, 0.8, 2.2), 0.6, 560.0),
+        (Vec3(1.0, 0.7, -2.4), 0.5, 300.0),
+    ];
+    for (center, radius, thickness) in bubbles {
+        world.add(Arc::new(Sphere::new(
+            center,
+            radius,
+            Box::new(Dielectric::new(1.0).with_film(ThinFilm::new(thickness, 1.33))),
+        )));
+    }
+    Scene::new(world).in_daylight()
+}
+
+/// Skin, marble, wax and milk in a row, lit from above and from behind
+/// so light can be seen bleeding through them. One unit is a centimetre.
+pub fn subsurface() -> Scene {
+    use crate::cylinder::Cylinder;
+    use crate::materials::{
+        diffuse_light::DiffuseLight, lambertian::Lambertian, material::Nothing,
+        subsurface::Subsurface,
+    };
+    use crate::quad::Quad;
+    use crate::sphere::Sphere;
+    use crate::subsurface_volume::SubsurfaceVolume;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 1.6;
+    let ball = |center: Vec3| -> Arc<dyn Hittable + Sync + Send> {
+        Arc::new(Sphere::new(center, 0.7, Box::new(Nothing {})))
+    };
+    world.add(Arc::new(SubsurfaceVolume::new(
+        ball(Vec3(0.0, 0.7, 0.0) - across * 1.5),
+        Subsurface::skin(10.0).with_roughness(0.4),
+    )));
+    world.add(Arc::new(SubsurfaceVolume::new(
+        ball(Vec3(0.0, 0.7, 0.0) - across * 0.5),
+        Subsurface::marble(10.0),
+    )));
+    world.add(Arc::new(SubsurfaceVolume::new(
+        Arc::new(Cylinder::new(
+            Vec3(0.0, 0.0, 0.0) + across * 0.5,
+            0.5,
+            0.0,
+            1.6,
+            360.0,
+            true,
+            Box::new(Nothing {}),
+        )),
+        // Candle wax.
+        Subsurface::new(1.45, Vec3(0.9, 0.75, 0.45), Vec3(0.4, 0.25, 0.1)).with_roughness(0.3),
+    )));
+    world.add(Arc::new(SubsurfaceVolume::new(
+        ball(Vec3(0.0, 0.7, 0.0) + across * 1.5),
+        // Milk's fat droplets scatter light mostly forwards.
+        Subsurface::whole_milk(10.0).with_anisotropy(0.7),
+    )));
+
+    // The lights face along u x v: one shines down, the other towards
+    // the camera from behind the row.
+    world.add(Arc::new(Quad::new(
+        Vec3(-1.5, 4.0, -2.5),
+        Vec3(3.0, 0.0, 0.0),
+        Vec3(0.0, 0.0, 5.0),
+        Box::new(DiffuseLight::new(Vec3(2.0, 2.0, 2.0))),
+    )));
+    world.add(Arc::new(Quad::new(
+        Vec3(-3.0, 0.5, -2.5),
+        Vec3(0.0, 0.0, 5.0),
+        Vec3(0.0, 2.0, 0.0),
+        Box::new(DiffuseLight::new(Vec3(4.0, 4.0, 4.0))),
+    )));
+    Scene::new(world).at_night()
+}
+
+/// Coated and blended materials: varnished wood, metallic car paint,
+/// gold under lacquer and scuffed silver.
+pub fn layered() -> Scene {
+    use crate::materials::{
+        conductor::Conductor, dielectric::Dielectric, lambertian::Lambertian, layered::Layered,
+        mix::Mix,
+    };
+    use crate::sphere::Sphere;
+    use crate::textures::{checker::Checker, noise::Noise, solid_color::SolidColor};
+
+    let mut world = HittableList::new();
+
+    // A satin floor, with a little sheen blended into the diffuse.
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Mix::new(
+            Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+            Box::new(Conductor::aluminium(0.25)),
+            0.15,
+        )),
+    )));
+
+    // Grain from two browns, under amber varnish.
+    let grain = Mix::with_mask(
+        Box::new(Lambertian::new(Vec3(0.45, 0.25, 0.1))),
+        Box::new(Lambertian::new(Vec3(0.25, 0.12, 0.04))),
+        Arc::new(Noise::new(6.0, Vec3(1.0, 1.0, 1.0))),
+    );
+    let wood = Layered::new(Box::new(Dielectric::new(1.5)), Box::new(grain))
+        .with_absorption(Vec3(1.0, 3.0, 8.0), 0.05);
+
+    // Aluminium flakes scattered through red paint, under a clear coat.
+    let flakes = Mix::with_mask(
+        Box::new(Lambertian::new(Vec3(0.6, 0.02, 0.03))),
+        Box::new(Conductor::aluminium(0.3)),
+        Arc::new(Checker::new(
+            0.004,
+            Arc::new(SolidColor::scalar(0.0)),
+            Arc::new(SolidColor::scalar(0.5)),
+        )),
+    );
+    let paint = Layered::new(Box::new(Dielectric::new(1.5)), Box::new(flakes));
+
+    let lacquered_gold = Layered::new(
+        Box::new(Dielectric::new(1.5)),
+        Box::new(Conductor::gold(0.4)),
+    );
+
+    let scuffed = Mix::with_mask(
+        Box::new(Conductor::silver(0.02)),
+        Box::new(Conductor::silver(0.3)),
+        Arc::new(Noise::new(3.0, Vec3(1.0, 1.0, 1.0))),
+    );
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 1.6;
+    let materials: [Box<dyn Material + Sync + Send>; 4] = [
+        Box::new(wood),
+        Box::new(paint),
+        Box::new(lacquered_gold),
+        Box::new(scuffed),
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Arc::new(Sphere::new(
+            Vec3(0.0, 0.7, 0.0) + across * (i as f64 - 1.5),
+            0.7,
+            material,
+        )));
+    }
+    Scene::new(world).in_daylight()
+}
+
+/// Detail that lives only in the shading: bumpy plaster, faceted gold, a
+/// coarse mesh shaded smooth and rippled glass.
+pub fn normal_map() -> Scene {
+    use crate::bvh::BvhNode;
+    use crate::materials::{
+        conductor::Conductor, dielectric::Dielectric, lambertian::Lambertian, metal::Metal,
+        normal_map::NormalMap,
+    };
+    use crate::sphere::Sphere;
+    use crate::textures::{checker::Checker, noise::Noise, solid_color::SolidColor};
+    use crate::triangle::Triangle;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 1.6;
+    let center = |i: f64| Vec3(0.0, 0.7, 0.0) + across * (i - 1.5);
+
+    // Plaster raised along marble veins.
+    let plaster = NormalMap::bump(
+        Box::new(Lambertian::new(Vec3(0.8, 0.75, 0.65))),
+        Arc::new(Noise::new(5.0, Vec3(0.0, 0.0, 0.0))),
+        0.02,
+    );
+    world.add(Arc::new(Sphere::new(center(0.0), 0.7, Box::new(plaster))));
+
+    // Facets from a normal map tilting alternate squares apart.
+    let faceted = NormalMap::new(
+        Box::new(Conductor::gold(0.15)),
+        Arc::new(Checker::new(
+            0.15,
+            Arc::new(SolidColor::new(Vec3(0.75, 0.5, 0.95))),
+            Arc::new(SolidColor::new(Vec3(0.5, 0.75, 0.95))),
+        )),
+    );
+    world.add(Arc::new(Sphere::new(center(1.0), 0.7, Box::new(faceted))));
+
+    // A coarse mesh, shaded smooth by the normals at its corners.
+    let mut mesh = HittableList::new();
+    let (segments, rings) = (12, 6);
+    let corner = |i: usize, j: usize| {
+        let (u, v) = (i as f64 / segments as f64, j as f64 / rings as f64);
+        let (phi, theta) = (2.0 * PI * u, PI * v);
+        let n = Vec3(
+            -theta.sin() * phi.cos(),
+            -theta.cos(),
+            theta.sin() * phi.sin(),
+        );
+        (center(2.0) + n * 0.7, n, (u, v))
+    };
+    for i in 0..segments {
+        for j in 0..rings {
+            let quad = [
+                corner(i, j),
+                corner(i + 1, j),
+                corner(i + 1, j + 1),
+                corner(i, j + 1),
+            ];
+            for [a, b, c] in [[0, 1, 2], [0, 2, 3]] {
+                let (a, b, c) = (quad[a], quad[b], quad[c]);
+                if (b.0 - a.0).cross(&(c.0 - a.0)).near_zero() {
+                    continue;
+                }
+                let triangle = Triangle::new(
+                    a.0,
+                    b.0,
+                    c.0,
+                    Box::new(Metal::new(Vec3(0.8, 0.3, 0.3), 0.05)),
+                )
+                .with_uvs([a.2, b.2, c.2])
+                .with_normals([a.1, b.1, c.1]);
+                mesh.add(Arc::new(triangle));
+            }
+        }
+    }
+    world.add(Arc::new(BvhNode::new(mesh)));
+
+    // Rippled glass.
+    let glass = NormalMap::bump(
+        Box::new(Dielectric::new(1.5)),
+        Arc::new(Noise::new(3.0, Vec3(0.0, 0.0, 0.0))),
+        0.01,
+    );
+    world.add(Arc::new(Sphere::new(center(3.0), 0.7, Box::new(glass))));
+    Scene::new(world).in_daylight()
+}
+
+/// Surfaces with holes cut out of them: a lattice panel turned three
+/// ways and a ball of foliage.
+pub fn cutout() -> Scene {
+    use crate::instance::Instance;
+    use crate::materials::{cutout::Cutout, lambertian::Lambertian};
+    use crate::quad::Quad;
+    use crate::sphere::Sphere;
+    use crate::textures::{checker::Checker, noise::Noise, solid_color::SolidColor};
+    use crate::types::transform::Transform;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 1.6;
+
+    // A lattice panel, cut with a sharp threshold and instanced so the
+    // holes turn with it.
+    let lattice = Cutout::new(
+        Box::new(Lambertian::new(Vec3(0.6, 0.45, 0.3))),
+        Arc::new(Checker::new(
+            0.2,
+            Arc::new(SolidColor::scalar(1.0)),
+            Arc::new(SolidColor::scalar(0.0)),
+        )),
+    )
+    .with_threshold(0.5);
+    let panel: Arc<dyn Hittable + Sync + Send> = Arc::new(Quad::new(
+        Vec3(-0.6, 0.01, 0.1),
+        Vec3(1.2, 0.0, 0.0),
+        Vec3(0.0, 1.2, 0.0),
+        Box::new(lattice),
+    ));
+    for (i, angle) in [30.0, 60.0, 90.0].into_iter().enumerate() {
+        let transform = Transform::identity()
+            .rotate_y(angle)
+            .translate(across * (i as f64 - 1.5));
+        world.add(Arc::new(Instance::new(panel.clone(), transform)));
+    }
+
+    // Foliage thinning out along marble veins, kept at random.
+    let foliage = Cutout::new(
+        Box::new(Lambertian::new(Vec3(0.2, 0.5, 0.1))),
+        Arc::new(Noise::new(4.0, Vec3(0.0, 0.0, 0.0))),
+    );
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, 0.7, 0.0) + across * 1.5,
+        0.7,
+        Box::new(foliage),
+    )));
+    Scene::new(world).in_daylight()
+}
@@ -0,0 +1,143 @@
+use std::io;
+use std::sync::Arc;
+
+use super::{asset, Scene};
+use crate::hittable::HittableList;
+use crate::types::vec3::Vec3;
+
+/// Constant density media: a column of fog, a ball of smoke and glass
+/// filled with a scattering medium.
+pub fn media() -> Scene {
+    use crate::constant_medium::ConstantMedium;
+    use crate::cylinder::Cylinder;
+    use crate::materials::{dielectric::Dielectric, lambertian::Lambertian};
+    use crate::sphere::Sphere;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 2.2;
+
+    // A column of white fog.
+    world.add(Arc::new(ConstantMedium::new(
+        Arc::new(Cylinder::new(
+            -across,
+            0.8,
+            0.0,
+            2.0,
+            360.0,
+            true,
+            Box::new(Lambertian::new(Vec3(0.0, 0.0, 0.0))),
+        )),
+        1.5,
+        Vec3(1.0, 1.0, 1.0),
+    )));
+
+    // A ball of dark smoke.
+    world.add(Arc::new(ConstantMedium::new(
+        Arc::new(Sphere::new(
+            Vec3(0.0, 1.0, 0.0),
+            1.0,
+            Box::new(Lambertian::new(Vec3(0.0, 0.0, 0.0))),
+        )),
+        3.0,
+        Vec3(0.2, 0.2, 0.2),
+    )));
+
+    // Glass filled with a scattering blue medium, for a subsurface look.
+    let center = across + Vec3(0.0, 0.9, 0.0);
+    world.add(Arc::new(Sphere::new(
+        center,
+        0.9,
+        Box::new(Dielectric::new(1.5)),
+    )));
+    world.add(Arc::new(ConstantMedium::new(
+        Arc::new(Sphere::new(center, 0.9, Box::new(Dielectric::new(1.5)))),
+        8.0,
+        Vec3(0.2, 0.4, 0.9),
+    )));
+    Scene::new(world)
+}
+
+/// Media with varying density from voxel grids: a cloud of smoke and a
+/// glowing flame.
+///
+/// The smoke is read from `assets/smoke.vol`, which is baked from Perlin
+/// turbulence and saved there the first time the scene is built.
+pub fn fire() -> io::Result<Scene> {
+    use crate::heterogeneous_medium::HeterogeneousMedium;
+    use crate::materials::lambertian::Lambertian;
+    use crate::perlin::Perlin;
+    use crate::sphere::Sphere;
+    use crate::types::voxel_grid::VoxelGrid;
+
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Sphere::new(
+        Vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+    )));
+
+    let across = Vec3(3.0, 0.0, -13.0).unit_vector() * 1.6;
+
+    // A forward scattering cloud of smoke, baked from Perlin turbulence.
+    let path = asset("smoke.vol");
+    let smoke = match VoxelGrid::load(&path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let grid = VoxelGrid::from_noise(32, 4.0);
+            grid.save(&path)?;
+            grid
+        }
+        loaded => loaded?,
+    };
+    world.add(Arc::new(HeterogeneousMedium::new(
+        -across + Vec3(-1.0, 0.2, -1.0),
+        -across + Vec3(1.0, 2.2, 1.0),
+        smoke,
+        12.0,
+        Vec3(0.8, 0.8, 0.8),
+        0.6,
+    )));
+
+    // A flame: a noisy plume narrowing upwards, hottest at its base.
+    let noise = Perlin::new();
+    let plume = |p: &Vec3| {
+        let height = p.y();
+        let width = 0.45 * (1.0 - height).max(0.0) + 0.05;
+        let dx = p.x() - 0.5;
+        let dz = p.z() - 0.5;
+        let r = (dx * dx + dz * dz).sqrt() / width;
+        let wobble = noise.turb(&(*p * 6.0 + Vec3(0.0, -height * 4.0, 0.0)), 5);
+        ((1.0 - r) * (0.6 + wobble)).max(0.0)
+    };
+    let density = VoxelGrid::from_fn(32, 48, 32, plume);
+    let temperature = VoxelGrid::from_fn(32, 48, 32, |p| plume(p) * (1.0 - p.y()));
+    world.add(Arc::new(
+        HeterogeneousMedium::new(
+            across + Vec3(-0.6, 0.0, -0.6),
+            across + Vec3(0.6, 1.8, 0.6),
+            density,
+            20.0,
+            Vec3(0.1, 0.1, 0.1),
+            0.0,
+        )
+        .with_emission(temperature, Vec3(12.0, 4.0, 1.0)),
+    ));
+
+    // Thin, dark smoke rising off the flame.
+    world.add(Arc::new(HeterogeneousMedium::new(
+        across + Vec3(-0.5, 1.6, -0.5),
+        across + Vec3(0.5, 2.6, 0.5),
+        VoxelGrid::from_noise(24, 3.0),
+        6.0,
+        Vec3(0.3, 0.3, 0.3),
+        0.2,
+    )));
+    Ok(Scene::new(world))
+}
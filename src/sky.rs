@@ -0,0 +1,266 @@
+use std::f64::consts::PI;
+
+use crate::lights::directional_light::DirectionalLight;
+use crate::types::color::xyz_to_rgb;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+
+/// The sun's angular diameter in degrees.
+const SUN_ANGULAR_DIAMETER: f64 = 0.53;
+/// Illuminance of the sun above the atmosphere, in kilolux.
+const SOLAR_ILLUMINANCE: f64 = 127.0;
+
+/// Analytic daylight sky ("A Practical Analytic Model for Daylight",
+/// Preetham et al. 1999) for a sun in `sun_direction`. `turbidity` runs from
+/// about 2 for a clear day to 10 for a hazy one. Below the horizon there is
+/// flat ground of `ground_albedo` lit by the sun and the sky.
+///
+/// The sun disk itself is only drawn where the sky is seen directly or in
+/// mirrors. Its light on everything else comes from `sun_light`, which should
+/// be added to the camera's punctual lights alongside the sky.
+#[derive(Clone, Copy)]
+pub struct Sky {
+    /// Unit vector towards the sun.
+    pub sun_direction: Vec3,
+    pub turbidity: f64,
+    pub ground_albedo: Color,
+    /// Converts the model's kilocandelas per square meter to scene radiance.
+    pub scale: f64,
+    /// Perez coefficients A to E for luminance and the two chromaticities.
+    perez: [[f64; 5]; 3],
+    /// Luminance and chromaticity straight up.
+    zenith: [f64; 3],
+    /// Irradiance on a surface facing the sun, after the atmosphere.
+    sun_irradiance: Color,
+    /// Irradiance the sky alone gives the ground.
+    sky_irradiance: Color,
+}
+
+impl Sky {
+    pub fn new(sun_direction: Vec3, turbidity: f64, ground_albedo: Color) -> Sky {
+        let t = turbidity;
+        let sun_direction = sun_direction.unit_vector();
+        let theta_s = sun_direction.y().clamp(0.0, 1.0).acos();
+
+        let perez = [
+            [
+                0.1787 * t - 1.4630,
+                -0.3554 * t + 0.4275,
+                -0.0227 * t + 5.3251,
+                0.1206 * t - 2.5771,
+                -0.0670 * t + 0.3703,
+            ],
+            [
+                -0.0193 * t - 0.2592,
+                -0.0665 * t + 0.0008,
+                -0.0004 * t + 0.2125,
+                -0.0641 * t - 0.8989,
+                -0.0033 * t + 0.0452,
+            ],
+            [
+                -0.0167 * t - 0.2608,
+                -0.0950 * t + 0.0092,
+                -0.0079 * t + 0.2102,
+                -0.0441 * t - 1.6537,
+                -0.0109 * t + 0.0529,
+            ],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let (th, th2, th3) = (theta_s, theta_s * theta_s, theta_s.powi(3));
+        let zenith_x = t * t * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * th)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * th + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * th + 0.25886);
+        let zenith_y = t * t * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * th)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * th + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * th + 0.26688);
+
+        let mut sky = Sky {
+            sun_direction,
+            turbidity,
+            ground_albedo,
+            scale: 0.02,
+            perez,
+            zenith: [zenith_luminance.max(0.0), zenith_x, zenith_y],
+            sun_irradiance: Color(0.0, 0.0, 0.0),
+            sky_irradiance: Color(0.0, 0.0, 0.0),
+        };
+        sky.sun_irradiance = sky.sun_transmittance() * SOLAR_ILLUMINANCE;
+        sky.sky_irradiance = sky.integrate_irradiance();
+        sky
+    }
+
+    /// Radiance seen looking along `dir`, without the sun disk.
+    pub fn radiance(&self, dir: &Vec3) -> Color {
+        let dir = dir.unit_vector();
+        if dir.y() < 0.0 {
+            // Lambertian ground lit by the sun and the sky above it.
+            let sun = self.sun_irradiance * self.sun_direction.y().max(0.0);
+            return self.ground_albedo * (sun + self.sky_irradiance) * self.scale / PI;
+        }
+
+        let cos_theta = dir.y().max(0.01);
+        let gamma = dir.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+        let theta_s = self.sun_direction.y().clamp(0.0, 1.0).acos();
+        let value = |i: usize| {
+            self.zenith[i] * self.perez_function(i, cos_theta, gamma)
+                / self.perez_function(i, 1.0, theta_s)
+        };
+        let (luminance, x, y) = (value(0), value(1), value(2));
+        if y <= 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        let xyz = Vec3(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+        let rgb = xyz_to_rgb(&xyz);
+        Color(rgb.x().max(0.0), rgb.y().max(0.0), rgb.z().max(0.0)) * self.scale
+    }
+
+    /// Radiance along `dir`, with the sun disk if `dir` points into it.
+    ///
+    /// The disk's radiance is the sun's irradiance spread over its projected
+    /// solid angle, as for `sun_light`, so both light a surface facing the
+    /// sun the same.
+    pub fn radiance_with_sun(&self, dir: &Vec3) -> Color {
+        let cos_half_angle = (SUN_ANGULAR_DIAMETER / 2.0).to_radians().cos();
+        let sky = self.radiance(dir);
+        if dir.unit_vector().dot(&self.sun_direction) < cos_half_angle {
+            return sky;
+        }
+        let projected_solid_angle = PI * (1.0 - cos_half_angle * cos_half_angle);
+        sky + self.sun_irradiance * self.scale / projected_solid_angle
+    }
+
+    /// A directional light for the sun, matching the disk seen in the sky.
+    pub fn sun_light(&self) -> DirectionalLight {
+        DirectionalLight::new(
+            self.sun_direction,
+            self.sun_irradiance * self.scale,
+            SUN_ANGULAR_DIAMETER,
+        )
+    }
+
+    fn perez_function(&self, i: usize, cos_theta: f64, gamma: f64) -> f64 {
+        let [a, b, c, d, e] = self.perez[i];
+        (1.0 + a * (b / cos_theta).exp())
+            * (1.0 + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos())
+    }
+
+    /// Fraction of sunlight in each channel that makes it through the
+    /// atmosphere, from Rayleigh and aerosol optical depths (the latter from
+    /// turbidity as in Preetham's appendix) over the relative air mass.
+    fn sun_transmittance(&self) -> Color {
+        let elevation = self.sun_direction.y().clamp(0.0, 1.0).asin().to_degrees();
+        if elevation <= 0.0 {
+            return Color(0.0, 0.0, 0.0);
+        }
+        // Kasten and Young's air mass, which stays finite at the horizon.
+        let zenith_angle = 90.0 - elevation;
+        let air_mass = 1.0
+            / (zenith_angle.to_radians().cos() + 0.50572 * (96.07995 - zenith_angle).powf(-1.6364));
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        // Representative wavelengths for red, green and blue, in micrometers.
+        let tau = |lambda: f64| {
+            let rayleigh = 0.008735 * lambda.powf(-4.08);
+            let aerosol = beta * lambda.powf(-1.3);
+            (-air_mass * (rayleigh + aerosol)).exp()
+        };
+        Color(tau(0.65), tau(0.57), tau(0.475))
+    }
+
+    /// Cosine weighted integral of the sky over the upper hemisphere.
+    fn integrate_irradiance(&self) -> Color {
+        let (n_theta, n_phi) = (16, 32);
+        let mut sum = Color(0.0, 0.0, 0.0);
+        for i in 0..n_theta {
+            // Equal steps in cos^2 theta, so each cell has the same weight.
+            let cos_theta = (1.0 - (i as f64 + 0.5) / n_theta as f64).sqrt();
+            let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+            for j in 0..n_phi {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / n_phi as f64;
+                let dir = Vec3(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+                sum += self.radiance(&dir) / self.scale;
+            }
+        }
+        sum * PI / (n_theta * n_phi) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lights::light::Light;
+    use crate::types::color::luminance;
+    use crate::types::onb::Onb;
+
+    // Turbidity 3 with the sun 30 degrees above the horizon.
+    fn sky() -> Sky {
+        let elevation = 30.0_f64.to_radians();
+        Sky::new(
+            Vec3(elevation.cos(), elevation.sin(), 0.0),
+            3.0,
+            Color(0.3, 0.3, 0.3),
+        )
+    }
+
+    #[test]
+    fn zenith_matches_the_papers_formulas() {
+        // Preetham et al.'s zenith luminance and chromaticity for T = 3 and
+        // a sun 60 degrees from the zenith, worked out by hand.
+        let sky = sky();
+        let up = sky.radiance(&Vec3(0.0, 1.0, 0.0)) / sky.scale;
+        assert!((luminance(&up) - 5.1392).abs() < 1e-2, "{up:?}");
+        let [y, x_chromaticity, y_chromaticity] = sky.zenith;
+        assert!((y - 5.1392).abs() < 1e-3);
+        assert!((x_chromaticity - 0.2449).abs() < 1e-3, "{x_chromaticity}");
+        assert!((y_chromaticity - 0.2526).abs() < 1e-3, "{y_chromaticity}");
+    }
+
+    #[test]
+    fn sky_is_brightest_around_the_sun() {
+        let sky = sky();
+        let near_sun = luminance(&sky.radiance(&(sky.sun_direction + Vec3(0.0, 0.1, 0.0))));
+        let away = luminance(&sky.radiance(&Vec3(-1.0, 0.6, 0.0)));
+        let up = luminance(&sky.radiance(&Vec3(0.0, 1.0, 0.0)));
+        assert!(
+            near_sun > 2.0 * up && near_sun > 2.0 * away,
+            "{near_sun} {up} {away}"
+        );
+    }
+
+    #[test]
+    fn sun_disk_lights_like_the_sun_light() {
+        let sky = sky();
+        let light = sky.sun_light();
+
+        // The disk's radiance over the cosine weighted cone it covers.
+        let cos_half_angle = (SUN_ANGULAR_DIAMETER / 2.0).to_radians().cos();
+        let frame = Onb::new(&sky.sun_direction);
+        let (rings, steps) = (200, 64);
+        let mut irradiance = Color(0.0, 0.0, 0.0);
+        for i in 0..rings {
+            let cos_theta = 1.0 - (i as f64 + 0.5) / rings as f64 * (1.0 - cos_half_angle);
+            let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+            for j in 0..steps {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / steps as f64;
+                let dir = frame.local(&Vec3(
+                    sin_theta * phi.cos(),
+                    sin_theta * phi.sin(),
+                    cos_theta,
+                ));
+                let sun = sky.radiance_with_sun(&dir) - sky.radiance(&dir);
+                irradiance += sun * cos_theta;
+            }
+        }
+        irradiance = irradiance * 2.0 * PI * (1.0 - cos_half_angle) / (rings * steps) as f64;
+        assert!((irradiance - light.irradiance).length() < 1e-6 * light.irradiance.length());
+
+        // Each sample of the light carries the disk's radiance over its
+        // uniform density.
+        let sample = light.sample(&Vec3(0.0, 0.0, 0.0)).unwrap();
+        let solid_angle = 2.0 * PI * (1.0 - cos_half_angle);
+        let disk = sky.radiance_with_sun(&sample.dir) - sky.radiance(&sample.dir);
+        assert!((disk * solid_angle - sample.radiance).length() < 1e-9 * sample.radiance.length());
+    }
+}
@@ -1,6 +1,14 @@
+use std::f64::consts::PI;
+
 use crate::hittable::Hittable;
 use crate::materials::material::Material;
+use crate::math::solve_quadratic;
+use crate::random::random_f64;
+use crate::types::aabb::Aabb;
+use crate::types::color::luminance;
 use crate::types::interval::Interval;
+use crate::types::light_bounds::LightBounds;
+use crate::types::onb::Onb;
 use crate::types::ray::Ray;
 use crate::types::vec3::Vec3;
 
@@ -30,6 +38,41 @@ impl Sphere {
             center: Ray::new(center1, center2 - center1),
         }
     }
+
+    /// `1 - cos` of the half angle of the cone the sphere subtends from
+    /// `origin`, or `None` if the origin is inside and the whole surface has
+    /// to be sampled instead.
+    fn cone_sampling(&self, origin: &Vec3, center: &Vec3) -> Option<f64> {
+        let distance_squared = (*center - *origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return None;
+        }
+        // Written so that it stays accurate for small, distant spheres.
+        let sin2_max = radius_squared / distance_squared;
+        Some(sin2_max / (1.0 + (1.0 - sin2_max).sqrt()))
+    }
+
+    fn get_sphere_uv(p: &Vec3) -> (f64, f64) {
+        // p: a given point on the sphere of radius one, centered at the origin.
+        // u: returned value [0,1] of angle around the Y axis from X=-1.
+        // v: returned value [0,1] of angle from Y=-1 to Y=+1.
+        let theta = (-p.y()).acos();
+        let phi = (-p.z()).atan2(p.x()) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    /// Derivatives of the point `p` on the unit sphere with respect to the
+    /// `u` and `v` of `get_sphere_uv`. `dpdv` vanishes at the poles.
+    fn get_sphere_tangents(p: &Vec3) -> (Vec3, Vec3) {
+        let dpdu = 2.0 * PI * Vec3(p.z(), 0.0, -p.x());
+        let rho = (p.x() * p.x() + p.z() * p.z()).sqrt();
+        if rho == 0.0 {
+            return (dpdu, Vec3(0.0, 0.0, 0.0));
+        }
+        let dpdv = PI * Vec3(-p.x() * p.y() / rho, rho, -p.y() * p.z() / rho);
+        (dpdu, dpdv)
+    }
 }
 
 impl Hittable for Sphere {
@@ -64,7 +107,84 @@ impl Hittable for Sphere {
 
         // rec.normal = (rec.p - self.center) / self.radius;
         rec.set_face_normal(r, &outward_normal);
+        (rec.u, rec.v) = Sphere::get_sphere_uv(&outward_normal);
+        let (dpdu, dpdv) = Sphere::get_sphere_tangents(&outward_normal);
+        rec.set_tangents(dpdu * self.radius, dpdv * self.radius);
         rec.mat = self.mat.my_copy();
         return true;
     }
+
+    fn bounding_box(&self) -> Aabb {
+        let rvec = Vec3(self.radius, self.radius, self.radius);
+        let box0 = Aabb::from_points(self.center.at(0.0) - rvec, self.center.at(0.0) + rvec);
+        let box1 = Aabb::from_points(self.center.at(1.0) - rvec, self.center.at(1.0) + rvec);
+        Aabb::surrounding(&box0, &box1)
+    }
+
+    fn is_light(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        let center = self.center.at(r.time());
+        let oc = r.origin() - center;
+        let roots = solve_quadratic(
+            r.dir().length_squared(),
+            2.0 * oc.dot(&r.dir()),
+            oc.length_squared() - self.radius * self.radius,
+        );
+        let (t0, t1) = match roots {
+            Some(roots) => roots,
+            None => return 0.0,
+        };
+
+        match self.cone_sampling(&r.origin(), &center) {
+            // Every direction in the cone is equally likely.
+            Some(one_minus_cos_max) if t1 > 0.001 => 1.0 / (2.0 * PI * one_minus_cos_max),
+            Some(_) => 0.0,
+            None => {
+                // From inside, `random` picks points uniformly over the whole
+                // surface, and both roots lie along the direction.
+                let area = 4.0 * PI * self.radius * self.radius;
+                let unit_dir = r.dir().unit_vector();
+                let mut pdf = 0.0;
+                for t in [t0, t1] {
+                    if t <= 0.001 {
+                        continue;
+                    }
+                    let p = r.at(t);
+                    let cosine = ((p - center) / self.radius).dot(&unit_dir).abs();
+                    let distance_squared = (p - r.origin()).length_squared();
+                    pdf += distance_squared / (cosine.max(1e-8) * area);
+                }
+                pdf
+            }
+        }
+    }
+
+    fn random(&self, origin: &Vec3, time: f64) -> Vec3 {
+        let center = self.center.at(time);
+        let one_minus_cos_max = match self.cone_sampling(origin, &center) {
+            Some(one_minus_cos_max) => one_minus_cos_max,
+            None => return center + self.radius * Vec3::random_unit_vector() - *origin,
+        };
+
+        // Uniform over the cone of directions the sphere subtends.
+        let cos_theta = 1.0 - random_f64() * one_minus_cos_max;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * random_f64();
+        Onb::new(&(center - *origin)).local(&Vec3(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+
+    fn light_bounds(&self) -> LightBounds {
+        let area = 4.0 * PI * self.radius * self.radius;
+        LightBounds::omni(
+            self.bounding_box(),
+            PI * area * luminance(&self.mat.emission()),
+        )
+    }
 }
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use crate::hittable::{HitRecord, Hittable};
+use crate::materials::material::Material;
+use crate::materials::subsurface::Subsurface;
+use crate::types::aabb::Aabb;
+use crate::types::interval::Interval;
+use crate::types::ray::Ray;
+
+/// A closed object made of a `Subsurface` material, which replaces the
+/// material of `boundary` and random walks inside it.
+pub struct SubsurfaceVolume {
+    boundary: Arc<dyn Hittable + Sync + Send>,
+    material: Box<dyn Material + Sync + Send>,
+}
+
+impl SubsurfaceVolume {
+    pub fn new(
+        boundary: Arc<dyn Hittable + Sync + Send>,
+        material: Subsurface,
+    ) -> SubsurfaceVolume {
+        SubsurfaceVolume {
+            material: Box::new(material.filling(boundary.clone())),
+            boundary,
+        }
+    }
+}
+
+impl Hittable for SubsurfaceVolume {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        if !self.boundary.hit(r, ray_t, rec) {
+            return false;
+        }
+        rec.mat = self.material.my_copy();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::RAY_EPSILON;
+    use crate::materials::material::Nothing;
+    use crate::sphere::Sphere;
+    use crate::types::vec3::Vec3;
+
+    use Vec3 as Color;
+    use Vec3 as Point3;
+
+    #[test]
+    fn light_walks_through_and_leaves_the_volume() {
+        let volume = SubsurfaceVolume::new(
+            Arc::new(Sphere::new(
+                Point3(0.0, 0.0, 0.0),
+                1.0,
+                Box::new(Nothing {}),
+            )),
+            Subsurface::new(1.3, Color(0.8, 0.8, 0.8), Color(0.2, 0.2, 0.2)),
+        );
+        let ray_t = Interval::new(RAY_EPSILON, f64::INFINITY);
+        let r_in = Ray::new(Point3(0.0, 0.0, 3.0), Vec3(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+        assert!(volume.hit(&r_in, &ray_t, &mut rec) && rec.front_face);
+
+        let mut entered = 0;
+        for _ in 0..1000 {
+            let mut attenuation = Color(0.0, 0.0, 0.0);
+            let mut scattered = Ray::new(rec.p, Vec3(0.0, 0.0, 0.0));
+            if !rec
+                .mat
+                .scatter(&r_in, &rec, &mut attenuation, &mut scattered)
+            {
+                continue;
+            }
+            if scattered.dir().z() > 0.0 && scattered.origin().z() > 1.0 - 1e-9 {
+                continue; // Reflected off the top.
+            }
+            entered += 1;
+
+            // The walk ends just short of the surface, where the volume
+            // sends the light back out.
+            let mut exit = HitRecord::new();
+            assert!(volume.hit(&scattered, &ray_t, &mut exit) && !exit.front_face);
+            assert!(exit.t < 3.0 * RAY_EPSILON);
+            let mut out = Ray::new(exit.p, Vec3(0.0, 0.0, 0.0));
+            assert!(exit
+                .mat
+                .scatter(&scattered, &exit, &mut attenuation, &mut out));
+            assert!(out.dir().dot(&exit.p) > 0.0);
+        }
+        assert!(entered > 500, "{entered}");
+    }
+}
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use super::texture::Texture;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+use Vec3 as Point3;
+
+/// Alternates between two textures in a 3D grid of cubes `scale` wide, so it
+/// needs no surface coordinates.
+pub struct Checker {
+    pub scale: f64,
+    pub even: Arc<dyn Texture + Sync + Send>,
+    pub odd: Arc<dyn Texture + Sync + Send>,
+}
+
+impl Checker {
+    pub fn new(
+        scale: f64,
+        even: Arc<dyn Texture + Sync + Send>,
+        odd: Arc<dyn Texture + Sync + Send>,
+    ) -> Checker {
+        Checker { scale, even, odd }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let cell = |x: f64| (x / self.scale).floor() as i64;
+        if (cell(p.x()) + cell(p.y()) + cell(p.z())) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod checker;
+pub mod noise;
+pub mod solid_color;
+pub mod texture;
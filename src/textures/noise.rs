@@ -0,0 +1,31 @@
+use super::texture::Texture;
+use crate::perlin::Perlin;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+use Vec3 as Point3;
+
+/// Marble-like veins of `color` on white, from Perlin turbulence warping
+/// stripes along z. `scale` sets how many stripes fit in a unit of length.
+pub struct Noise {
+    pub scale: f64,
+    pub color: Color,
+    noise: Perlin,
+}
+
+impl Noise {
+    pub fn new(scale: f64, color: Color) -> Noise {
+        Noise {
+            scale,
+            color,
+            noise: Perlin::new(),
+        }
+    }
+}
+
+impl Texture for Noise {
+    fn value(&self, _u: f64, _v: f64, p: &Point3) -> Color {
+        let stripes = 0.5 * (1.0 + (self.scale * p.z() + 10.0 * self.noise.turb(p, 7)).sin());
+        Color(1.0, 1.0, 1.0) * stripes + self.color * (1.0 - stripes)
+    }
+}
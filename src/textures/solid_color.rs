@@ -0,0 +1,27 @@
+use super::texture::Texture;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+use Vec3 as Point3;
+
+/// The same color everywhere.
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> SolidColor {
+        SolidColor { color }
+    }
+
+    /// A constant for a scalar parameter.
+    pub fn scalar(value: f64) -> SolidColor {
+        SolidColor::new(Color(value, value, value))
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
+        self.color
+    }
+}
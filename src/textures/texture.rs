@@ -0,0 +1,10 @@
+use crate::types::vec3::Vec3;
+
+use Vec3 as Color;
+use Vec3 as Point3;
+
+pub trait Texture {
+    /// Color at the surface coordinates `(u, v)` of the hit point `p`.
+    /// Scalar parameters read the first channel.
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color;
+}
@@ -0,0 +1,170 @@
+use std::f64::consts::PI;
+
+use crate::hittable::{HitRecord, Hittable};
+use crate::materials::material::Material;
+use crate::math::{azimuth, solve_quadratic, solve_quartic};
+use crate::types::aabb::Aabb;
+use crate::types::interval::Interval;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// A torus lying in the horizontal plane through `center`: a tube of
+/// `minor_radius` swept around a circle of `major_radius`, `phi_max` radians far.
+pub struct Torus {
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub phi_max: f64,
+    pub mat: Box<dyn Material + Sync + Send>,
+    center: Point3,
+}
+
+impl Torus {
+    /// `phi_max` is given in degrees, like the camera's field of view.
+    pub fn new(
+        center: Point3,
+        major_radius: f64,
+        minor_radius: f64,
+        phi_max: f64,
+        mat: Box<dyn Material + Sync + Send>,
+    ) -> Torus {
+        Torus {
+            major_radius,
+            minor_radius,
+            phi_max: phi_max.clamp(0.0, 360.0).to_radians(),
+            mat,
+            center,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let big_r = self.major_radius;
+        let small_r = self.minor_radius;
+
+        // The quartic is badly conditioned for distant origins, so solve it with
+        // a unit direction starting where the line enters the bounding sphere.
+        // That may be behind the ray, as spans are found from -∞.
+        let len = r.dir().length();
+        let dir = r.dir() / len;
+        let mut origin = r.origin() - self.center;
+        let bound = big_r + small_r;
+        let (enter, exit) = match solve_quadratic(
+            1.0,
+            2.0 * origin.dot(&dir),
+            origin.length_squared() - bound * bound,
+        ) {
+            Some(roots) => roots,
+            None => return false,
+        };
+        if exit / len <= ray_t.min || enter / len >= ray_t.max {
+            return false;
+        }
+        let shift = enter;
+        origin += shift * dir;
+
+        // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2)
+        let od = origin.dot(&dir);
+        let l = origin.length_squared() + big_r * big_r - small_r * small_r;
+        let four_r2 = 4.0 * big_r * big_r;
+        let coeffs = [
+            l * l - four_r2 * (origin.x() * origin.x() + origin.z() * origin.z()),
+            4.0 * od * l - 2.0 * four_r2 * (origin.x() * dir.x() + origin.z() * dir.z()),
+            4.0 * od * od + 2.0 * l - four_r2 * (dir.x() * dir.x() + dir.z() * dir.z()),
+            4.0 * od,
+            1.0,
+        ];
+        // Grazing and degenerate rays can give NaN roots.
+        let mut roots: Vec<f64> = solve_quartic(coeffs)
+            .into_iter()
+            .filter(|s| s.is_finite())
+            .collect();
+        roots.sort_by(f64::total_cmp);
+
+        for s in roots {
+            let t = (s + shift) / len;
+            if !ray_t.surrounds(t) {
+                continue;
+            }
+            let p = origin + s * dir;
+            let phi = azimuth(p.x(), p.z());
+            if phi > self.phi_max {
+                continue;
+            }
+            rec.t = t;
+            rec.p = r.at(t);
+            // The normal points away from the closest point on the tube's core circle.
+            let core = Vec3(p.x(), 0.0, p.z()).unit_vector() * big_r;
+            let outward_normal = (p - core).unit_vector();
+            rec.set_face_normal(r, &outward_normal);
+            let radial = (p.x() * p.x() + p.z() * p.z()).sqrt() - big_r;
+            let theta = p.y().atan2(radial);
+            rec.u = phi / self.phi_max;
+            rec.v = if theta < 0.0 { theta + 2.0 * PI } else { theta } / (2.0 * PI);
+            let around = Vec3(p.x(), 0.0, p.z()).unit_vector();
+            rec.set_tangents(
+                self.phi_max * Vec3(-p.z(), 0.0, p.x()),
+                2.0 * PI * (Vec3(0.0, radial, 0.0) - around * p.y()),
+            );
+            rec.mat = self.mat.my_copy();
+            return true;
+        }
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let extent = self.major_radius + self.minor_radius;
+        Aabb::from_points(
+            self.center + Vec3(-extent, -self.minor_radius, -extent),
+            self.center + Vec3(extent, self.minor_radius, extent),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn torus() -> Torus {
+        Torus::new(
+            Point3(0.0, 0.0, 0.0),
+            2.0,
+            0.5,
+            360.0,
+            Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn spans_include_hits_behind_the_origin() {
+        // Past the torus along x, the tube is crossed twice behind the ray.
+        let r = Ray::new(Point3(5.0, 0.0, 0.0), Vec3(1.0, 0.0, 0.0));
+        let spans = torus().hit_spans(&r);
+        assert_eq!(spans.len(), 2);
+        let expected = [(-7.5, -6.5), (-3.5, -2.5)];
+        for (span, (enter, exit)) in spans.iter().zip(expected) {
+            assert!((span.enter.t - enter).abs() < 1e-9);
+            assert!((span.exit.t - exit).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn hit_skips_roots_before_the_interval() {
+        let r = Ray::new(Point3(0.0, 0.0, 0.0), Vec3(1.0, 0.0, 0.0));
+        let mut rec = HitRecord::new();
+        assert!(torus().hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rec));
+        assert!((rec.t - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn grazing_rays_do_not_panic() {
+        // Along the top of the tube, where roots are double.
+        let r = Ray::new(Point3(-5.0, 0.5, 0.0), Vec3(1.0, 0.0, 0.0));
+        let mut rec = HitRecord::new();
+        torus().hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rec);
+        torus().hit_spans(&r);
+    }
+}
@@ -0,0 +1,326 @@
+use std::f64::consts::PI;
+
+use crate::hittable::{HitRecord, Hittable, RAY_EPSILON};
+use crate::materials::material::Material;
+use crate::math::{MAX_SPHERICAL_SAMPLE_AREA, MIN_SPHERICAL_SAMPLE_AREA};
+use crate::random::random_f64;
+use crate::types::aabb::Aabb;
+use crate::types::color::luminance;
+use crate::types::interval::Interval;
+use crate::types::light_bounds::LightBounds;
+use crate::types::ray::Ray;
+use crate::types::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// A triangle with corners `a`, `b` and `c`, facing the side from which they
+/// run counter-clockwise.
+///
+/// As part of a mesh it can carry texture coordinates and normals at its
+/// corners, which are interpolated across it. Without them `u` and `v` are
+/// the barycentric coordinates of `b` and `c`.
+pub struct Triangle {
+    pub a: Point3,
+    pub b: Point3,
+    pub c: Point3,
+    pub mat: Box<dyn Material + Sync + Send>,
+    pub uvs: Option<[(f64, f64); 3]>,
+    pub normals: Option<[Vec3; 3]>,
+    normal: Vec3,
+    area: f64,
+}
+
+impl Triangle {
+    pub fn new(a: Point3, b: Point3, c: Point3, mat: Box<dyn Material + Sync + Send>) -> Triangle {
+        let n = (b - a).cross(&(c - a));
+        Triangle {
+            a,
+            b,
+            c,
+            mat,
+            uvs: None,
+            normals: None,
+            normal: n.unit_vector(),
+            area: n.length() / 2.0,
+        }
+    }
+
+    pub fn with_uvs(mut self, uvs: [(f64, f64); 3]) -> Triangle {
+        self.uvs = Some(uvs);
+        self
+    }
+
+    /// Smooths the shading with normals at the corners. Normals pointing to
+    /// the back are flipped, since the winding decides which side is out.
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Triangle {
+        self.normals = Some(normals.map(|n| match n.dot(&self.normal) < 0.0 {
+            true => -n.unit_vector(),
+            false => n.unit_vector(),
+        }));
+        self
+    }
+
+    /// Derivatives of the surface with respect to the texture coordinates,
+    /// from how they change along the edges. Degenerate coordinates fall
+    /// back to the edges themselves.
+    fn tangents(&self) -> (Vec3, Vec3) {
+        let edges = (self.b - self.a, self.c - self.a);
+        let Some([uv0, uv1, uv2]) = self.uvs else {
+            return edges;
+        };
+        let duv1 = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+        let duv2 = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+        let det = duv1.0 * duv2.1 - duv1.1 * duv2.0;
+        if det.abs() < 1e-12 {
+            return edges;
+        }
+        let dpdu = (edges.0 * duv2.1 - edges.1 * duv1.1) / det;
+        let dpdv = (edges.1 * duv1.0 - edges.0 * duv2.0) / det;
+        (dpdu, dpdv)
+    }
+
+    /// Unit vectors from `origin` to the corners, if the triangle's solid
+    /// angle from there is suitable for sampling directly.
+    fn spherical_triangle(&self, origin: &Point3) -> Option<[Vec3; 3]> {
+        let corners = [
+            (self.a - *origin).unit_vector(),
+            (self.b - *origin).unit_vector(),
+            (self.c - *origin).unit_vector(),
+        ];
+        let solid_angle = spherical_triangle_area(&corners);
+        if !(MIN_SPHERICAL_SAMPLE_AREA..=MAX_SPHERICAL_SAMPLE_AREA).contains(&solid_angle) {
+            return None;
+        }
+        Some(corners)
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        // Möller-Trumbore: solve for t and two barycentric coordinates at once.
+        let edge1 = self.b - self.a;
+        let edge2 = self.c - self.a;
+        let p = r.dir().cross(&edge2);
+        let det = edge1.dot(&p);
+        if det.abs() < 1e-12 {
+            return false;
+        }
+        let inv_det = 1.0 / det;
+        let s = r.origin() - self.a;
+        let u = s.dot(&p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return false;
+        }
+        let q = s.cross(&edge1);
+        let v = r.dir().dot(&q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return false;
+        }
+        let t = edge2.dot(&q) * inv_det;
+        if !ray_t.surrounds(t) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.set_face_normal(r, &self.normal);
+        let w = 1.0 - u - v;
+        (rec.u, rec.v) = match self.uvs {
+            Some([uv0, uv1, uv2]) => (
+                w * uv0.0 + u * uv1.0 + v * uv2.0,
+                w * uv0.1 + u * uv1.1 + v * uv2.1,
+            ),
+            None => (u, v),
+        };
+        let (dpdu, dpdv) = self.tangents();
+        rec.set_tangents(dpdu, dpdv);
+        if let Some([n0, n1, n2]) = self.normals {
+            rec.set_shading_normal(r, &(w * n0 + u * n1 + v * n2));
+        }
+        rec.mat = self.mat.my_copy();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let ab = Aabb::from_points(self.a, self.b);
+        Aabb::surrounding(&ab, &Aabb::from_points(self.c, self.c)).pad()
+    }
+
+    fn is_light(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    fn pdf_value(&self, r: &Ray) -> f64 {
+        let mut rec = HitRecord::new();
+        if !self.hit(r, &Interval::new(RAY_EPSILON, f64::INFINITY), &mut rec) {
+            return 0.0;
+        }
+        if let Some(corners) = self.spherical_triangle(&r.origin()) {
+            return 1.0 / spherical_triangle_area(&corners);
+        }
+        let distance_squared = rec.t * rec.t * r.dir().length_squared();
+        let cosine = r.dir().unit_vector().dot(&self.normal).abs();
+        distance_squared / (cosine.max(1e-8) * self.area)
+    }
+
+    fn random(&self, origin: &Point3, _time: f64) -> Vec3 {
+        if let Some(corners) = self.spherical_triangle(origin) {
+            return sample_spherical_triangle(&corners, random_f64(), random_f64());
+        }
+        let su = random_f64().sqrt();
+        let (b0, b1) = (1.0 - su, random_f64() * su);
+        b0 * self.a + b1 * self.b + (1.0 - b0 - b1) * self.c - *origin
+    }
+
+    fn light_bounds(&self) -> LightBounds {
+        let power = PI * self.area * luminance(&self.mat.emission());
+        LightBounds::new(self.bounding_box(), self.normal, power, 1.0, 0.0, false)
+    }
+}
+
+/// Solid angle of the spherical triangle with unit vertices `v`
+/// (Van Oosterom and Strackee).
+fn spherical_triangle_area(v: &[Vec3; 3]) -> f64 {
+    let [a, b, c] = v;
+    let numerator = a.dot(&b.cross(c)).abs();
+    let denominator = 1.0 + a.dot(b) + b.dot(c) + c.dot(a);
+    2.0 * numerator.atan2(denominator)
+}
+
+/// Uniformly distributed direction inside the spherical triangle with unit
+/// vertices `v`, for uniform `s` and `t` in [0,1) ("Stratified Sampling of
+/// Spherical Triangles", Arvo 1995, in the form given by pbrt-v4).
+fn sample_spherical_triangle(v: &[Vec3; 3], s: f64, t: f64) -> Vec3 {
+    let [a, b, c] = v;
+    // Normals of the great circles through each edge.
+    let n_ab = a.cross(b).unit_vector();
+    let n_bc = b.cross(c).unit_vector();
+    let n_ca = c.cross(a).unit_vector();
+    let alpha = angle_between(&n_ab, &-n_ca);
+    let beta = angle_between(&n_bc, &-n_ab);
+    let gamma = angle_between(&n_ca, &-n_bc);
+
+    // Pick the sub-triangle a, b, c' holding fraction `s` of the area.
+    let area_pi = alpha + beta + gamma;
+    let sub_area_pi = PI + s * (area_pi - PI);
+    let (sin_alpha, cos_alpha) = alpha.sin_cos();
+    let (sin_sub, cos_sub) = sub_area_pi.sin_cos();
+    let sin_phi = sin_sub * cos_alpha - cos_sub * sin_alpha;
+    let cos_phi = cos_sub * cos_alpha + sin_sub * sin_alpha;
+    let k1 = cos_phi + cos_alpha;
+    let k2 = sin_phi - sin_alpha * a.dot(b);
+    let cos_b = ((k2 + (k2 * cos_phi - k1 * sin_phi) * cos_alpha)
+        / ((k2 * sin_phi + k1 * cos_phi) * sin_alpha))
+        .clamp(-1.0, 1.0);
+    let sin_b = (1.0 - cos_b * cos_b).max(0.0).sqrt();
+    let c_prime = cos_b * *a + sin_b * gram_schmidt(c, a).unit_vector();
+
+    // Then a point on the arc from b to c'.
+    let cos_theta = 1.0 - t * (1.0 - c_prime.dot(b));
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    cos_theta * *b + sin_theta * gram_schmidt(&c_prime, b).unit_vector()
+}
+
+/// Angle between unit vectors, accurate when they are nearly parallel.
+fn angle_between(v1: &Vec3, v2: &Vec3) -> f64 {
+    if v1.dot(v2) < 0.0 {
+        PI - 2.0 * ((*v1 + *v2).length() / 2.0).clamp(-1.0, 1.0).asin()
+    } else {
+        2.0 * ((*v2 - *v1).length() / 2.0).clamp(-1.0, 1.0).asin()
+    }
+}
+
+/// The part of `v` perpendicular to the unit vector `w`.
+fn gram_schmidt(v: &Vec3, w: &Vec3) -> Vec3 {
+    *v - v.dot(w) * *w
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::Lambertian;
+
+    fn triangle(a: Point3, b: Point3, c: Point3) -> Triangle {
+        Triangle::new(a, b, c, Box::new(Lambertian::new(Vec3(0.5, 0.5, 0.5))))
+    }
+
+    fn hits(object: &Triangle, origin: &Point3, dir: Vec3) -> bool {
+        let r = Ray::new(*origin, dir);
+        object.hit(
+            &r,
+            &Interval::new(0.0, f64::INFINITY),
+            &mut HitRecord::new(),
+        )
+    }
+
+    #[test]
+    fn octant_covers_an_eighth_of_the_sphere() {
+        let corners = [
+            Vec3(1.0, 0.0, 0.0),
+            Vec3(0.0, 1.0, 0.0),
+            Vec3(0.0, 0.0, 1.0),
+        ];
+        assert!((spherical_triangle_area(&corners) - PI / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn pdf_integrates_to_one_over_the_sphere() {
+        let light = triangle(
+            Point3(1.0, 0.0, -1.0),
+            Point3(-0.5, 0.5, -1.5),
+            Point3(0.2, -1.0, -0.8),
+        );
+        let origin = Point3(0.1, 0.2, 0.3);
+        assert!(light.spherical_triangle(&origin).is_some());
+
+        // Directions on rings of equal area, all weighing the same.
+        let (rings, steps) = (500, 1000);
+        let mut total = 0.0;
+        for i in 0..rings {
+            let z = 1.0 - 2.0 * (i as f64 + 0.5) / rings as f64;
+            let radius = (1.0 - z * z).sqrt();
+            for j in 0..steps {
+                let phi = 2.0 * PI * (j as f64 + 0.5) / steps as f64;
+                let dir = Vec3(radius * phi.cos(), radius * phi.sin(), z);
+                total += light.pdf_value(&Ray::new(origin, dir));
+            }
+        }
+        let integral = total * 4.0 * PI / (rings * steps) as f64;
+        assert!((integral - 1.0).abs() < 5e-3, "{integral}");
+    }
+
+    #[test]
+    fn samples_spread_evenly_over_the_solid_angle() {
+        let (a, b, c) = (
+            Point3(1.0, 0.0, -1.0),
+            Point3(-0.5, 0.5, -1.5),
+            Point3(0.2, -1.0, -0.8),
+        );
+        let light = triangle(a, b, c);
+        let origin = Point3(0.1, 0.2, 0.3);
+        let corners = light.spherical_triangle(&origin).unwrap();
+
+        // The half cut off by the midpoint of one side holds a share of the
+        // samples matching its share of the solid angle.
+        let half = triangle(a, b, (a + c) / 2.0);
+        let share = spherical_triangle_area(&half.spherical_triangle(&origin).unwrap())
+            / spherical_triangle_area(&corners);
+
+        let n = 200;
+        let mut inside_half = 0;
+        for i in 0..n {
+            for j in 0..n {
+                let s = (i as f64 + 0.5) / n as f64;
+                let t = (j as f64 + 0.5) / n as f64;
+                let dir = sample_spherical_triangle(&corners, s, t);
+                assert!((dir.length() - 1.0).abs() < 1e-9);
+                assert!(hits(&light, &origin, dir));
+                if hits(&half, &origin, dir) {
+                    inside_half += 1;
+                }
+            }
+        }
+        let fraction = inside_half as f64 / (n * n) as f64;
+        assert!((fraction - share).abs() < 5e-3, "{fraction} vs {share}");
+    }
+}
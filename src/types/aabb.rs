@@ -0,0 +1,90 @@
+use super::interval::Interval;
+use super::ray::Ray;
+use super::vec3::Vec3 as Point3;
+
+/// Axis-aligned bounding box, stored as one interval per axis.
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Aabb {
+        Aabb { x, y, z }
+    }
+
+    pub fn empty() -> Aabb {
+        Aabb::new(Interval::empty(), Interval::empty(), Interval::empty())
+    }
+
+    pub fn from_points(a: Point3, b: Point3) -> Aabb {
+        // Treat the two points as extrema for the bounding box, in any order.
+        Aabb {
+            x: Interval::new(a.x().min(b.x()), a.x().max(b.x())),
+            y: Interval::new(a.y().min(b.y()), a.y().max(b.y())),
+            z: Interval::new(a.z().min(b.z()), a.z().max(b.z())),
+        }
+    }
+
+    pub fn surrounding(box0: &Aabb, box1: &Aabb) -> Aabb {
+        Aabb {
+            x: Interval::from_intervals(&box0.x, &box1.x),
+            y: Interval::from_intervals(&box0.y, &box1.y),
+            z: Interval::from_intervals(&box0.z, &box1.z),
+        }
+    }
+
+    pub fn axis(&self, n: usize) -> &Interval {
+        match n {
+            1 => &self.y,
+            2 => &self.z,
+            _ => &self.x,
+        }
+    }
+
+    /// Grows any axis thinner than a small delta, so flat shapes still have volume.
+    pub fn pad(&self) -> Aabb {
+        let delta = 0.0001;
+        let pad = |i: &Interval| {
+            if i.size() >= delta {
+                *i
+            } else {
+                i.expand(delta)
+            }
+        };
+        Aabb::new(pad(&self.x), pad(&self.y), pad(&self.z))
+    }
+
+    pub fn hit(&self, r: &Ray, ray_t: &Interval) -> bool {
+        self.intersect(r, ray_t).is_some()
+    }
+
+    /// The part of `ray_t` where the ray is inside the box, if any.
+    pub fn intersect(&self, r: &Ray, ray_t: &Interval) -> Option<Interval> {
+        let mut t_min = ray_t.min;
+        let mut t_max = ray_t.max;
+        let origin = r.origin();
+        let dir = r.dir();
+        for a in 0..3 {
+            let (o, d) = match a {
+                0 => (origin.x(), dir.x()),
+                1 => (origin.y(), dir.y()),
+                _ => (origin.z(), dir.z()),
+            };
+            let inv_d = 1.0 / d;
+            let mut t0 = (self.axis(a).min - o) * inv_d;
+            let mut t1 = (self.axis(a).max - o) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some(Interval::new(t_min, t_max))
+    }
+}
@@ -0,0 +1,187 @@
+use super::aabb::Aabb;
+use super::quaternion::Quaternion;
+use super::transform::Transform;
+use super::vec3::Vec3;
+
+/// The pose of an object at one instant: scaled, then rotated, then translated.
+#[derive(Copy, Clone, Debug)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, translation: Vec3, rotation: Quaternion, scale: Vec3) -> Keyframe {
+        Keyframe {
+            time,
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    fn to_transform(self) -> Transform {
+        Transform::from_scale(self.scale)
+            .then(&Transform::from_quaternion(&self.rotation))
+            .translate(self.translation)
+    }
+}
+
+/// A transform that varies over the shutter interval. Translation and scale
+/// are interpolated linearly and rotation with slerp between neighbouring
+/// keyframes; times outside the keyframes hold the first or last pose.
+#[derive(Clone)]
+pub struct AnimatedTransform {
+    keyframes: Vec<Keyframe>,
+}
+
+impl AnimatedTransform {
+    /// Panics if `keyframes` is empty.
+    pub fn new(mut keyframes: Vec<Keyframe>) -> AnimatedTransform {
+        assert!(!keyframes.is_empty(), "animated transform needs a keyframe");
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        AnimatedTransform { keyframes }
+    }
+
+    pub fn interpolate(&self, time: f64) -> Transform {
+        let first = &self.keyframes[0];
+        let last = &self.keyframes[self.keyframes.len() - 1];
+        if time <= first.time {
+            return first.to_transform();
+        }
+        if time >= last.time {
+            return last.to_transform();
+        }
+
+        let i = self.keyframes.iter().rposition(|k| k.time <= time).unwrap();
+        let (k0, k1) = (&self.keyframes[i], &self.keyframes[i + 1]);
+        let t = (time - k0.time) / (k1.time - k0.time);
+        Keyframe::new(
+            time,
+            k0.translation + t * (k1.translation - k0.translation),
+            k0.rotation.slerp(&k1.rotation, t),
+            k0.scale + t * (k1.scale - k0.scale),
+        )
+        .to_transform()
+    }
+
+    /// Box enclosing `bbox` at every pose along the motion.
+    ///
+    /// Each segment is sampled finely enough that the box corners' arcs stay
+    /// within the padding added for the rotation between two samples.
+    pub fn motion_bounds(&self, bbox: &Aabb) -> Aabb {
+        const STEPS: usize = 32;
+
+        let mut result = self.keyframes[0].to_transform().apply_aabb(bbox);
+        for pair in self.keyframes.windows(2) {
+            let (k0, k1) = (&pair[0], &pair[1]);
+
+            // Farthest any corner can get from the rotation center.
+            let mut radius: f64 = 0.0;
+            for i in 0..8 {
+                let corner = Vec3(
+                    if i & 1 == 0 { bbox.x.min } else { bbox.x.max },
+                    if i & 2 == 0 { bbox.y.min } else { bbox.y.max },
+                    if i & 4 == 0 { bbox.z.min } else { bbox.z.max },
+                );
+                radius = radius.max(corner.length());
+            }
+            let max_scale = [k0.scale, k1.scale]
+                .iter()
+                .map(|s| s.x().abs().max(s.y().abs()).max(s.z().abs()))
+                .fold(0.0, f64::max);
+            let step_angle = k0.rotation.angle_to(&k1.rotation) / STEPS as f64;
+            let padding = 2.0 * radius * max_scale * (1.0 - (step_angle / 2.0).cos());
+
+            for step in 1..=STEPS {
+                let time = k0.time + (k1.time - k0.time) * step as f64 / STEPS as f64;
+                let moved = self.interpolate(time).apply_aabb(bbox);
+                let padded = Aabb::new(
+                    moved.x.expand(padding),
+                    moved.y.expand(padding),
+                    moved.z.expand(padding),
+                );
+                result = Aabb::surrounding(&result, &padded);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use Vec3 as Point3;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    // Slides along x while turning a quarter turn around y and doubling in
+    // size, then holds still.
+    fn motion() -> AnimatedTransform {
+        let scale = Vec3(1.0, 1.0, 1.0);
+        AnimatedTransform::new(vec![
+            Keyframe::new(
+                1.0,
+                Vec3(4.0, 0.0, 0.0),
+                Quaternion::from_axis_angle(90.0, Vec3(0.0, 1.0, 0.0)),
+                2.0 * scale,
+            ),
+            Keyframe::new(0.0, Vec3(0.0, 0.0, 0.0), Quaternion::identity(), scale),
+            Keyframe::new(
+                2.0,
+                Vec3(4.0, 0.0, 0.0),
+                Quaternion::from_axis_angle(90.0, Vec3(0.0, 1.0, 0.0)),
+                2.0 * scale,
+            ),
+        ])
+    }
+
+    #[test]
+    fn keyframe_times_give_their_poses() {
+        let motion = motion();
+        let p = Point3(1.0, 0.0, 0.0);
+        assert_close(motion.interpolate(0.0).apply_point(&p), p);
+        assert_close(
+            motion.interpolate(1.0).apply_point(&p),
+            Point3(4.0, 0.0, -2.0),
+        );
+        // Outside the keyframes the first and last poses hold.
+        assert_close(motion.interpolate(-1.0).apply_point(&p), p);
+        assert_close(
+            motion.interpolate(3.0).apply_point(&p),
+            Point3(4.0, 0.0, -2.0),
+        );
+    }
+
+    #[test]
+    fn halfway_blends_every_part_of_the_pose() {
+        let p = Point3(1.0, 0.0, 0.0);
+        let angle = 45.0_f64.to_radians();
+        let expected = Point3(2.0, 0.0, 0.0) + 1.5 * Vec3(angle.cos(), 0.0, -angle.sin());
+        assert_close(motion().interpolate(0.5).apply_point(&p), expected);
+    }
+
+    #[test]
+    fn motion_bounds_hold_the_box_at_every_time() {
+        let motion = motion();
+        let bbox = Aabb::from_points(Point3(0.5, -1.0, -0.5), Point3(2.0, 1.0, 0.5));
+        let bounds = motion.motion_bounds(&bbox);
+        for step in 0..=1000 {
+            let moved = motion
+                .interpolate(2.0 * step as f64 / 1000.0)
+                .apply_aabb(&bbox);
+            for (outer, inner) in [
+                (bounds.x, moved.x),
+                (bounds.y, moved.y),
+                (bounds.z, moved.z),
+            ] {
+                assert!(outer.min <= inner.min + 1e-9 && inner.max <= outer.max + 1e-9);
+            }
+        }
+    }
+}
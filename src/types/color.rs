@@ -30,3 +30,18 @@ pub fn write_color(pixel_color: color, sample_per_pixel: i32, mut f: &File) {
 fn linear_to_gamma(linear_comp: f64) -> f64 {
     linear_comp.sqrt()
 }
+
+/// Perceived brightness of a linear sRGB color.
+pub fn luminance(c: &color) -> f64 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+/// Converts CIE XYZ to linear sRGB (D65 white).
+pub fn xyz_to_rgb(xyz: &color) -> color {
+    let (x, y, z) = (xyz.x(), xyz.y(), xyz.z());
+    Vec3(
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    )
+}
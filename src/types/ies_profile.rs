@@ -0,0 +1,250 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Most values a file may list in one table, far more than real ones have,
+/// so a bad count cannot ask for huge amounts of memory.
+const MAX_COUNT: usize = 1_000_000;
+
+/// Candela distribution of a luminaire read from an IES LM-63 photometric
+/// file. Only type C photometry is supported: vertical angles run from 0
+/// straight down to 180 straight up, horizontal angles around the vertical.
+pub struct IesProfile {
+    vertical: Vec<f64>,
+    horizontal: Vec<f64>,
+    /// One row of `vertical.len()` values per horizontal angle.
+    candela: Vec<f64>,
+    max_candela: f64,
+}
+
+impl IesProfile {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<IesProfile> {
+        IesProfile::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> io::Result<IesProfile> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        // Skip the free-form header up to the TILT line; the numbers follow it.
+        let mut lines = text.lines();
+        let tilt = loop {
+            match lines.next() {
+                Some(line) if line.trim_start().starts_with("TILT=") => {
+                    break line.trim_start()[5..].trim().to_string();
+                }
+                Some(_) => continue,
+                None => return Err(invalid("IES file has no TILT line")),
+            }
+        };
+        let mut numbers = Vec::new();
+        for token in lines.flat_map(|l| l.split(|c: char| c.is_whitespace() || c == ',')) {
+            if token.is_empty() {
+                continue;
+            }
+            let value = token
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| invalid("IES file holds a value that is not a number"))?;
+            numbers.push(value);
+        }
+        let mut numbers = numbers.into_iter();
+        let mut next = || {
+            numbers
+                .next()
+                .ok_or_else(|| invalid("IES file ends before its data does"))
+        };
+        let count = |value: f64| {
+            if value < 0.0 || value.fract() != 0.0 || value > MAX_COUNT as f64 {
+                return Err(invalid(
+                    "IES file holds a count that is not a sensible number",
+                ));
+            }
+            Ok(value as usize)
+        };
+
+        match tilt.as_str() {
+            "NONE" => {}
+            "INCLUDE" => {
+                // Lamp to luminaire geometry, then angle and multiplier pairs,
+                // which only matter for lamps that change output with tilt.
+                next()?;
+                let pairs = count(next()?)?;
+                for _ in 0..2 * pairs {
+                    next()?;
+                }
+            }
+            _ => return Err(invalid("IES tilt data in a separate file is not supported")),
+        }
+
+        let _lamps = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let n_vertical = count(next()?)?;
+        let n_horizontal = count(next()?)?;
+        let photometric_type = next()?;
+        let _units = next()?;
+        for _ in 0..3 {
+            // Width, length and height of the luminous opening.
+            next()?;
+        }
+        let ballast_factor = next()?;
+        let _file_generation_type = next()?;
+        let _input_watts = next()?;
+
+        if photometric_type != 1.0 {
+            return Err(invalid("only type C IES photometry is supported"));
+        }
+        if n_vertical == 0 || n_horizontal == 0 {
+            return Err(invalid("IES file has no angles"));
+        }
+        let n_candela = n_vertical
+            .checked_mul(n_horizontal)
+            .filter(|&n| n <= MAX_COUNT)
+            .ok_or_else(|| invalid("IES file has too many angles"))?;
+
+        let vertical = (0..n_vertical)
+            .map(|_| next())
+            .collect::<io::Result<Vec<_>>>()?;
+        let horizontal = (0..n_horizontal)
+            .map(|_| next())
+            .collect::<io::Result<Vec<_>>>()?;
+        // Interpolation divides by the gaps between neighbouring angles.
+        for angles in [&vertical, &horizontal] {
+            if angles.windows(2).any(|pair| pair[0] >= pair[1]) {
+                return Err(invalid("IES angles do not increase"));
+            }
+        }
+        let candela = (0..n_candela)
+            .map(|_| next().map(|c| c * multiplier * ballast_factor))
+            .collect::<io::Result<Vec<_>>>()?;
+        let max_candela = candela.iter().cloned().fold(0.0, f64::max);
+
+        Ok(IesProfile {
+            vertical,
+            horizontal,
+            candela,
+            max_candela,
+        })
+    }
+
+    pub fn max_candela(&self) -> f64 {
+        self.max_candela
+    }
+
+    /// Interpolated luminous intensity `vertical` degrees up from straight
+    /// down and `horizontal` degrees around, using the symmetry implied by
+    /// the file's last horizontal angle.
+    pub fn candela(&self, vertical: f64, horizontal: f64) -> f64 {
+        let last_horizontal = *self.horizontal.last().unwrap();
+        let mut h = horizontal.rem_euclid(360.0);
+        if last_horizontal <= 180.0 && h > 180.0 {
+            h = 360.0 - h;
+        }
+        if last_horizontal <= 90.0 && h > 90.0 {
+            h = 180.0 - h;
+        }
+
+        let Some((v0, v1, fv)) = bracket(&self.vertical, vertical) else {
+            return 0.0;
+        };
+        let (h0, h1, fh) = if last_horizontal <= 0.0 {
+            (0, 0, 0.0)
+        } else {
+            bracket(&self.horizontal, h).unwrap_or((0, 0, 0.0))
+        };
+
+        let n = self.vertical.len();
+        let at = |i: usize, j: usize| self.candela[j * n + i];
+        let lerp = |a: f64, b: f64, t: f64| a + t * (b - a);
+        lerp(
+            lerp(at(v0, h0), at(v1, h0), fv),
+            lerp(at(v0, h1), at(v1, h1), fv),
+            fh,
+        )
+    }
+}
+
+/// Indices of the sorted `angles` around `x` and how far `x` is between
+/// them, or `None` if `x` lies outside them.
+fn bracket(angles: &[f64], x: f64) -> Option<(usize, usize, f64)> {
+    if x < angles[0] || x > *angles.last().unwrap() {
+        return None;
+    }
+    let i = angles.partition_point(|&a| a <= x).clamp(1, angles.len()) - 1;
+    if i + 1 >= angles.len() {
+        return Some((i, i, 0.0));
+    }
+    let t = (x - angles[i]) / (angles[i + 1] - angles[i]);
+    Some((i, i + 1, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rotationally symmetric, brightest straight down and dark from 90
+    // degrees up.
+    const DOWNLIGHT: &str = "IESNA:LM-63-2002
+[TEST] Example
+TILT=NONE
+1 1000 2.0 4 1 1 2 0.1 0.1 0.0
+0.5 1.0 12
+0 30 60 90
+0
+1000 800 200 0
+";
+
+    fn with_counts(tilt: &str, n_vertical: &str, n_horizontal: &str) -> String {
+        format!("TILT={tilt}\n1 1000 1.0 {n_vertical} {n_horizontal} 1 2 0 0 0\n1 1 0\n")
+    }
+
+    #[test]
+    fn candela_interpolates_between_angles() {
+        let profile = IesProfile::parse(DOWNLIGHT).unwrap();
+        // The multiplier and ballast factor scale every value.
+        assert_eq!(profile.max_candela(), 1000.0);
+        assert_eq!(profile.candela(0.0, 0.0), 1000.0);
+        assert!((profile.candela(15.0, 0.0) - 900.0).abs() < 1e-9);
+        assert!((profile.candela(45.0, 123.0) - 500.0).abs() < 1e-9);
+        assert_eq!(profile.candela(120.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn tilt_data_is_skipped() {
+        let text = DOWNLIGHT.replace("TILT=NONE", "TILT=INCLUDE\n1\n2\n0 90\n1 1");
+        let profile = IesProfile::parse(&text).unwrap();
+        assert!((profile.candela(15.0, 0.0) - 900.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bad_files_are_rejected() {
+        let cases = [
+            ("no tilt", "1 2 3".to_string()),
+            ("tilt file", DOWNLIGHT.replace("TILT=NONE", "TILT=lamp.tlt")),
+            ("truncated", DOWNLIGHT.replace("200 0", "200")),
+            ("not a number", DOWNLIGHT.replace("800", "eight")),
+            ("nan", DOWNLIGHT.replace("800", "NaN")),
+            ("infinite", DOWNLIGHT.replace("800", "inf")),
+            (
+                "duplicate angles",
+                DOWNLIGHT.replace("0 30 60 90", "0 30 30 90"),
+            ),
+            (
+                "falling angles",
+                DOWNLIGHT.replace("0 30 60 90", "0 60 30 90"),
+            ),
+            ("no angles", with_counts("NONE", "0", "1")),
+            ("negative count", with_counts("NONE", "-4", "1")),
+            ("fractional count", with_counts("NONE", "2.5", "1")),
+            ("huge count", with_counts("NONE", "1e300", "1")),
+            ("huge product", with_counts("NONE", "100000", "100000")),
+            ("huge tilt", "TILT=INCLUDE\n1 1e19".to_string()),
+        ];
+        for (name, text) in cases {
+            let err = IesProfile::parse(&text).err();
+            assert!(err.is_some(), "{name}");
+            assert_eq!(err.unwrap().kind(), io::ErrorKind::InvalidData, "{name}");
+        }
+    }
+}
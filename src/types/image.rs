@@ -1,4 +1,5 @@
 use crate::types::color::write_color;
+use crate::types::spectrum::xyz_to_srgb;
 use crate::types::vec3::Vec3;
 use std::fs::File;
 use std::ops::AddAssign;
@@ -38,6 +39,16 @@ impl Image {
         self
     }
 
+    /// Converts pixels holding CIE XYZ, as spectral rendering produces, to
+    /// linear sRGB.
+    pub fn xyz_to_srgb(&mut self) {
+        for row in self.pixels.iter_mut() {
+            for pixel in row.iter_mut() {
+                *pixel = xyz_to_srgb(pixel);
+            }
+        }
+    }
+
     pub fn write(&self, samples_per_pixel: i32, f: &File) {
         for y in 0..self.height {
             for x in 0..self.width {
@@ -1,3 +1,4 @@
+#[derive(Clone, Copy)]
 pub struct Interval {
     pub min: f64,
     pub max: f64,
@@ -8,6 +9,14 @@ impl Interval {
         Interval { min, max }
     }
 
+    pub fn from_intervals(a: &Interval, b: &Interval) -> Interval {
+        // The tightest interval enclosing both inputs.
+        Interval {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
     pub fn empty() -> Interval {
         Interval {
             min: f64::INFINITY,
@@ -22,6 +31,10 @@ impl Interval {
         }
     }
 
+    pub fn size(&self) -> f64 {
+        self.max - self.min
+    }
+
     pub fn contains(&self, x: f64) -> bool {
         self.min <= x && x <= self.max
     }
@@ -0,0 +1,146 @@
+use std::f64::consts::PI;
+
+use super::aabb::Aabb;
+use super::transform::Transform;
+use super::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// Conservative summary of one or more lights for estimating how much they
+/// can contribute at a point: where they are, how much power they give off
+/// and a cone bounding the directions they emit in (as in pbrt-v4).
+///
+/// The surface normals lie within `theta_o` of `w`, and each point emits
+/// within `theta_e` of its normal.
+#[derive(Clone, Copy)]
+pub struct LightBounds {
+    pub bounds: Aabb,
+    pub w: Vec3,
+    pub phi: f64,
+    pub cos_theta_o: f64,
+    pub cos_theta_e: f64,
+    pub two_sided: bool,
+}
+
+impl LightBounds {
+    pub fn new(
+        bounds: Aabb,
+        w: Vec3,
+        phi: f64,
+        cos_theta_o: f64,
+        cos_theta_e: f64,
+        two_sided: bool,
+    ) -> LightBounds {
+        LightBounds {
+            bounds,
+            w: w.unit_vector(),
+            phi,
+            cos_theta_o,
+            cos_theta_e,
+            two_sided,
+        }
+    }
+
+    /// Bounds of a light that shines the same in every direction.
+    pub fn omni(bounds: Aabb, phi: f64) -> LightBounds {
+        LightBounds::new(bounds, Vec3(0.0, 1.0, 0.0), phi, -1.0, 0.0, false)
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3(
+            (self.bounds.x.min + self.bounds.x.max) / 2.0,
+            (self.bounds.y.min + self.bounds.y.max) / 2.0,
+            (self.bounds.z.min + self.bounds.z.max) / 2.0,
+        )
+    }
+
+    pub fn union(a: &LightBounds, b: &LightBounds) -> LightBounds {
+        let (w, cos_theta_o) = union_cones(&a.w, a.cos_theta_o, &b.w, b.cos_theta_o);
+        LightBounds {
+            bounds: Aabb::surrounding(&a.bounds, &b.bounds),
+            w,
+            phi: a.phi + b.phi,
+            cos_theta_o,
+            cos_theta_e: a.cos_theta_e.min(b.cos_theta_e),
+            two_sided: a.two_sided || b.two_sided,
+        }
+    }
+
+    /// Upper bound style estimate of the light arriving at `p`: the power
+    /// over the squared distance, scaled by the cosine of the smallest angle
+    /// between the emission cone and the direction to `p`.
+    pub fn importance(&self, p: &Point3) -> f64 {
+        let centroid = self.centroid();
+        let diagonal = Vec3(
+            self.bounds.x.size(),
+            self.bounds.y.size(),
+            self.bounds.z.size(),
+        );
+        let radius_squared = diagonal.length_squared() / 4.0;
+        let distance_squared = (*p - centroid).length_squared();
+        if distance_squared <= radius_squared {
+            // Inside the bounds light could come from anywhere. pbrt clamps
+            // the distance to half the diagonal's length (not squared), which
+            // strongly favors the node around `p` when it is large.
+            return self.phi / distance_squared.max(diagonal.length() / 2.0).max(1e-8);
+        }
+
+        let wi = (*p - centroid) / distance_squared.sqrt();
+        let mut cos_theta_w = self.w.dot(&wi);
+        if self.two_sided {
+            cos_theta_w = cos_theta_w.abs();
+        }
+        let sin_theta_w = safe_sqrt(1.0 - cos_theta_w * cos_theta_w);
+        // Half angle of the cone of directions the bounds cover from `p`.
+        let cos_theta_b = safe_sqrt(1.0 - radius_squared / distance_squared);
+        let sin_theta_b = safe_sqrt(1.0 - cos_theta_b * cos_theta_b);
+        let sin_theta_o = safe_sqrt(1.0 - self.cos_theta_o * self.cos_theta_o);
+
+        // theta' = max(0, theta_w - theta_o - theta_b)
+        let (sin_theta_x, cos_theta_x) =
+            sub_clamped(sin_theta_w, cos_theta_w, sin_theta_o, self.cos_theta_o);
+        let (_, cos_theta_p) = sub_clamped(sin_theta_x, cos_theta_x, sin_theta_b, cos_theta_b);
+        if cos_theta_p <= self.cos_theta_e {
+            return 0.0;
+        }
+        self.phi * cos_theta_p / distance_squared
+    }
+}
+
+/// Sine and cosine of `max(0, a - b)` from those of `a` and `b`.
+fn sub_clamped(sin_a: f64, cos_a: f64, sin_b: f64, cos_b: f64) -> (f64, f64) {
+    if cos_a > cos_b {
+        return (0.0, 1.0);
+    }
+    (sin_a * cos_b - cos_a * sin_b, cos_a * cos_b + sin_a * sin_b)
+}
+
+fn safe_sqrt(x: f64) -> f64 {
+    x.max(0.0).sqrt()
+}
+
+/// Smallest cone around both the cone with axis `wa` and the one with axis
+/// `wb`, as an axis and the cosine of its half angle.
+fn union_cones(wa: &Vec3, cos_a: f64, wb: &Vec3, cos_b: f64) -> (Vec3, f64) {
+    let theta_a = cos_a.clamp(-1.0, 1.0).acos();
+    let theta_b = cos_b.clamp(-1.0, 1.0).acos();
+    let theta_d = wa.dot(wb).clamp(-1.0, 1.0).acos();
+    if (theta_d + theta_b).min(PI) <= theta_a {
+        return (*wa, cos_a);
+    }
+    if (theta_d + theta_a).min(PI) <= theta_b {
+        return (*wb, cos_b);
+    }
+
+    let theta_o = (theta_a + theta_d + theta_b) / 2.0;
+    if theta_o >= PI {
+        return (*wa, -1.0);
+    }
+    // Swing a's axis towards b's so the new cone just touches both.
+    let axis = wa.cross(wb);
+    if axis.length_squared() == 0.0 {
+        return (*wa, -1.0);
+    }
+    let w = Transform::from_rotation((theta_o - theta_a).to_degrees(), axis).apply_vector(wa);
+    (w, theta_o.cos())
+}
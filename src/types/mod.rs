@@ -1,6 +1,15 @@
+pub mod aabb;
+pub mod animated_transform;
 pub mod color;
+pub mod ies_profile;
 pub mod image;
 pub mod interval;
+pub mod light_bounds;
+pub mod onb;
+pub mod quaternion;
 pub mod ray;
 pub mod render_task;
+pub mod spectrum;
+pub mod transform;
 pub mod vec3;
+pub mod voxel_grid;
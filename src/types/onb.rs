@@ -0,0 +1,56 @@
+use super::vec3::Vec3;
+
+/// Orthonormal basis with `w` along a given direction, used to build
+/// directions sampled around a normal or a ray.
+#[derive(Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn new(n: &Vec3) -> Onb {
+        let w = n.unit_vector();
+        let a = if w.x().abs() > 0.9 {
+            Vec3(0.0, 1.0, 0.0)
+        } else {
+            Vec3(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(&a).unit_vector();
+        let u = w.cross(&v);
+        Onb { u, v, w }
+    }
+
+    /// A basis around `n` with `u` along the part of `tangent` across it,
+    /// or an arbitrary one if `tangent` is (nearly) parallel to `n`.
+    pub fn from_tangent(n: &Vec3, tangent: &Vec3) -> Onb {
+        let w = n.unit_vector();
+        let u = *tangent - w * tangent.dot(&w);
+        if u.length_squared() <= 1e-12 * tangent.length_squared() {
+            return Onb::new(n);
+        }
+        let u = u.unit_vector();
+        // Same handedness as `new`.
+        let v = u.cross(&w);
+        Onb { u, v, w }
+    }
+
+    /// The basis turned over, so that `w` points the other way.
+    pub fn flipped(&self) -> Onb {
+        Onb {
+            u: self.u,
+            v: -self.v,
+            w: -self.w,
+        }
+    }
+
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        a.x() * self.u + a.y() * self.v + a.z() * self.w
+    }
+
+    /// The inverse of `local`: coordinates of the world space `a` in this basis.
+    pub fn to_local(self, a: &Vec3) -> Vec3 {
+        Vec3(a.dot(&self.u), a.dot(&self.v), a.dot(&self.w))
+    }
+}
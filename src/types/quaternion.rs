@@ -0,0 +1,148 @@
+use super::transform::Matrix4;
+use super::vec3::Vec3;
+
+/// Unit quaternion `w + v`, used to interpolate rotations between keyframes.
+#[derive(Copy, Clone, Debug)]
+pub struct Quaternion {
+    pub w: f64,
+    pub v: Vec3,
+}
+
+impl Quaternion {
+    pub fn identity() -> Quaternion {
+        Quaternion {
+            w: 1.0,
+            v: Vec3::zero_vec3(),
+        }
+    }
+
+    /// Rotation by `degrees` counter-clockwise around `axis`.
+    pub fn from_axis_angle(degrees: f64, axis: Vec3) -> Quaternion {
+        let (sin_half, cos_half) = (degrees.to_radians() / 2.0).sin_cos();
+        Quaternion {
+            w: cos_half,
+            v: axis.unit_vector() * sin_half,
+        }
+    }
+
+    pub fn dot(&self, other: &Quaternion) -> f64 {
+        self.w * other.w + self.v.dot(&other.v)
+    }
+
+    pub fn normalize(&self) -> Quaternion {
+        let len = self.dot(self).sqrt();
+        Quaternion {
+            w: self.w / len,
+            v: self.v / len,
+        }
+    }
+
+    /// Spherical linear interpolation, taking the shorter way around.
+    pub fn slerp(&self, other: &Quaternion, t: f64) -> Quaternion {
+        let mut cos_theta = self.dot(other);
+        let mut end = *other;
+        if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            end = Quaternion {
+                w: -end.w,
+                v: -end.v,
+            };
+        }
+
+        // Nearly parallel: fall back to a normalized lerp to avoid dividing by ~0.
+        if cos_theta > 0.9995 {
+            return Quaternion {
+                w: self.w + t * (end.w - self.w),
+                v: self.v + t * (end.v - self.v),
+            }
+            .normalize();
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+        Quaternion {
+            w: a * self.w + b * end.w,
+            v: a * self.v + b * end.v,
+        }
+    }
+
+    /// Angle in radians between the two rotations.
+    pub fn angle_to(&self, other: &Quaternion) -> f64 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+
+    pub fn to_matrix(self) -> Matrix4 {
+        let (x, y, z, w) = (self.v.x(), self.v.y(), self.v.z(), self.w);
+        Matrix4([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_same_rotation(a: &Quaternion, b: &Quaternion) {
+        assert!(a.angle_to(b) < 1e-6, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn slerp_hits_its_ends() {
+        let a = Quaternion::identity();
+        let b = Quaternion::from_axis_angle(120.0, Vec3(0.0, 1.0, 1.0));
+        assert_same_rotation(&a.slerp(&b, 0.0), &a);
+        assert_same_rotation(&a.slerp(&b, 1.0), &b);
+    }
+
+    #[test]
+    fn slerp_turns_at_a_constant_rate() {
+        let axis = Vec3(1.0, -2.0, 0.5);
+        let a = Quaternion::from_axis_angle(10.0, axis);
+        let b = Quaternion::from_axis_angle(130.0, axis);
+        for t in [0.1, 0.25, 0.5, 0.9] {
+            let expected = Quaternion::from_axis_angle(10.0 + 120.0 * t, axis);
+            let q = a.slerp(&b, t);
+            assert!((q.dot(&q) - 1.0).abs() < 1e-12);
+            assert_same_rotation(&q, &expected);
+        }
+    }
+
+    #[test]
+    fn slerp_takes_the_shorter_way() {
+        // 350 degrees one way is 10 the other, so halfway is -5 degrees.
+        let axis = Vec3(0.0, 0.0, 1.0);
+        let q = Quaternion::identity().slerp(&Quaternion::from_axis_angle(350.0, axis), 0.5);
+        assert_same_rotation(&q, &Quaternion::from_axis_angle(-5.0, axis));
+    }
+
+    #[test]
+    fn nearly_equal_rotations_stay_normalized() {
+        let axis = Vec3(0.3, 0.4, 0.5);
+        let a = Quaternion::from_axis_angle(20.0, axis);
+        let b = Quaternion::from_axis_angle(20.5, axis);
+        let q = a.slerp(&b, 0.5);
+        assert!((q.dot(&q) - 1.0).abs() < 1e-12);
+        assert_same_rotation(&q, &Quaternion::from_axis_angle(20.25, axis));
+    }
+}
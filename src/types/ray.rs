@@ -6,6 +6,9 @@ pub struct Ray {
     orig: Point3,
     dir: Vec3,
     tm: f64,
+    /// The hero wavelength in nm of the path the ray belongs to, when
+    /// rendering spectrally.
+    wavelength: Option<f64>,
 }
 
 impl Ray {
@@ -14,6 +17,7 @@ impl Ray {
             orig: origin,
             dir: direction,
             tm: 0.,
+            wavelength: None,
         }
     }
 
@@ -22,9 +26,15 @@ impl Ray {
             orig: origin,
             dir: direction,
             tm,
+            wavelength: None,
         }
     }
 
+    pub fn with_wavelength(mut self, wavelength: Option<f64>) -> Ray {
+        self.wavelength = wavelength;
+        self
+    }
+
     pub fn origin(&self) -> Point3 {
         self.orig
     }
@@ -37,6 +47,10 @@ impl Ray {
         self.tm
     }
 
+    pub fn wavelength(&self) -> Option<f64> {
+        self.wavelength
+    }
+
     pub fn at(&self, t: f64) -> Point3 {
         self.orig + self.dir * t
     }
@@ -0,0 +1,237 @@
+use super::color::xyz_to_rgb;
+use super::vec3::Vec3;
+
+use Vec3 as Color;
+
+/// Number of wavelengths a path carries in spectral mode, one per channel of
+/// the `Vec3` holding its spectral values.
+pub const WAVELENGTH_SAMPLES: usize = 3;
+
+/// Integral of the CIE Y matching function over wavelength in nm, the
+/// luminance of a spectrum that is one everywhere.
+const CIE_Y_INTEGRAL: f64 = 106.856895;
+
+/// Wavelengths in nm that a path is traced for in spectral mode. The first
+/// is the hero wavelength: it sets the path's direction wherever the
+/// wavelength matters, the others are evenly spaced in the sampling
+/// distribution and share the path as long as the directions agree ("Hero
+/// Wavelength Spectral Sampling", Wilkie et al. 2014).
+#[derive(Clone, Copy)]
+pub struct SampledWavelengths {
+    pub lambda: [f64; WAVELENGTH_SAMPLES],
+    pdf: [f64; WAVELENGTH_SAMPLES],
+}
+
+impl SampledWavelengths {
+    /// Wavelengths distributed roughly like the eye's sensitivity, from a
+    /// uniform `u` in [0,1).
+    pub fn sample_visible(u: f64) -> SampledWavelengths {
+        let mut lambda = [0.0; WAVELENGTH_SAMPLES];
+        let mut pdf = [0.0; WAVELENGTH_SAMPLES];
+        for i in 0..WAVELENGTH_SAMPLES {
+            let up = (u + i as f64 / WAVELENGTH_SAMPLES as f64).fract();
+            lambda[i] = 538.0 - 138.888889 * (0.85691062 - 1.82750197 * up).atanh();
+            pdf[i] = visible_wavelengths_pdf(lambda[i]);
+        }
+        SampledWavelengths { lambda, pdf }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    /// Drops every wavelength but the hero, after a bounce that sent each
+    /// one in its own direction.
+    pub fn terminate_secondary(&mut self) {
+        if self.pdf[1..].iter().all(|&pdf| pdf == 0.0) {
+            return;
+        }
+        self.pdf[0] /= WAVELENGTH_SAMPLES as f64;
+        self.pdf[1..].iter_mut().for_each(|pdf| *pdf = 0.0);
+    }
+
+    /// The same spectral values at these wavelengths, with `rgb` upsampled
+    /// to a smooth spectrum.
+    pub fn upsample(&self, rgb: &Color) -> Vec3 {
+        Vec3(
+            rgb_to_spectrum(rgb, self.lambda[0]),
+            rgb_to_spectrum(rgb, self.lambda[1]),
+            rgb_to_spectrum(rgb, self.lambda[2]),
+        )
+    }
+
+    /// CIE XYZ estimate of a spectrum from its `values` at these wavelengths,
+    /// scaled so that a spectrum of one everywhere has a Y of one.
+    pub fn to_xyz(self, values: &Vec3) -> Color {
+        let values = [values.x(), values.y(), values.z()];
+        let mut xyz = Color(0.0, 0.0, 0.0);
+        for ((lambda, pdf), value) in self.lambda.iter().zip(self.pdf).zip(values) {
+            if pdf > 0.0 {
+                xyz += cie_xyz(*lambda) * (value / pdf);
+            }
+        }
+        xyz / (WAVELENGTH_SAMPLES as f64 * CIE_Y_INTEGRAL)
+    }
+}
+
+/// Converts CIE XYZ to linear sRGB, white balancing from equal energy to D65
+/// by scaling X and Z so that a flat spectrum comes out neutral.
+pub fn xyz_to_srgb(xyz: &Color) -> Color {
+    xyz_to_rgb(&Color(xyz.x() * 0.95047, xyz.y(), xyz.z() * 1.08883))
+}
+
+/// Linear sRGB color of the reflectance `f` gives for each wavelength in nm,
+/// integrated over the visible range so that a reflectance of one is white.
+pub fn reflectance_to_rgb(f: impl Fn(f64) -> f64) -> Color {
+    const STEPS: usize = 16;
+    let mut xyz = Color(0.0, 0.0, 0.0);
+    let mut white = Color(0.0, 0.0, 0.0);
+    for i in 0..STEPS {
+        let lambda = 380.0 + (i as f64 + 0.5) * (720.0 - 380.0) / STEPS as f64;
+        let cmf = cie_xyz(lambda);
+        xyz += cmf * f(lambda);
+        white += cmf;
+    }
+    let rgb = xyz_to_srgb(&Color(
+        xyz.x() / white.x(),
+        xyz.y() / white.y(),
+        xyz.z() / white.z(),
+    ));
+    Color(rgb.x().max(0.0), rgb.y().max(0.0), rgb.z().max(0.0))
+}
+
+/// Density of `SampledWavelengths::sample_visible`'s wavelengths, a fit to
+/// the luminance response that covers 360 to 830 nm.
+fn visible_wavelengths_pdf(lambda: f64) -> f64 {
+    if !(360.0..=830.0).contains(&lambda) {
+        return 0.0;
+    }
+    0.0039398042 / (0.0072 * (lambda - 538.0)).cosh().powi(2)
+}
+
+/// The CIE 1931 color matching functions at `lambda` in nm, from the
+/// piecewise Gaussian fit of Wyman, Sloan and Shirley (2013).
+pub fn cie_xyz(lambda: f64) -> Color {
+    let g = |mu: f64, sigma_below: f64, sigma_above: f64| {
+        let sigma = if lambda < mu {
+            sigma_below
+        } else {
+            sigma_above
+        };
+        let t = (lambda - mu) / sigma;
+        (-0.5 * t * t).exp()
+    };
+    Color(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+// Smits' basis spectra over ten equal bins from 380 to 720 nm.
+const SMITS_WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Value at `lambda` of a smooth spectrum whose color is `rgb`, built from
+/// white and the most saturated spectra it needs ("An RGB-to-Spectrum
+/// Conversion for Reflectances", Smits 1999). Negative channels are treated
+/// as zero.
+pub fn rgb_to_spectrum(rgb: &Color, lambda: f64) -> f64 {
+    let bin = (((lambda - 380.0) / 34.0).floor().max(0.0) as usize).min(9);
+    let (r, g, b) = (rgb.x().max(0.0), rgb.y().max(0.0), rgb.z().max(0.0));
+    if r <= g && r <= b {
+        let rest = if g <= b {
+            (g - r) * SMITS_CYAN[bin] + (b - g) * SMITS_BLUE[bin]
+        } else {
+            (b - r) * SMITS_CYAN[bin] + (g - b) * SMITS_GREEN[bin]
+        };
+        r * SMITS_WHITE[bin] + rest
+    } else if g <= r && g <= b {
+        let rest = if r <= b {
+            (r - g) * SMITS_MAGENTA[bin] + (b - r) * SMITS_BLUE[bin]
+        } else {
+            (b - g) * SMITS_MAGENTA[bin] + (r - b) * SMITS_RED[bin]
+        };
+        g * SMITS_WHITE[bin] + rest
+    } else {
+        let rest = if r <= g {
+            (r - b) * SMITS_YELLOW[bin] + (g - r) * SMITS_GREEN[bin]
+        } else {
+            (g - b) * SMITS_YELLOW[bin] + (r - g) * SMITS_RED[bin]
+        };
+        b * SMITS_WHITE[bin] + rest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hero wavelengths for `n` evenly spaced values of `u`.
+    fn heroes(n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| SampledWavelengths::sample_visible((i as f64 + 0.5) / n as f64).hero())
+            .collect()
+    }
+
+    #[test]
+    fn visible_pdf_integrates_to_one() {
+        let steps = 47_000;
+        let step = (830.0 - 360.0) / steps as f64;
+        let integral: f64 = (0..steps)
+            .map(|i| visible_wavelengths_pdf(360.0 + (i as f64 + 0.5) * step) * step)
+            .sum();
+        assert!((integral - 1.0).abs() < 1e-6, "{integral}");
+    }
+
+    #[test]
+    fn wavelengths_follow_their_pdf() {
+        let heroes = heroes(100_000);
+        assert!(heroes.iter().all(|lambda| (360.0..=830.0).contains(lambda)));
+        for below in [420.0, 500.0, 538.0, 600.0, 700.0] {
+            let fraction = heroes.iter().filter(|&&lambda| lambda < below).count() as f64
+                / heroes.len() as f64;
+            let steps = 10_000;
+            let step = (below - 360.0) / steps as f64;
+            let expected: f64 = (0..steps)
+                .map(|i| visible_wavelengths_pdf(360.0 + (i as f64 + 0.5) * step) * step)
+                .sum();
+            assert!(
+                (fraction - expected).abs() < 2e-3,
+                "{below}: {fraction} vs {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn flat_spectrum_has_unit_luminance() {
+        let n = 100_000;
+        let y: f64 = (0..n)
+            .map(|i| {
+                let wavelengths = SampledWavelengths::sample_visible((i as f64 + 0.5) / n as f64);
+                wavelengths.to_xyz(&Vec3(1.0, 1.0, 1.0)).y()
+            })
+            .sum::<f64>()
+            / n as f64;
+        assert!((y - 1.0).abs() < 2e-3, "{y}");
+    }
+}
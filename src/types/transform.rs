@@ -0,0 +1,356 @@
+use std::ops;
+
+use super::aabb::Aabb;
+use super::quaternion::Quaternion;
+use super::ray::Ray;
+use super::vec3::Vec3;
+
+use Vec3 as Point3;
+
+/// Row-major 4x4 matrix acting on column vectors.
+#[derive(Copy, Clone, Debug)]
+pub struct Matrix4(pub [[f64; 4]; 4]);
+
+impl Matrix4 {
+    pub fn identity() -> Matrix4 {
+        Matrix4([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Matrix4 {
+        let mut t = [[0.0; 4]; 4];
+        for (i, row) in t.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.0[j][i];
+            }
+        }
+        Matrix4(t)
+    }
+
+    /// Gauss-Jordan elimination with partial pivoting; `None` if singular.
+    pub fn inverse(&self) -> Option<Matrix4> {
+        let mut a = self.0;
+        let mut inv = Matrix4::identity().0;
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().partial_cmp(&a[j][col].abs()).unwrap())
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+        Some(Matrix4(inv))
+    }
+}
+
+impl ops::Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+    fn mul(self, rhs: Matrix4) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.0[i][k] * rhs.0[k][j]).sum();
+            }
+        }
+        Matrix4(m)
+    }
+}
+
+/// An affine transform together with its inverse, so rays can be taken into
+/// object space and hit points and normals brought back without re-inverting.
+///
+/// The builder methods compose left to right: `Transform::identity().scale(..)
+/// .rotate_y(..).translate(..)` scales first, then rotates, then translates.
+#[derive(Copy, Clone, Debug)]
+pub struct Transform {
+    m: Matrix4,
+    m_inv: Matrix4,
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            m: Matrix4::identity(),
+            m_inv: Matrix4::identity(),
+        }
+    }
+
+    /// Panics if `m` is not invertible.
+    pub fn new(m: Matrix4) -> Transform {
+        Transform {
+            m,
+            m_inv: m.inverse().expect("transform matrix is singular"),
+        }
+    }
+
+    pub fn from_translation(delta: Vec3) -> Transform {
+        let m = Matrix4([
+            [1.0, 0.0, 0.0, delta.x()],
+            [0.0, 1.0, 0.0, delta.y()],
+            [0.0, 0.0, 1.0, delta.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let m_inv = Matrix4([
+            [1.0, 0.0, 0.0, -delta.x()],
+            [0.0, 1.0, 0.0, -delta.y()],
+            [0.0, 0.0, 1.0, -delta.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        Transform { m, m_inv }
+    }
+
+    pub fn from_scale(factors: Vec3) -> Transform {
+        let m = Matrix4([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let m_inv = Matrix4([
+            [1.0 / factors.x(), 0.0, 0.0, 0.0],
+            [0.0, 1.0 / factors.y(), 0.0, 0.0],
+            [0.0, 0.0, 1.0 / factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        Transform { m, m_inv }
+    }
+
+    /// Rotation by `degrees` counter-clockwise around `axis` (Rodrigues' formula).
+    pub fn from_rotation(degrees: f64, axis: Vec3) -> Transform {
+        let a = axis.unit_vector();
+        let (sin_theta, cos_theta) = degrees.to_radians().sin_cos();
+        let one_minus_cos = 1.0 - cos_theta;
+        let m = Matrix4([
+            [
+                a.x() * a.x() * one_minus_cos + cos_theta,
+                a.x() * a.y() * one_minus_cos - a.z() * sin_theta,
+                a.x() * a.z() * one_minus_cos + a.y() * sin_theta,
+                0.0,
+            ],
+            [
+                a.y() * a.x() * one_minus_cos + a.z() * sin_theta,
+                a.y() * a.y() * one_minus_cos + cos_theta,
+                a.y() * a.z() * one_minus_cos - a.x() * sin_theta,
+                0.0,
+            ],
+            [
+                a.z() * a.x() * one_minus_cos - a.y() * sin_theta,
+                a.z() * a.y() * one_minus_cos + a.x() * sin_theta,
+                a.z() * a.z() * one_minus_cos + cos_theta,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        // Rotations are orthogonal, so the inverse is just the transpose.
+        Transform {
+            m,
+            m_inv: m.transpose(),
+        }
+    }
+
+    pub fn from_quaternion(q: &Quaternion) -> Transform {
+        let m = q.to_matrix();
+        Transform {
+            m,
+            m_inv: m.transpose(),
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        Transform {
+            m: self.m_inv,
+            m_inv: self.m,
+        }
+    }
+
+    /// Applies `next` after this transform.
+    pub fn then(&self, next: &Transform) -> Transform {
+        Transform {
+            m: next.m * self.m,
+            m_inv: self.m_inv * next.m_inv,
+        }
+    }
+
+    pub fn translate(&self, delta: Vec3) -> Transform {
+        self.then(&Transform::from_translation(delta))
+    }
+
+    pub fn scale(&self, factors: Vec3) -> Transform {
+        self.then(&Transform::from_scale(factors))
+    }
+
+    pub fn uniform_scale(&self, factor: f64) -> Transform {
+        self.scale(Vec3(factor, factor, factor))
+    }
+
+    pub fn rotate(&self, degrees: f64, axis: Vec3) -> Transform {
+        self.then(&Transform::from_rotation(degrees, axis))
+    }
+
+    pub fn rotate_x(&self, degrees: f64) -> Transform {
+        self.rotate(degrees, Vec3(1.0, 0.0, 0.0))
+    }
+
+    pub fn rotate_y(&self, degrees: f64) -> Transform {
+        self.rotate(degrees, Vec3(0.0, 1.0, 0.0))
+    }
+
+    pub fn rotate_z(&self, degrees: f64) -> Transform {
+        self.rotate(degrees, Vec3(0.0, 0.0, 1.0))
+    }
+
+    pub fn apply_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m.0;
+        let x = m[0][0] * p.x() + m[0][1] * p.y() + m[0][2] * p.z() + m[0][3];
+        let y = m[1][0] * p.x() + m[1][1] * p.y() + m[1][2] * p.z() + m[1][3];
+        let z = m[2][0] * p.x() + m[2][1] * p.y() + m[2][2] * p.z() + m[2][3];
+        let w = m[3][0] * p.x() + m[3][1] * p.y() + m[3][2] * p.z() + m[3][3];
+        if w == 1.0 {
+            Point3(x, y, z)
+        } else {
+            Point3(x, y, z) / w
+        }
+    }
+
+    pub fn apply_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m.0;
+        Vec3(
+            m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+            m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+            m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+        )
+    }
+
+    /// Normals transform by the inverse transpose to stay perpendicular to
+    /// the surface. The result is not normalized.
+    pub fn apply_normal(&self, n: &Vec3) -> Vec3 {
+        let mi = &self.m_inv.0;
+        Vec3(
+            mi[0][0] * n.x() + mi[1][0] * n.y() + mi[2][0] * n.z(),
+            mi[0][1] * n.x() + mi[1][1] * n.y() + mi[2][1] * n.z(),
+            mi[0][2] * n.x() + mi[1][2] * n.y() + mi[2][2] * n.z(),
+        )
+    }
+
+    /// The direction is left unnormalized so hit distances agree in both spaces.
+    pub fn apply_ray(&self, r: &Ray) -> Ray {
+        Ray::new_with_tm(
+            self.apply_point(&r.origin()),
+            self.apply_vector(&r.dir()),
+            r.time(),
+        )
+    }
+
+    /// Box around all eight transformed corners of `bbox`.
+    pub fn apply_aabb(&self, bbox: &Aabb) -> Aabb {
+        let mut result = Aabb::empty();
+        for i in 0..8 {
+            let corner = Point3(
+                if i & 1 == 0 { bbox.x.min } else { bbox.x.max },
+                if i & 2 == 0 { bbox.y.min } else { bbox.y.max },
+                if i & 4 == 0 { bbox.z.min } else { bbox.z.max },
+            );
+            let p = self.apply_point(&corner);
+            result = Aabb::surrounding(&result, &Aabb::from_points(p, p));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    fn sample_transform() -> Transform {
+        Transform::identity()
+            .scale(Vec3(2.0, 0.5, 3.0))
+            .rotate(37.0, Vec3(1.0, 2.0, -0.5))
+            .translate(Vec3(-4.0, 1.0, 2.5))
+    }
+
+    #[test]
+    fn matrix_inverse_undoes_the_matrix() {
+        let m = Matrix4([
+            [2.0, 1.0, 0.0, 3.0],
+            [0.0, 1.0, 4.0, -1.0],
+            [1.0, 0.0, 1.0, 2.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let product = m * m.inverse().unwrap();
+        for (i, row) in product.0.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((value - expected).abs() < 1e-12);
+            }
+        }
+        assert!(Matrix4([[0.0; 4]; 4]).inverse().is_none());
+    }
+
+    #[test]
+    fn inverse_round_trips_points_and_vectors() {
+        let t = sample_transform();
+        let p = Point3(0.3, -1.2, 5.0);
+        assert_close(t.inverse().apply_point(&t.apply_point(&p)), p);
+        assert_close(t.inverse().apply_vector(&t.apply_vector(&p)), p);
+    }
+
+    #[test]
+    fn builders_agree_with_a_general_matrix() {
+        let t = sample_transform();
+        let general = Transform::new(t.m);
+        let p = Point3(1.0, 2.0, 3.0);
+        assert_close(
+            general.inverse().apply_point(&p),
+            t.inverse().apply_point(&p),
+        );
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_to_the_surface() {
+        // Two tangents of a plane and its normal, sheared along with it.
+        let t = sample_transform().then(&Transform::new(Matrix4([
+            [1.0, 0.7, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])));
+        let (a, b) = (Vec3(1.0, 0.0, 1.0), Vec3(0.0, 1.0, -2.0));
+        let n = t.apply_normal(&a.cross(&b));
+        assert!(n.dot(&t.apply_vector(&a)).abs() < 1e-9);
+        assert!(n.dot(&t.apply_vector(&b)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rays_keep_their_hit_distances() {
+        let t = sample_transform();
+        let r = Ray::new(Point3(1.0, 1.0, 1.0), Vec3(0.0, -2.0, 1.0));
+        let moved = t.apply_ray(&r);
+        assert_close(moved.at(0.8), t.apply_point(&r.at(0.8)));
+    }
+}
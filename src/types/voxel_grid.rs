@@ -0,0 +1,197 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+use super::vec3::Vec3;
+use crate::perlin::Perlin;
+
+use Vec3 as Point3;
+
+/// Scalar values sampled on a regular 3D lattice over the unit cube, with x
+/// varying fastest in `data`.
+pub struct VoxelGrid {
+    nx: usize,
+    ny: usize,
+    nz: usize,
+    data: Vec<f64>,
+    max_value: f64,
+}
+
+impl VoxelGrid {
+    /// Panics if any dimension is zero or `data` does not hold `nx * ny * nz`
+    /// values.
+    pub fn new(nx: usize, ny: usize, nz: usize, data: Vec<f64>) -> VoxelGrid {
+        assert!(nx > 0 && ny > 0 && nz > 0, "voxel grid is empty");
+        assert_eq!(data.len(), nx * ny * nz, "voxel grid size mismatch");
+        let max_value = data.iter().cloned().fold(0.0, f64::max);
+        VoxelGrid {
+            nx,
+            ny,
+            nz,
+            data,
+            max_value,
+        }
+    }
+
+    /// Fills the grid by evaluating `f` at each voxel's center in [0,1]^3.
+    pub fn from_fn(nx: usize, ny: usize, nz: usize, f: impl Fn(&Point3) -> f64) -> VoxelGrid {
+        let mut data = Vec::with_capacity(nx * ny * nz);
+        for k in 0..nz {
+            for j in 0..ny {
+                for i in 0..nx {
+                    let p = Point3(
+                        (i as f64 + 0.5) / nx as f64,
+                        (j as f64 + 0.5) / ny as f64,
+                        (k as f64 + 0.5) / nz as f64,
+                    );
+                    data.push(f(&p));
+                }
+            }
+        }
+        VoxelGrid::new(nx, ny, nz, data)
+    }
+
+    /// Cloud-like density from Perlin turbulence, `frequency` cells of noise
+    /// across the grid, fading out towards the edges of the cube.
+    pub fn from_noise(n: usize, frequency: f64) -> VoxelGrid {
+        let noise = Perlin::new();
+        VoxelGrid::from_fn(n, n, n, |p| {
+            let falloff = 1.0 - ((*p - Point3(0.5, 0.5, 0.5)).length() * 2.0).min(1.0);
+            noise.turb(&(*p * frequency), 7) * falloff
+        })
+    }
+
+    /// Reads a raw grid: three little-endian `u32` dimensions followed by
+    /// `nx * ny * nz` little-endian `f32` values, x varying fastest.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<VoxelGrid> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        if bytes.len() < 12 {
+            return Err(invalid("voxel grid header is truncated"));
+        }
+        let dim =
+            |i: usize| u32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap()) as usize;
+        let (nx, ny, nz) = (dim(0), dim(1), dim(2));
+        if nx == 0 || ny == 0 || nz == 0 {
+            return Err(invalid("voxel grid has a zero dimension"));
+        }
+        let size = nx
+            .checked_mul(ny)
+            .and_then(|n| n.checked_mul(nz))
+            .and_then(|n| n.checked_mul(4))
+            .ok_or_else(|| invalid("voxel grid dimensions are too large"))?;
+        let body = &bytes[12..];
+        if body.len() != size {
+            return Err(invalid("voxel grid data does not match its dimensions"));
+        }
+
+        let data = body
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes(c.try_into().unwrap()) as f64)
+            .collect();
+        Ok(VoxelGrid::new(nx, ny, nz, data))
+    }
+
+    /// Writes the grid in the format `load` reads, with values rounded to
+    /// `f32`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(12 + 4 * self.data.len());
+        for n in [self.nx, self.ny, self.nz] {
+            let n = u32::try_from(n).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "voxel grid is too large")
+            })?;
+            bytes.extend(n.to_le_bytes());
+        }
+        for value in self.data.iter() {
+            bytes.extend((*value as f32).to_le_bytes());
+        }
+        fs::write(path, bytes)
+    }
+
+    pub fn max_value(&self) -> f64 {
+        self.max_value
+    }
+
+    /// Trilinearly interpolated value at `p` in [0,1]^3, clamped at the edges.
+    pub fn lookup(&self, p: &Point3) -> f64 {
+        let coord = |x: f64, n: usize| {
+            let g = (x * n as f64 - 0.5).clamp(0.0, (n - 1) as f64);
+            let i = (g.floor() as usize).min(n.saturating_sub(2));
+            (i, (i + 1).min(n - 1), g - i as f64)
+        };
+        let (x0, x1, fx) = coord(p.x(), self.nx);
+        let (y0, y1, fy) = coord(p.y(), self.ny);
+        let (z0, z1, fz) = coord(p.z(), self.nz);
+
+        let at = |i: usize, j: usize, k: usize| self.data[(k * self.ny + j) * self.nx + i];
+        let lerp = |a: f64, b: f64, t: f64| a + t * (b - a);
+        lerp(
+            lerp(
+                lerp(at(x0, y0, z0), at(x1, y0, z0), fx),
+                lerp(at(x0, y1, z0), at(x1, y1, z0), fx),
+                fy,
+            ),
+            lerp(
+                lerp(at(x0, y0, z1), at(x1, y0, z1), fx),
+                lerp(at(x0, y1, z1), at(x1, y1, z1), fx),
+                fy,
+            ),
+            fz,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `words` as a grid file named `name` in the temporary directory.
+    fn grid_file(name: &str, words: &[[u8; 4]]) -> String {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, words.concat()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn load_reads_values_x_fastest() {
+        let mut words = vec![2u32.to_le_bytes(), 1u32.to_le_bytes(), 1u32.to_le_bytes()];
+        words.extend([0.0f32, 1.0].map(f32::to_le_bytes));
+        let grid = VoxelGrid::load(grid_file("voxel_grid_valid.vol", &words)).unwrap();
+        assert_eq!(grid.max_value(), 1.0);
+        assert_eq!(grid.lookup(&Point3(0.25, 0.5, 0.5)), 0.0);
+        assert_eq!(grid.lookup(&Point3(0.5, 0.5, 0.5)), 0.5);
+        assert_eq!(grid.lookup(&Point3(0.75, 0.5, 0.5)), 1.0);
+    }
+
+    #[test]
+    fn saved_grids_load_back() {
+        let grid = VoxelGrid::from_fn(3, 2, 4, |p| p.x() + 10.0 * p.y() + 100.0 * p.z());
+        let path = std::env::temp_dir().join("voxel_grid_saved.vol");
+        grid.save(&path).unwrap();
+        let loaded = VoxelGrid::load(&path).unwrap();
+        assert_eq!((loaded.nx, loaded.ny, loaded.nz), (3, 2, 4));
+        for (a, b) in grid.data.iter().zip(loaded.data.iter()) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn load_rejects_bad_headers() {
+        let cases = [
+            ("voxel_grid_truncated.vol", vec![1u32, 1]),
+            ("voxel_grid_zero.vol", vec![0, 4, 4]),
+            (
+                "voxel_grid_overflow.vol",
+                vec![u32::MAX, u32::MAX, u32::MAX],
+            ),
+            ("voxel_grid_short.vol", vec![2, 2, 2, 0]),
+        ];
+        for (name, header) in cases {
+            let words: Vec<[u8; 4]> = header.into_iter().map(u32::to_le_bytes).collect();
+            let err = VoxelGrid::load(grid_file(name, &words)).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{name}");
+        }
+    }
+}